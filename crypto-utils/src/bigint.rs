@@ -0,0 +1,26 @@
+use num_bigint::{BigInt, BigUint, Sign};
+
+// Parses a decimal or 0x-prefixed hexadecimal integer of any length, with
+// underscores allowed between digits, e.g. 0xffff_ffff or 1_000_000
+pub fn parse_big(val: &str) -> Option<BigUint> {
+    let val = val.trim();
+    let (digits, radix) = match val.strip_prefix("0x").or_else(|| val.strip_prefix("0X")) {
+        Some(hex) => (hex, 16),
+        None => (val, 10),
+    };
+    if digits.is_empty() || digits.starts_with('_') || digits.ends_with('_')
+        || !digits.chars().all(|c| c == '_' || c.is_digit(radix)) {
+        return None;
+    }
+    let digits: String = digits.chars().filter(|&c| c != '_').collect();
+    BigUint::parse_bytes(digits.as_bytes(), radix)
+}
+
+// Like parse_big, with an optional leading minus sign
+pub fn parse_bigint(val: &str) -> Option<BigInt> {
+    let val = val.trim();
+    match val.strip_prefix('-') {
+        Some(magnitude) => parse_big(magnitude).map(|n| BigInt::from_biguint(Sign::Minus, n)),
+        None => parse_big(val).map(|n| BigInt::from_biguint(Sign::Plus, n)),
+    }
+}
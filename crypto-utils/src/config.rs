@@ -0,0 +1,187 @@
+use std::env;
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::process;
+
+use clap::error::ErrorKind;
+use clap::{value_parser, Arg, Command, CommandFactory, FromArgMatches};
+use toml::{Table, Value};
+
+use crate::exit;
+
+// The config file holds a table of defaults per tool, named like the
+// commands of stanford-crypto, whose keys are long options:
+//
+//     [file-auth]
+//     block-size = "4K"
+//
+//     [dlog]
+//     threads = 8
+//     max-memory = "2G"
+//
+// Each value becomes the default of its option, so a flag on the command
+// line still overrides it, and --help shows it.
+
+// Without --config, $XDG_CONFIG_HOME/stanford-crypto/config.toml or else
+// ~/.config/stanford-crypto/config.toml, if it exists
+fn default_path() -> Option<PathBuf> {
+    let dir = env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()).map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(dir.join("stanford-crypto").join("config.toml"))
+}
+
+// The command of a tool with the --config option, which is read before
+// the other options and so isn't part of their Args
+pub fn command<T: CommandFactory>() -> Command {
+    T::command().arg(Arg::new("config").long("config").value_name("FILE").global(true)
+        .value_parser(value_parser!(PathBuf))
+        .help("read the defaults of options from FILE instead of \
+               ~/.config/stanford-crypto/config.toml"))
+}
+
+// The FILE of --config FILE or --config=FILE, before any "--"
+fn config_path(args: &[OsString]) -> Option<PathBuf> {
+    let mut args = args.iter().skip(1).take_while(|arg| *arg != "--");
+    while let Some(arg) = args.next() {
+        if arg == "--config" {
+            return args.next().map(PathBuf::from);
+        }
+        if let Some(path) = strip_config(arg) {
+            return Some(path);
+        }
+    }
+    None
+}
+
+// The FILE of --config=FILE. Elsewhere than on Unix, FILE must be UTF-8
+// this way, but not with --config FILE.
+#[cfg(unix)]
+fn strip_config(arg: &OsStr) -> Option<PathBuf> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = arg.as_bytes().strip_prefix(b"--config=")?;
+    Some(PathBuf::from(OsStr::from_bytes(path)))
+}
+
+#[cfg(not(unix))]
+fn strip_config(arg: &OsStr) -> Option<PathBuf> {
+    arg.to_str()?.strip_prefix("--config=").map(PathBuf::from)
+}
+
+// Exits like clap on bad input, but with the code of the exit module
+fn fail(command: &mut Command, code: u8, message: String) -> ! {
+    let _ = command.error(ErrorKind::InvalidValue, message).print();
+    process::exit(code.into())
+}
+
+// The config file and its tables, None if there is none
+fn load(command: &mut Command, args: &[OsString]) -> Option<(PathBuf, Table)> {
+    let (path, given) = match config_path(args) {
+        Some(path) => (path, true),
+        None => (default_path()?, false),
+    };
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if !given && e.kind() == io::ErrorKind::NotFound => return None,
+        Err(e) => fail(command, exit::IO, format!("can't read {}: {}", path.display(), e)),
+    };
+    match text.parse::<Table>() {
+        Ok(table) => Some((path, table)),
+        Err(e) => fail(command, exit::USAGE, format!("invalid {}: {}", path.display(), e)),
+    }
+}
+
+fn to_string(key: &str, value: &Value) -> Result<String, String> {
+    match value {
+        Value::String(s) => Ok(s.clone()),
+        Value::Integer(_) | Value::Float(_) | Value::Boolean(_) => Ok(value.to_string()),
+        _ => Err(format!("--{} can't be a {}", key, value.type_str())),
+    }
+}
+
+// Sets the default of --key in command and its subcommands, returning
+// whether any of them has it
+fn set_default(mut command: Command, key: &str, values: &[String]) -> (Command, bool) {
+    let id = command.get_arguments()
+        .find(|arg| arg.get_long() == Some(key))
+        .map(|arg| arg.get_id().clone());
+    let mut found = id.is_some();
+    if let Some(id) = id {
+        command = command.mut_arg(id, |arg| arg.default_values(values.to_vec()));
+    }
+    let names: Vec<String> = command.get_subcommands().map(|sub| sub.get_name().to_string()).collect();
+    for name in names {
+        command = command.mut_subcommand(name, |sub| {
+            let (sub, has) = set_default(sub, key, values);
+            found |= has;
+            sub
+        });
+    }
+    (command, found)
+}
+
+// Sets the defaults of a section, an array giving several values
+fn defaults(mut command: Command, section: &str, table: &Table) -> Result<Command, String> {
+    for (key, value) in table {
+        let values = match value {
+            Value::Array(values) => values.iter().map(|value| to_string(key, value)).collect(),
+            value => to_string(key, value).map(|value| vec![value]),
+        }?;
+        let (with_default, found) = set_default(command, key, &values);
+        if !found {
+            return Err(format!("{} has no option --{}", section, key));
+        }
+        command = with_default;
+    }
+    Ok(command)
+}
+
+fn parse_with<T: FromArgMatches>(mut command: Command, args: Vec<OsString>) -> T {
+    let matches = command.try_get_matches_from_mut(args).unwrap_or_else(|e| e.exit());
+    T::from_arg_matches(&matches).unwrap_or_else(|e| e.format(&mut command).exit())
+}
+
+// Parses the arguments of the tool whose defaults are in the given section
+pub fn parse<T: CommandFactory + FromArgMatches>(section: &str) -> T {
+    let args: Vec<OsString> = env::args_os().collect();
+    let mut command = command::<T>();
+    if let Some((path, table)) = load(&mut command, &args) {
+        let applied = match table.get(section) {
+            Some(Value::Table(section_table)) => defaults(command.clone(), section, section_table),
+            Some(_) => Err(format!("{} is not a table", section)),
+            None => Ok(command.clone()),
+        };
+        command = applied.unwrap_or_else(|e| fail(&mut command, exit::USAGE,
+                                                  format!("{}: {}", path.display(), e)));
+    }
+    parse_with(command, args)
+}
+
+// Parses the arguments of stanford-crypto, each section holding the
+// defaults of the command of the same name
+pub fn parse_commands<T: CommandFactory + FromArgMatches>() -> T {
+    let args: Vec<OsString> = env::args_os().collect();
+    let mut command = command::<T>();
+    if let Some((path, table)) = load(&mut command, &args) {
+        let mut applied = Ok(command.clone());
+        for (section, value) in &table {
+            applied = applied.and_then(|command| match value {
+                Value::Table(section_table) if command.find_subcommand(section).is_some() => {
+                    let mut result = Ok(());
+                    let command = command.mut_subcommand(section, |sub| {
+                        defaults(sub.clone(), section, section_table)
+                            .unwrap_or_else(|e| { result = Err(e); sub })
+                    });
+                    result.map(|()| command)
+                },
+                Value::Table(_) => Err(format!("no command {}", section)),
+                _ => Err(format!("{} is not a table", section)),
+            });
+        }
+        command = applied.unwrap_or_else(|e| fail(&mut command, exit::USAGE,
+                                                  format!("{}: {}", path.display(), e)));
+    }
+    parse_with(command, args)
+}
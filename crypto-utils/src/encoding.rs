@@ -0,0 +1,256 @@
+use clap::ValueEnum;
+use thiserror::Error;
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const BASE64_URL: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+// RFC 4648 in lower case, as multibase has it
+const BASE32: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+// Bitcoin's, without 0, O, I and l
+const BASE58: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+// Why a string doesn't decode as hex, base64 or multibase
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum DecodeError {
+    #[error("hex has an odd number of digits")]
+    OddLength,
+    #[error("not hex: {0:?}")]
+    NotHex(String),
+    #[error("base64 has a wrong length")]
+    Base64Length,
+    #[error("not base64: {0:?}")]
+    NotBase64(char),
+    #[error("base64 has stray bits after the last byte")]
+    StrayBits,
+    #[error("not base32: {0:?}")]
+    NotBase32(char),
+    #[error("base32 has a wrong length")]
+    Base32Length,
+    #[error("not base58: {0:?}")]
+    NotBase58(char),
+    #[error("not multibase: no encoding starts with {0:?}")]
+    UnknownBase(String),
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn hex_digit(c: u8) -> Option<u8> {
+    char::from(c).to_digit(16).map(|digit| digit as u8)
+}
+
+// Upper or lower case, ignoring surrounding whitespace
+pub fn from_hex(s: &str) -> Result<Vec<u8>, DecodeError> {
+    let s = s.trim();
+    if !s.len().is_multiple_of(2) {
+        return Err(DecodeError::OddLength);
+    }
+    s.as_bytes()
+        .chunks(2)
+        // Digit by digit, as u8::from_str_radix would take a sign, as in "+f"
+        .map(|pair| match (hex_digit(pair[0]), hex_digit(pair[1])) {
+            (Some(high), Some(low)) => Ok(high << 4 | low),
+            _ => Err(DecodeError::NotHex(String::from_utf8_lossy(pair).into_owned())),
+        })
+        .collect()
+}
+
+fn encode_base64(bytes: &[u8], alphabet: &[u8; 64], pad: bool) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let mut group = [0u8; 3];
+        group[..chunk.len()].copy_from_slice(chunk);
+        let n = u32::from_be_bytes([0, group[0], group[1], group[2]]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(alphabet[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else if pad {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+fn decode_base64(s: &str, alphabet: &[u8; 64]) -> Result<Vec<u8>, DecodeError> {
+    let chars: Vec<u8> = s.bytes().filter(|b| !b.is_ascii_whitespace()).collect();
+    let data_len = chars.iter().rposition(|&b| b != b'=').map_or(0, |i| i + 1);
+    let padding = chars.len() - data_len;
+    if padding > 2 || (padding > 0 && !chars.len().is_multiple_of(4)) || data_len % 4 == 1 {
+        return Err(DecodeError::Base64Length);
+    }
+
+    let mut out = Vec::with_capacity(data_len * 3 / 4);
+    let (mut acc, mut bits) = (0u32, 0);
+    for &c in &chars[..data_len] {
+        let value = alphabet.iter()
+            .position(|&b| b == c)
+            .ok_or(DecodeError::NotBase64(c as char))?;
+        acc = acc << 6 | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+            acc &= (1 << bits) - 1;
+        }
+    }
+    if acc != 0 {
+        return Err(DecodeError::StrayBits);
+    }
+    Ok(out)
+}
+
+// The standard alphabet, with = padding
+pub fn to_base64(bytes: &[u8]) -> String {
+    encode_base64(bytes, BASE64, true)
+}
+
+// The standard alphabet. Whitespace such as line breaks is skipped, and
+// the = padding may be left out.
+pub fn from_base64(s: &str) -> Result<Vec<u8>, DecodeError> {
+    decode_base64(s, BASE64)
+}
+
+// Without padding
+fn to_base32(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(5) * 8);
+    let (mut acc, mut bits) = (0u16, 0);
+    for &byte in bytes {
+        acc = acc << 8 | u16::from(byte);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(BASE32[(acc >> bits & 0x1f) as usize] as char);
+        }
+        acc &= (1 << bits) - 1;
+    }
+    if bits > 0 {
+        out.push(BASE32[(acc << (5 - bits) & 0x1f) as usize] as char);
+    }
+    out
+}
+
+// Either case, without padding
+fn from_base32(s: &str) -> Result<Vec<u8>, DecodeError> {
+    // Lengths that leave more than 4 bits over, which can't be padding
+    if matches!(s.len() % 8, 1 | 3 | 6) {
+        return Err(DecodeError::Base32Length);
+    }
+    let mut out = Vec::with_capacity(s.len() * 5 / 8);
+    let (mut acc, mut bits) = (0u16, 0);
+    for c in s.chars() {
+        let value = BASE32.iter()
+            .position(|&b| char::from(b) == c.to_ascii_lowercase())
+            .ok_or(DecodeError::NotBase32(c))?;
+        acc = acc << 5 | value as u16;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+        acc &= (1 << bits) - 1;
+    }
+    if acc != 0 {
+        return Err(DecodeError::StrayBits);
+    }
+    Ok(out)
+}
+
+// Each leading zero byte becomes a 1, and the rest is a big-endian number
+// in base 58
+fn to_base58(bytes: &[u8]) -> String {
+    let zeros = bytes.iter().take_while(|&&byte| byte == 0).count();
+    // Base 58 digits, least significant first
+    let mut digits: Vec<u8> = Vec::new();
+    for &byte in &bytes[zeros..] {
+        let mut carry = u32::from(byte);
+        for digit in digits.iter_mut() {
+            carry += u32::from(*digit) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+    let ones = std::iter::repeat_n('1', zeros);
+    ones.chain(digits.iter().rev().map(|&digit| BASE58[digit as usize] as char)).collect()
+}
+
+fn from_base58(s: &str) -> Result<Vec<u8>, DecodeError> {
+    let zeros = s.chars().take_while(|&c| c == '1').count();
+    // Bytes, least significant first
+    let mut bytes: Vec<u8> = Vec::new();
+    for c in s.chars().skip(zeros) {
+        let mut carry = BASE58.iter()
+            .position(|&b| char::from(b) == c)
+            .ok_or(DecodeError::NotBase58(c))? as u32;
+        for byte in bytes.iter_mut() {
+            carry += u32::from(*byte) * 58;
+            *byte = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.push(carry as u8);
+            carry >>= 8;
+        }
+    }
+    bytes.extend(std::iter::repeat_n(0, zeros));
+    bytes.reverse();
+    Ok(bytes)
+}
+
+// The encodings of multibase, a string whose first character says how the
+// rest is encoded, as IPFS writes hashes and identifiers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Base {
+    Base16,
+    Base32,
+    #[value(name = "base58btc")]
+    Base58Btc,
+    Base64,
+    #[value(name = "base64url")]
+    Base64Url,
+}
+
+impl Base {
+    pub fn prefix(self) -> char {
+        match self {
+            Base::Base16 => 'f',
+            Base::Base32 => 'b',
+            Base::Base58Btc => 'z',
+            Base::Base64 => 'm',
+            Base::Base64Url => 'u',
+        }
+    }
+}
+
+// Lower case, and without padding, as is usual for multibase
+pub fn to_multibase(base: Base, bytes: &[u8]) -> String {
+    let encoded = match base {
+        Base::Base16 => to_hex(bytes),
+        Base::Base32 => to_base32(bytes),
+        Base::Base58Btc => to_base58(bytes),
+        Base::Base64 => encode_base64(bytes, BASE64, false),
+        Base::Base64Url => encode_base64(bytes, BASE64_URL, false),
+    };
+    format!("{}{}", base.prefix(), encoded)
+}
+
+// Any of the encodings of Base, with the upper case prefixes F and B of
+// base16 and base32 too
+pub fn from_multibase(s: &str) -> Result<Vec<u8>, DecodeError> {
+    let s = s.trim();
+    let mut chars = s.chars();
+    let prefix = chars.next();
+    let rest = chars.as_str();
+    match prefix {
+        Some('f') | Some('F') => from_hex(rest),
+        Some('b') | Some('B') => from_base32(rest),
+        Some('z') => from_base58(rest),
+        Some('m') => decode_base64(rest, BASE64),
+        Some('u') => decode_base64(rest, BASE64_URL),
+        _ => Err(DecodeError::UnknownBase(prefix.map(String::from).unwrap_or_default())),
+    }
+}
@@ -0,0 +1,31 @@
+use std::fmt::Display;
+use std::process::ExitCode;
+
+use tracing::error;
+
+// The exit codes of every tool, past 0 for success: an attack or check
+// that failed, bad input (as for clap's usage errors), a file that
+// couldn't be read or written, and an oracle or peer that couldn't be
+// reached or made no sense
+pub const FAILED: u8 = 1;
+pub const USAGE: u8 = 2;
+pub const IO: u8 = 3;
+pub const ORACLE: u8 = 4;
+// Stopped by Ctrl-C, as shells report a process killed by SIGINT
+pub const INTERRUPTED: u8 = 130;
+
+// The error type of a tool, which knows its exit code
+pub trait Status: Display {
+    fn exit_code(&self) -> u8;
+}
+
+// The exit code of main for the result of a run, logging its error
+pub fn report<E: Status>(result: Result<(), E>) -> ExitCode {
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            error!("{}", e);
+            ExitCode::from(e.exit_code())
+        },
+    }
+}
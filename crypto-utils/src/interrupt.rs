@@ -0,0 +1,107 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::{Mutex, MutexGuard, Once, OnceLock};
+use std::time::Instant;
+
+pub use ctrlc::Error;
+use tracing::debug;
+
+use crate::exit;
+use crate::output;
+
+// Ctrl-C for the tools with long runs: instead of dying with half-written
+// files and nothing to show, a run prints what it got through, removes the
+// outputs it hadn't finished, says how to pick up where it stopped, and
+// exits with exit::INTERRUPTED.
+
+struct State {
+    // Created by this run and not finished yet
+    partial: Vec<PathBuf>,
+    summary: Option<Box<dyn FnMut() + Send>>,
+    resume: Option<String>,
+}
+
+static STATE: Mutex<State> = Mutex::new(State { partial: Vec::new(), summary: None, resume: None });
+static INSTALL: Once = Once::new();
+static START: OnceLock<Instant> = OnceLock::new();
+
+fn state() -> MutexGuard<'static, State> {
+    // A panic while holding the lock leaves nothing inconsistent
+    STATE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn handle() {
+    eprintln!("Interrupted after {:.2?}", START.get_or_init(Instant::now).elapsed());
+    let summary = state().summary.take();
+    if let Some(mut summary) = summary {
+        summary();
+    }
+    let (partial, resume) = {
+        let mut state = state();
+        (state.partial.split_off(0), state.resume.take())
+    };
+    for path in &partial {
+        match fs::remove_file(path) {
+            Ok(()) => eprintln!("Removed the partial {}", path.display()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {},
+            Err(e) => eprintln!("Could not remove the partial {}: {}", path.display(), e),
+        }
+    }
+    output::result("removed", partial.iter().map(|path| path.display().to_string())
+                   .collect::<Vec<_>>());
+    if let Some(resume) = resume {
+        eprintln!("To resume: {}", resume);
+        output::result("resume", resume);
+    }
+    let _ = output::finish(Err("Interrupted"));
+    process::exit(exit::INTERRUPTED.into());
+}
+
+// Handles Ctrl-C from now on. Only the first call installs the handler.
+pub fn init() -> Result<(), Error> {
+    START.get_or_init(Instant::now);
+    let mut result = Ok(());
+    INSTALL.call_once(|| result = ctrlc::set_handler(handle));
+    debug!(installed = result.is_ok(), "Set up Ctrl-C");
+    result
+}
+
+// Prints what the run got through on Ctrl-C, before the partial outputs
+// are removed. A later call replaces it.
+pub fn on_interrupt<F: FnMut() + Send + 'static>(summary: F) {
+    state().summary = Some(Box::new(summary));
+}
+
+// How to carry on after Ctrl-C at this point, such as the options that
+// skip what is done, or None once there is nothing to resume
+pub fn resume(hint: Option<String>) {
+    state().resume = hint;
+}
+
+// An output being written: removed on Ctrl-C until it is dropped, which
+// should happen once the file is complete, or once it is known to be
+// kept as it is. Register a file only after creating it, so that one
+// that was there before is never removed.
+#[derive(Debug)]
+pub struct Partial {
+    path: PathBuf,
+}
+
+impl Partial {
+    pub fn new(path: &Path) -> Partial {
+        state().partial.push(path.to_path_buf());
+        Partial { path: path.to_path_buf() }
+    }
+}
+
+impl Drop for Partial {
+    fn drop(&mut self) {
+        let mut state = state();
+        // Not there if Ctrl-C came first
+        if let Some(i) = state.partial.iter().rposition(|path| *path == self.path) {
+            state.partial.swap_remove(i);
+        }
+    }
+}
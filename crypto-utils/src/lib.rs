@@ -0,0 +1,28 @@
+extern crate clap;
+extern crate ctrlc;
+extern crate num_bigint;
+extern crate num_traits;
+extern crate rand;
+extern crate rand_chacha;
+extern crate serde_json;
+extern crate thiserror;
+extern crate toml;
+extern crate tracing;
+extern crate tracing_subscriber;
+extern crate zeroize;
+
+pub mod bigint;
+pub mod config;
+pub mod ct;
+pub mod encoding;
+pub mod exit;
+pub mod interrupt;
+pub mod log;
+pub mod modular;
+pub mod oracle;
+pub mod output;
+pub mod padding;
+pub mod random;
+pub mod roots;
+pub mod secret;
+pub mod size;
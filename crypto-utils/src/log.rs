@@ -0,0 +1,44 @@
+use std::io::{self, IsTerminal};
+
+use clap::{ArgAction, ValueEnum};
+use tracing::Level;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum LogFormat {
+    Text,
+    Json,
+}
+
+// The logging options every tool takes. They are global, so they can be
+// given after a subcommand too.
+#[derive(Debug, Clone, clap::Args)]
+pub struct LogArgs {
+    #[arg(short, long, action = ArgAction::Count, global = true,
+          help = "log more of what happens on stderr: debug events such as the phases \
+                  and rates of an attack, and every step with -vv")]
+    pub verbose: u8,
+    #[arg(long, value_name = "FORMAT", value_enum, default_value_t = LogFormat::Text,
+          global = true, help = "text, or one JSON object per line")]
+    pub log_format: LogFormat,
+}
+
+// Sends events to stderr: info and above by default, debug with -v and
+// trace with -vv. Only the first call in a process installs the logger.
+pub fn init(args: &LogArgs) {
+    let level = match args.verbose {
+        0 => Level::INFO,
+        1 => Level::DEBUG,
+        _ => Level::TRACE,
+    };
+    let builder = tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_writer(io::stderr);
+    let _ = match args.log_format {
+        LogFormat::Text => builder
+            .with_ansi(io::stderr().is_terminal())
+            .with_target(false)
+            .without_time()
+            .try_init(),
+        LogFormat::Json => builder.json().try_init(),
+    };
+}
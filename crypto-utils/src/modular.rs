@@ -0,0 +1,90 @@
+use num_bigint::{BigInt, BigUint, Sign};
+use num_traits::{One, Signed, Zero};
+
+// Returns the gcd and coefficients of Bézout's identity
+pub fn extended_gcd(a: &BigInt, b: &BigInt) -> (BigInt, BigInt, BigInt) {
+    if a.is_zero() {
+        (b.clone(), Zero::zero(), One::one())
+    } else {
+        let (g, s, t) = extended_gcd(&(b % a), a);
+        (g, t - (b / a) * &s, s)
+    }
+}
+
+pub fn gcd(a: &BigUint, b: &BigUint) -> BigUint {
+    let a = BigInt::from_biguint(Sign::Plus, a.clone());
+    let b = BigInt::from_biguint(Sign::Plus, b.clone());
+    let (g, _, _) = extended_gcd(&a, &b);
+    g.to_biguint().unwrap()
+}
+
+pub fn mod_inverse(a: &BigUint, m: &BigUint) -> Option<BigUint> {
+    let a = BigInt::from_biguint(Sign::Plus, a.clone());
+    let m = BigInt::from_biguint(Sign::Plus, m.clone());
+    let (gcd, s, _) = extended_gcd(&a, &m);
+    if !gcd.is_one() {
+        return None;
+    }
+    ((s % &m + &m) % &m).to_biguint()
+}
+
+// base^exp mod m, where a negative exp raises the inverse of base; None
+// if m is 0, or if exp is negative and base has no inverse
+pub fn mod_pow(base: &BigUint, exp: &BigInt, m: &BigUint) -> Option<BigUint> {
+    if m.is_zero() {
+        return None;
+    }
+    let exp_magnitude = exp.abs().to_biguint().unwrap();
+    if exp.sign() == Sign::Minus {
+        mod_inverse(base, m).map(|inverse| inverse.modpow(&exp_magnitude, m))
+    } else {
+        Some(base.modpow(&exp_magnitude, m))
+    }
+}
+
+// Combines x = r_i (mod m_i) for pairwise coprime moduli into x mod the
+// product of the m_i, returned with it; None if two moduli share a factor
+pub fn crt(congruences: &[(BigUint, BigUint)]) -> Option<(BigUint, BigUint)> {
+    let mut x = BigInt::zero();
+    let mut m = BigInt::one();
+
+    for (r, m_i) in congruences {
+        let r = BigInt::from_biguint(Sign::Plus, r.clone());
+        let m_i = BigInt::from_biguint(Sign::Plus, m_i.clone());
+        let (gcd, s, _) = extended_gcd(&m, &m_i);
+        if !gcd.is_one() {
+            return None;
+        }
+        // x + m * k = r (mod m_i)  =>  k = (r - x) * m^(-1) (mod m_i)
+        let k = ((&r - &x) * s % &m_i + &m_i) % &m_i;
+        x += &m * k;
+        m *= &m_i;
+    }
+
+    Some((x.to_biguint()?, m.to_biguint()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn congruences(pairs: &[(u32, u32)]) -> Vec<(BigUint, BigUint)> {
+        pairs.iter().map(|&(r, m)| (BigUint::from(r), BigUint::from(m))).collect()
+    }
+
+    #[test]
+    fn combines_congruences() {
+        // The classic x = 2 mod 3, 3 mod 5, 2 mod 7
+        let found = crt(&congruences(&[(2, 3), (3, 5), (2, 7)]));
+        assert_eq!(found, Some((BigUint::from(23u32), BigUint::from(105u32))));
+        let found = crt(&congruences(&[(0, 4), (8, 9)]));
+        assert_eq!(found, Some((BigUint::from(8u32), BigUint::from(36u32))));
+        assert_eq!(crt(&[]), Some((BigUint::zero(), BigUint::one())));
+    }
+
+    #[test]
+    fn rejects_moduli_with_a_common_factor() {
+        assert_eq!(crt(&congruences(&[(1, 6), (1, 4)])), None);
+        assert_eq!(crt(&congruences(&[(2, 3), (3, 5), (1, 9)])), None);
+    }
+}
@@ -0,0 +1,127 @@
+use std::io;
+use std::marker::PhantomData;
+use std::process::{Command, ExitStatus, Stdio};
+
+use thiserror::Error;
+
+use crate::encoding::to_hex;
+
+// How a padding oracle answered a chosen ciphertext
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OracleResponse {
+    // It decrypted to a genuine message
+    Valid,
+    // The padding was valid but the message was rejected, e.g. by a MAC
+    // check
+    BadMac,
+    BadPadding,
+}
+
+impl OracleResponse {
+    pub fn valid_padding(self) -> bool {
+        self != OracleResponse::BadPadding
+    }
+}
+
+// The least significant bit of a decryption, as leaked by an RSA parity
+// oracle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Parity {
+    Even,
+    Odd,
+}
+
+// Why an oracle gave no answer
+#[derive(Debug, Error)]
+pub enum OracleError {
+    #[error("could not run {program}: {source}")]
+    Spawn { program: String, source: io::Error },
+    #[error("{program} exited with {status}")]
+    Exit { program: String, status: ExitStatus },
+    // The oracle refused the query itself, such as a ciphertext that isn't
+    // whole blocks
+    #[error("{0}")]
+    Rejected(String),
+    // The oracle couldn't be reached, or answered with nonsense
+    #[error("{0}")]
+    Unreachable(String),
+}
+
+// Something that decrypts the ciphertexts it is sent and leaks something
+// about the result: an in-process simulation, a web server, another
+// program. Attacks may query it from several threads at once.
+pub trait Oracle: Sync {
+    type Response;
+
+    fn query(&self, ciphertext: &[u8]) -> Result<Self::Response, OracleError>;
+}
+
+impl<O: Oracle + ?Sized> Oracle for Box<O> {
+    type Response = O::Response;
+
+    fn query(&self, ciphertext: &[u8]) -> Result<O::Response, OracleError> {
+        (**self).query(ciphertext)
+    }
+}
+
+// Responses a program can give by its exit code
+pub trait FromExitCode: Sized {
+    fn from_exit_code(code: i32) -> Option<Self>;
+}
+
+// 0 for a valid message, 1 for a bad MAC and 2 for a bad padding
+impl FromExitCode for OracleResponse {
+    fn from_exit_code(code: i32) -> Option<OracleResponse> {
+        match code {
+            0 => Some(OracleResponse::Valid),
+            1 => Some(OracleResponse::BadMac),
+            2 => Some(OracleResponse::BadPadding),
+            _ => None,
+        }
+    }
+}
+
+// 0 for even and 1 for odd
+impl FromExitCode for Parity {
+    fn from_exit_code(code: i32) -> Option<Parity> {
+        match code {
+            0 => Some(Parity::Even),
+            1 => Some(Parity::Odd),
+            _ => None,
+        }
+    }
+}
+
+// Runs a program with the ciphertext in hex as its last argument, which
+// answers with its exit code
+#[derive(Debug, Clone)]
+pub struct CommandOracle<R> {
+    program: String,
+    args: Vec<String>,
+    response: PhantomData<fn() -> R>,
+}
+
+impl<R> CommandOracle<R> {
+    // The command line is split on whitespace; None if it is empty
+    pub fn new(command: &str) -> Option<CommandOracle<R>> {
+        let mut words = command.split_whitespace().map(str::to_string);
+        let program = words.next()?;
+        Some(CommandOracle { program, args: words.collect(), response: PhantomData })
+    }
+}
+
+impl<R: FromExitCode> Oracle for CommandOracle<R> {
+    type Response = R;
+
+    fn query(&self, ciphertext: &[u8]) -> Result<R, OracleError> {
+        let status = Command::new(&self.program)
+            .args(&self.args)
+            .arg(to_hex(ciphertext))
+            .stdout(Stdio::null())
+            .status()
+            .map_err(|source| OracleError::Spawn { program: self.program.clone(), source })?;
+        status.code()
+            .and_then(R::from_exit_code)
+            .ok_or_else(|| OracleError::Exit { program: self.program.clone(), status })
+    }
+}
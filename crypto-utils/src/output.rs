@@ -0,0 +1,99 @@
+use std::fmt::Display;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde_json::Map;
+pub use serde_json::{json, Value};
+
+// The --json option every tool takes, global like the logging options
+#[derive(Debug, Clone, clap::Args)]
+pub struct OutputArgs {
+    #[arg(long, global = true,
+          help = "print the result on stdout as one JSON object instead of text")]
+    pub json: bool,
+}
+
+// What a run did: the operation, its parameters, what it found and how
+// long its phases took, in seconds
+struct Report {
+    operation: String,
+    parameters: Map<String, Value>,
+    results: Map<String, Value>,
+    timings: Map<String, Value>,
+    start: Instant,
+}
+
+// Only there with --json, like the logger one report per process
+static REPORT: Mutex<Option<Report>> = Mutex::new(None);
+
+// Starts the report of a run if --json was given. Until finish, text!
+// prints nothing and the functions below fill in the report; without
+// --json they do nothing.
+pub fn init(operation: &str, args: &OutputArgs) {
+    if args.json {
+        *REPORT.lock().unwrap() = Some(Report {
+            operation: operation.to_string(),
+            parameters: Map::new(),
+            results: Map::new(),
+            timings: Map::new(),
+            start: Instant::now(),
+        });
+    }
+}
+
+pub fn is_json() -> bool {
+    REPORT.lock().unwrap().is_some()
+}
+
+fn with<F: FnOnce(&mut Report)>(f: F) {
+    if let Some(report) = REPORT.lock().unwrap().as_mut() {
+        f(report);
+    }
+}
+
+pub fn parameter<V: Into<Value>>(name: &str, value: V) {
+    with(|report| {
+        report.parameters.insert(name.to_string(), value.into());
+    });
+}
+
+pub fn result<V: Into<Value>>(name: &str, value: V) {
+    with(|report| {
+        report.results.insert(name.to_string(), value.into());
+    });
+}
+
+pub fn timing(name: &str, elapsed: Duration) {
+    with(|report| {
+        report.timings.insert(name.to_string(), elapsed.as_secs_f64().into());
+    });
+}
+
+// Prints the report as one line, with the total time and whether the run
+// succeeded, and the error if not. The result passes through to
+// exit::report.
+pub fn finish<E: Display>(result: Result<(), E>) -> Result<(), E> {
+    if let Some(report) = REPORT.lock().unwrap().take() {
+        let mut timings = report.timings;
+        timings.insert("total".to_string(), report.start.elapsed().as_secs_f64().into());
+        println!("{}", json!({
+            "operation": report.operation,
+            "parameters": report.parameters,
+            "results": report.results,
+            "timings": timings,
+            "success": result.is_ok(),
+            "error": result.as_ref().err().map(ToString::to_string),
+        }));
+    }
+    result
+}
+
+// println!, unless the run reports as JSON instead
+#[macro_export]
+macro_rules! text {
+    ($($arg:tt)*) => {
+        if !$crate::output::is_json() {
+            println!($($arg)*);
+        }
+    };
+}
@@ -0,0 +1,103 @@
+use thiserror::Error;
+
+// Why a buffer is not validly PKCS#7 padded. A padding oracle is exactly a
+// server that lets these cases be told apart from a valid message, so they
+// are kept distinct.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum PaddingError {
+    // Not even a padding byte
+    #[error("empty input has no padding")]
+    Empty,
+    // Not a whole number of blocks
+    #[error("{len} bytes is not a multiple of the {block_size}-byte block size")]
+    BadLength { len: usize, block_size: usize },
+    // The last byte, which should give the padding length, is 0 or more
+    // than the block size
+    #[error("invalid padding length {0}")]
+    BadPadLength(u8),
+    // A byte within the padding that differs from the padding length
+    #[error("padding byte {index} is {found:#04x}, expected {expected:#04x}")]
+    BadByte { index: usize, found: u8, expected: u8 },
+}
+
+fn check_block_size(block_size: usize) {
+    assert!(block_size > 0 && block_size < 256, "PKCS#7 block sizes are 1 to 255 bytes");
+}
+
+// Appends n bytes of value n, 1 <= n <= block_size, to reach a whole
+// number of blocks; a whole block of padding if data already is one
+pub fn pad(data: &[u8], block_size: usize) -> Vec<u8> {
+    check_block_size(block_size);
+    let n = block_size - data.len() % block_size;
+    let mut padded = Vec::with_capacity(data.len() + n);
+    padded.extend_from_slice(data);
+    padded.extend(std::iter::repeat_n(n as u8, n));
+    padded
+}
+
+// Checks the padding of a whole number of blocks and returns its length
+pub fn validate(padded: &[u8], block_size: usize) -> Result<usize, PaddingError> {
+    check_block_size(block_size);
+    let last = *padded.last().ok_or(PaddingError::Empty)?;
+    if !padded.len().is_multiple_of(block_size) {
+        return Err(PaddingError::BadLength { len: padded.len(), block_size });
+    }
+    let n = last as usize;
+    if n == 0 || n > block_size {
+        return Err(PaddingError::BadPadLength(last));
+    }
+    let start = padded.len() - n;
+    match padded[start..].iter().position(|&b| b != last) {
+        Some(i) => Err(PaddingError::BadByte { index: start + i, found: padded[start + i],
+                                               expected: last }),
+        None => Ok(n),
+    }
+}
+
+// The data without its padding
+pub fn unpad(padded: &[u8], block_size: usize) -> Result<&[u8], PaddingError> {
+    let n = validate(padded, block_size)?;
+    Ok(&padded[..padded.len() - n])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrips() {
+        for len in [0, 15, 16, 17] {
+            let data: Vec<u8> = (0..len as u8).collect();
+            let padded = pad(&data, 16);
+            assert_eq!(padded.len(), (len / 16 + 1) * 16);
+            assert_eq!(unpad(&padded, 16), Ok(&data[..]));
+        }
+    }
+
+    #[test]
+    fn rejects_bad_pad_lengths() {
+        let mut block = [0x41; 16];
+        assert_eq!(unpad(&block[..0], 16), Err(PaddingError::Empty));
+        block[15] = 0;
+        assert_eq!(unpad(&block, 16), Err(PaddingError::BadPadLength(0)));
+        block[15] = 17;
+        assert_eq!(unpad(&block, 16), Err(PaddingError::BadPadLength(17)));
+        // More padding than there is data
+        assert_eq!(unpad(&[5; 4], 4), Err(PaddingError::BadPadLength(5)));
+    }
+
+    #[test]
+    fn rejects_inconsistent_padding() {
+        let padded = b"ICE ICE BABY\x04\x04\x03\x04";
+        assert_eq!(unpad(padded, 16), Err(PaddingError::BadByte { index: 14, found: 3,
+                                                                  expected: 4 }));
+    }
+
+    #[test]
+    fn rejects_partial_blocks() {
+        let padded = b"ICE ICE BABY\x04\x04\x04\x04\x01";
+        assert_eq!(unpad(padded, 16), Err(PaddingError::BadLength { len: 17, block_size: 16 }));
+        assert_eq!(unpad(&padded[..15], 16), Err(PaddingError::BadLength { len: 15,
+                                                                          block_size: 16 }));
+    }
+}
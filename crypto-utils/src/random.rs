@@ -0,0 +1,46 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+
+use rand::rngs::OsRng;
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use tracing::debug;
+
+use crate::output;
+
+// The --seed option of the tools that use randomness, global like the
+// logging options
+#[derive(Debug, Clone, clap::Args)]
+pub struct RandomArgs {
+    #[arg(long, value_name = "N", global = true,
+          help = "seed the random generator, to repeat a run (default: from the OS)")]
+    pub seed: Option<u64>,
+}
+
+// One seed per process, from --seed or else the OS
+static SEED: OnceLock<u64> = OnceLock::new();
+// The streams handed out so far
+static STREAMS: AtomicU64 = AtomicU64::new(0);
+
+// Sets the seed of the run, logged with -v and in the --json report so
+// that a run can be repeated with --seed. Only the first call in a process
+// sets it.
+pub fn init(args: &RandomArgs) {
+    let seed = *SEED.get_or_init(|| args.seed.unwrap_or_else(|| OsRng.next_u64()));
+    debug!(seed, "Seeded the random generator");
+    output::parameter("seed", seed);
+}
+
+pub fn seed() -> u64 {
+    *SEED.get_or_init(|| OsRng.next_u64())
+}
+
+// A new generator. The nth call in a run gets the nth stream of the seed:
+// ChaCha rather than StdRng, whose algorithm may change between versions
+// of rand, so the same seed gives the same run everywhere, and a generator
+// per thread, taken in the same order, keeps threads from sharing one.
+pub fn rng() -> ChaCha20Rng {
+    let mut rng = ChaCha20Rng::seed_from_u64(seed());
+    rng.set_stream(STREAMS.fetch_add(1, Ordering::Relaxed));
+    rng
+}
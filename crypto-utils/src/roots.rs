@@ -0,0 +1,103 @@
+use num_bigint::BigUint;
+use num_traits::{pow, ToPrimitive};
+
+// Moduli whose quadratic residues rule out most non-squares before taking
+// a square root
+const FILTER_MODULI: [u32; 4] = [64, 63, 65, 11];
+
+// floor(sqrt(n))
+pub fn sqrt_floor(n: &BigUint) -> BigUint {
+    n.sqrt()
+}
+
+// ceil(sqrt(n)), the smallest r with r^2 >= n
+pub fn sqrt_ceil(n: &BigUint) -> BigUint {
+    let r = n.sqrt();
+    if &r * &r == *n {
+        r
+    } else {
+        r + 1u32
+    }
+}
+
+fn maybe_square(n: &BigUint) -> bool {
+    FILTER_MODULI.iter().all(|&m| {
+        let r = (n % m).to_u32().unwrap();
+        (0..m).any(|x| x * x % m == r)
+    })
+}
+
+// sqrt(n) if n is a perfect square
+pub fn sqrt_exact(n: &BigUint) -> Option<BigUint> {
+    if !maybe_square(n) {
+        return None;
+    }
+    let r = n.sqrt();
+    if &r * &r == *n {
+        Some(r)
+    } else {
+        None
+    }
+}
+
+// floor(n^(1/k)), for k >= 1
+pub fn root_floor(n: &BigUint, k: u32) -> BigUint {
+    assert!(k > 0, "there is no 0th root");
+    n.nth_root(k)
+}
+
+// n^(1/k) if n is a perfect kth power
+pub fn root_exact(n: &BigUint, k: u32) -> Option<BigUint> {
+    let r = root_floor(n, k);
+    if pow(r.clone(), k as usize) == *n {
+        Some(r)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn takes_square_roots() {
+        for n in [0u32, 1] {
+            assert_eq!(sqrt_ceil(&BigUint::from(n)), BigUint::from(n));
+            assert_eq!(sqrt_exact(&BigUint::from(n)), Some(BigUint::from(n)));
+        }
+        assert_eq!(sqrt_ceil(&BigUint::from(2u32)), BigUint::from(2u32));
+        assert_eq!(sqrt_exact(&BigUint::from(2u32)), None);
+        // Neither r^2 - 1 nor r^2 + 1 is a square for r >= 2
+        for r in [2u64, 3, 255, 256, 65_535, 1 << 32, u64::MAX] {
+            let r = BigUint::from(r);
+            let square = &r * &r;
+            assert_eq!(sqrt_ceil(&square), r);
+            assert_eq!(sqrt_exact(&square), Some(r.clone()));
+            assert_eq!(sqrt_ceil(&(&square - 1u32)), r);
+            assert_eq!(sqrt_exact(&(&square - 1u32)), None);
+            assert_eq!(sqrt_ceil(&(&square + 1u32)), &r + 1u32);
+            assert_eq!(sqrt_exact(&(&square + 1u32)), None);
+        }
+    }
+
+    #[test]
+    fn takes_kth_roots() {
+        for k in [1u32, 2, 3, 5, 17] {
+            assert_eq!(root_exact(&BigUint::from(0u32), k), Some(BigUint::from(0u32)));
+            assert_eq!(root_exact(&BigUint::from(1u32), k), Some(BigUint::from(1u32)));
+            for r in [2u64, 3, 1000, u64::MAX] {
+                let r = BigUint::from(r);
+                let power = pow(r.clone(), k as usize);
+                assert_eq!(root_exact(&power, k), Some(r.clone()));
+                // Every n is its own first root
+                if k > 1 {
+                    assert_eq!(root_exact(&(&power - 1u32), k), None);
+                    assert_eq!(root_exact(&(&power + 1u32), k), None);
+                    assert_eq!(root_floor(&(&power + 1u32), k), r);
+                    assert_eq!(root_floor(&(&power - 1u32), k), r - 1u32);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use rand::RngCore;
+pub use zeroize::{Zeroize, Zeroizing};
+
+use crate::encoding::from_hex;
+use crate::random;
+
+// Key material lives in these, so that it is wiped when dropped rather than
+// left behind in freed memory
+pub type SecretBytes = Zeroizing<Vec<u8>>;
+pub type SecretKey<const N: usize> = Zeroizing<[u8; N]>;
+
+// len random bytes from the generator of the run
+pub fn random_key(len: usize) -> SecretBytes {
+    let mut key = Zeroizing::new(vec![0; len]);
+    random::rng().fill_bytes(&mut key);
+    key
+}
+
+// An N-byte key from the generator of the run
+pub fn random_secret_key<const N: usize>() -> SecretKey<N> {
+    let mut key = Zeroizing::new([0; N]);
+    random::rng().fill_bytes(&mut *key);
+    key
+}
+
+// An N-byte key in hex, with the bytes decoded on the way wiped too
+pub fn key_from_hex<const N: usize>(s: &str) -> Option<SecretKey<N>> {
+    let bytes = Zeroizing::new(from_hex(s).ok()?);
+    if bytes.len() != N {
+        return None;
+    }
+    let mut key = Zeroizing::new([0; N]);
+    key.copy_from_slice(&bytes);
+    Some(key)
+}
@@ -0,0 +1,12 @@
+// Parses a byte count with an optional binary K, M, G or T suffix, e.g. 2G
+pub fn parse_size(val: &str) -> Option<u64> {
+    let val = val.trim().trim_end_matches(['B', 'b']).trim_end_matches('i');
+    let (digits, shift) = match val.chars().last()?.to_ascii_uppercase() {
+        'K' => (&val[..val.len() - 1], 10),
+        'M' => (&val[..val.len() - 1], 20),
+        'G' => (&val[..val.len() - 1], 30),
+        'T' => (&val[..val.len() - 1], 40),
+        _ => (val, 0),
+    };
+    digits.trim().parse::<u64>().ok()?.checked_mul(1 << shift)
+}
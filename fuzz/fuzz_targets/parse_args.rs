@@ -0,0 +1,38 @@
+#![no_main]
+
+extern crate clap;
+extern crate libfuzzer_sys;
+extern crate w1_many_time_pad;
+extern crate w2_aes;
+extern crate w3_file_auth;
+extern crate w5_mitm_dlog;
+extern crate w6_rsa_problem;
+
+use clap::Parser;
+use libfuzzer_sys::fuzz_target;
+
+// Parses the arguments of a tool, for its value parsers
+fn parse<T: Parser>(args: &[&str]) {
+    let _ = T::try_parse_from(args);
+}
+
+// Every option and argument with a parser of its own gets the string: the
+// parsers must reject it or accept it, but not panic
+fuzz_target!(|text: &str| {
+    parse::<w1_many_time_pad::cli::Args>(&["many-time-pad", "--threshold", text]);
+    parse::<w1_many_time_pad::lcg::Args>(&["lcg", "--modulus", text]);
+    parse::<w1_many_time_pad::lcg::Args>(&["lcg", "--shift", text, "1"]);
+    parse::<w1_many_time_pad::lcg::Args>(&["lcg", "--", text]);
+    parse::<w2_aes::cli::Args>(&["aes", "--key", text]);
+    parse::<w2_aes::cbc_mac_forgery::Args>(&["cbc-mac-forgery", "--", text]);
+    parse::<w2_aes::ecb_penguin::Args>(&["ecb-penguin", "--key", text]);
+    parse::<w3_file_auth::cli::Args>(&["file-auth", "verify", "--", text, "in", "out"]);
+    parse::<w3_file_auth::cli::Args>(&["file-auth", "sign", "--block-size", text, "in", "out"]);
+    parse::<w3_file_auth::length_extension::Args>(&["length-extension", "--tag", text]);
+    parse::<w5_mitm_dlog::cli::Args>(&["dlog", "--prime", text]);
+    parse::<w5_mitm_dlog::cli::Args>(&["dlog", "--range", text]);
+    parse::<w5_mitm_dlog::cli::Args>(&["dlog", "--max-memory", text]);
+    parse::<w6_rsa_problem::cli::Args>(&["rsa", "--modulus", text]);
+    parse::<w6_rsa_problem::cli::Args>(&["rsa", "--ratio", text]);
+    parse::<w6_rsa_problem::cli::Args>(&["rsa", "hastad", "--modulus", text]);
+});
@@ -0,0 +1,22 @@
+#![no_main]
+
+extern crate crypto_utils;
+extern crate libfuzzer_sys;
+
+use crypto_utils::bigint::{parse_big, parse_bigint};
+use crypto_utils::size::parse_size;
+use libfuzzer_sys::fuzz_target;
+
+// The integer parsers must not panic, and what they accept must read back
+// the same from its decimal form
+fuzz_target!(|text: &str| {
+    if let Some(n) = parse_big(text) {
+        assert_eq!(parse_big(&n.to_string()), Some(n));
+    }
+    if let Some(n) = parse_bigint(text) {
+        assert_eq!(parse_bigint(&n.to_string()), Some(n));
+    }
+    if let Some(size) = parse_size(text) {
+        assert_eq!(parse_size(&size.to_string()), Some(size));
+    }
+});
@@ -0,0 +1,29 @@
+#![no_main]
+
+extern crate libfuzzer_sys;
+extern crate w3_file_auth;
+
+use std::io::{self, Cursor};
+
+use libfuzzer_sys::fuzz_target;
+use w3_file_auth::cli::{hash_blocks, sign_stream, verify_stream};
+
+// A block size of up to 256 bytes and an arbitrary file: its signature
+// must verify against its h0 and give the file back
+fuzz_target!(|data: &[u8]| {
+    if data.is_empty() {
+        return;
+    }
+    let block_size = usize::from(data[0]) + 1;
+    let file = &data[1..];
+
+    let hashes = hash_blocks(Cursor::new(file), block_size).unwrap();
+    let mut signed = Vec::new();
+    sign_stream(file, &mut signed, block_size, &hashes, |e| e, |e: io::Error| e).unwrap();
+    let h0 = hashes.last().unwrap();
+
+    let mut output = Vec::new();
+    let verified = verify_stream(&signed[..], &mut output, block_size, h0, 2, |e| e, |e: io::Error| e);
+    assert!(verified.unwrap());
+    assert_eq!(output, file);
+});
@@ -0,0 +1,26 @@
+#![no_main]
+
+extern crate libfuzzer_sys;
+extern crate w3_file_auth;
+
+use std::convert::TryInto;
+use std::io;
+
+use libfuzzer_sys::fuzz_target;
+use w3_file_auth::chain::HASH_SIZE;
+use w3_file_auth::cli::verify_stream;
+
+// A block size of up to 4K, h0 and an arbitrary signed file: verifying
+// must fail or succeed, and never write more than it read
+fuzz_target!(|data: &[u8]| {
+    if data.len() < 2 + HASH_SIZE {
+        return;
+    }
+    let block_size = usize::from(u16::from_le_bytes([data[0], data[1]]) % 4096) + 1;
+    let hash: [u8; HASH_SIZE] = data[2..2 + HASH_SIZE].try_into().unwrap();
+    let signed = &data[2 + HASH_SIZE..];
+
+    let mut output = Vec::new();
+    verify_stream(signed, &mut output, block_size, &hash, 1, |e| e, |e: io::Error| e).unwrap();
+    assert!(output.len() <= signed.len());
+});
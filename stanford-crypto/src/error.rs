@@ -0,0 +1,22 @@
+use std::io;
+use std::path::PathBuf;
+
+use crypto_utils::exit::{self, Status};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Could not write {}: {source}", path.display())]
+    Write { path: PathBuf, source: io::Error },
+    #[error("{failed} of {components} components failed their known answers")]
+    SelfTest { failed: usize, components: usize },
+}
+
+impl Status for Error {
+    fn exit_code(&self) -> u8 {
+        match self {
+            Error::Write { .. } => exit::IO,
+            Error::SelfTest { .. } => exit::FAILED,
+        }
+    }
+}
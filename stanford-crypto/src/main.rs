@@ -0,0 +1,100 @@
+extern crate clap;
+extern crate clap_complete;
+extern crate crypto_utils;
+extern crate num_bigint;
+extern crate rand;
+extern crate rand_chacha;
+extern crate thiserror;
+extern crate tracing;
+extern crate w1_many_time_pad;
+extern crate w2_aes;
+extern crate w3_file_auth;
+extern crate w4_padding_oracle_attack;
+extern crate w5_mitm_dlog;
+extern crate w6_rsa_problem;
+
+mod error;
+mod selftest;
+mod vectors;
+
+use std::io;
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand};
+use clap_complete::Shell;
+use crypto_utils::{config, exit};
+
+#[derive(Parser)]
+#[command(name = "stanford-crypto", about = "All the assignments of the Stanford Cryptography I course",
+          long_about = "All the assignments of the Stanford Cryptography I course. Each command \
+                        takes the same arguments and options as the binary of its week, listed \
+                        with COMMAND --help.")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    #[command(name = "many-time-pad",
+              about = "week 1: decrypt ciphertexts that reuse a stream cipher key")]
+    ManyTimePad(w1_many_time_pad::cli::Args),
+    #[command(about = "week 1: recover the state of an LCG and predict its outputs")]
+    Lcg(w1_many_time_pad::lcg::Args),
+    #[command(about = "week 2: AES in CBC and CTR modes")]
+    Aes(w2_aes::cli::Args),
+    #[command(about = "week 2: forge an admin cookie by flipping CBC ciphertext bits")]
+    CbcBitflip(w2_aes::cbc_bitflip::Args),
+    #[command(about = "week 2: splice two tagged messages into a raw CBC-MAC forgery")]
+    CbcMacForgery(w2_aes::cbc_mac_forgery::Args),
+    #[command(about = "week 2: encrypt a BMP image under ECB, or detect ECB")]
+    EcbPenguin(w2_aes::ecb_penguin::Args),
+    #[command(about = "week 3: sign and verify files with a chain of SHA-256 hashes")]
+    FileAuth(w3_file_auth::cli::Args),
+    #[command(about = "week 3: forge a secret-prefix SHA-256 MAC")]
+    LengthExtension(w3_file_auth::length_extension::Args),
+    #[command(about = "week 3: find collisions on truncated SHA-256")]
+    Birthday(w3_file_auth::birthday::Args),
+    #[command(about = "week 4: decrypt a CBC ciphertext with a padding oracle")]
+    PaddingOracle(w4_padding_oracle_attack::cli::Args),
+    #[command(about = "week 5: discrete logs by meet-in-the-middle and more")]
+    Dlog(Box<w5_mitm_dlog::cli::Args>),
+    #[command(about = "week 6: break RSA with close primes, a parity oracle or a broadcast")]
+    Rsa(w6_rsa_problem::cli::Args),
+    #[command(name = "gen-vectors",
+              about = "write test vectors of weeks 2, 3 and 5 for other implementations")]
+    GenVectors(vectors::Args),
+    #[command(name = "self-test",
+              about = "check every primitive against known answers, e.g. on a new platform")]
+    SelfTest(selftest::Args),
+    #[command(about = "print a completion script for SHELL to stdout",
+              long_about = "Prints a completion script for SHELL to stdout, e.g. \
+                            stanford-crypto completions bash > /etc/bash_completion.d/stanford-crypto")]
+    Completions {
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+}
+
+fn main() -> ExitCode {
+    match config::parse_commands::<Cli>().command {
+        Command::ManyTimePad(args) => exit::report(w1_many_time_pad::cli::run(args)),
+        Command::Lcg(args) => exit::report(w1_many_time_pad::lcg::run(args)),
+        Command::Aes(args) => exit::report(w2_aes::cli::run(args)),
+        Command::CbcBitflip(args) => exit::report(w2_aes::cbc_bitflip::run(args)),
+        Command::CbcMacForgery(args) => exit::report(w2_aes::cbc_mac_forgery::run(args)),
+        Command::EcbPenguin(args) => exit::report(w2_aes::ecb_penguin::run(args)),
+        Command::FileAuth(args) => exit::report(w3_file_auth::cli::run(args)),
+        Command::LengthExtension(args) => exit::report(w3_file_auth::length_extension::run(args)),
+        Command::Birthday(args) => exit::report(w3_file_auth::birthday::run(args)),
+        Command::PaddingOracle(args) => exit::report(w4_padding_oracle_attack::cli::run(args)),
+        Command::Dlog(args) => exit::report(w5_mitm_dlog::cli::run(*args)),
+        Command::Rsa(args) => exit::report(w6_rsa_problem::cli::run(args)),
+        Command::GenVectors(args) => exit::report(vectors::run(args)),
+        Command::SelfTest(args) => exit::report(selftest::run(args)),
+        Command::Completions { shell } => {
+            clap_complete::generate(shell, &mut config::command::<Cli>(), "stanford-crypto", &mut io::stdout());
+            ExitCode::SUCCESS
+        },
+    }
+}
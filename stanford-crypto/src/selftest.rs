@@ -0,0 +1,295 @@
+use std::fmt::Debug;
+use std::io::Cursor;
+use std::panic;
+use std::time::Instant;
+
+use clap::builder::PossibleValuesParser;
+use clap::Parser;
+use crypto_utils::encoding::{from_hex, to_hex};
+use crypto_utils::log::{self, LogArgs};
+use crypto_utils::modular::{crt, gcd, mod_inverse, mod_pow};
+use crypto_utils::output::{self, json, OutputArgs};
+use crypto_utils::padding;
+use crypto_utils::text;
+use num_bigint::{BigInt, BigUint};
+use w3_file_auth::chain::{hash_block, Rejected, Verifier};
+use w3_file_auth::cli::{hash_blocks, sign_stream, verify_stream};
+use w5_mitm_dlog::ec::Point;
+use w5_mitm_dlog::pohlig_hellman::{self, PohligHellman};
+use w5_mitm_dlog::{Curve, DiscreteLogSolver, Group, Instance, Zp};
+use w6_rsa_problem::{fermat, hastad, rsa};
+
+use crate::error::Error;
+
+// Known answers for the primitives and modes of every week, from the
+// standards where there are some (FIPS 180-4, FIPS 197, SP 800-38A) and
+// otherwise computed independently with Python. Each check returns the
+// first answer that differs.
+type Check = fn() -> Result<(), String>;
+
+const COMPONENTS: [(&str, Check); 11] = [
+    ("sha-256", sha256),
+    ("hash-chain", hash_chain),
+    ("aes", aes),
+    ("aes-cbc", aes_cbc),
+    ("aes-ctr", aes_ctr),
+    ("pkcs7", pkcs7),
+    ("modular", modular),
+    ("dlog-zp", dlog_zp),
+    ("pohlig-hellman", dlog_smooth),
+    ("dlog-curve", dlog_curve),
+    ("rsa", rsa),
+];
+
+#[derive(Parser)]
+#[command(about = "Checks every primitive against known answers",
+          long_about = "Checks every primitive and mode of the weeks against known answers: \
+          SHA-256 and the hash chain of week 3, AES and its CBC and CTR modes, PKCS#7 padding, \
+          modular arithmetic, the discrete log solvers of week 5 over Z_p* and the toy curve, \
+          and the RSA attacks of week 6. Exits with 1 if any fails, e.g. after building on a \
+          new platform or with another arithmetic backend.")]
+pub struct Args {
+    #[arg(value_name = "COMPONENT",
+          value_parser = PossibleValuesParser::new(COMPONENTS.iter().map(|(name, _)| *name)),
+          help = "components to check (default: all)")]
+    components: Vec<String>,
+    #[command(flatten)]
+    log: LogArgs,
+    #[command(flatten)]
+    output: OutputArgs,
+}
+
+fn expect<T: PartialEq + Debug>(what: &str, got: T, expected: T) -> Result<(), String> {
+    if got == expected {
+        Ok(())
+    } else {
+        Err(format!("{}: got {:?}, expected {:?}", what, got, expected))
+    }
+}
+
+fn expect_hex(what: &str, got: &[u8], expected: &str) -> Result<(), String> {
+    expect(what, to_hex(got), expected.to_string())
+}
+
+fn big(s: &str) -> BigUint {
+    BigUint::parse_bytes(s.as_bytes(), 10).unwrap()
+}
+
+fn sha256() -> Result<(), String> {
+    expect_hex("SHA-256(\"\")", &hash_block(b"", None),
+               "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855")?;
+    expect_hex("SHA-256(\"abc\")", &hash_block(b"abc", None),
+               "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+}
+
+// 2500 bytes in blocks of 1024: two whole blocks and a short last one
+fn hash_chain() -> Result<(), String> {
+    let data: Vec<u8> = (0..2500).map(|i| (i % 251) as u8).collect();
+    let hashes = hash_blocks(Cursor::new(&data), 1024).map_err(|e| e.to_string())?;
+    let h0 = *hashes.last().unwrap();
+    expect_hex("h0", &h0, "bb3891e04dd74d1939898173c2ab5e7e73e6fd0fe95cdeb1e00642f447c970b8")?;
+
+    let mut signed = Vec::new();
+    sign_stream(&data[..], &mut signed, 1024, &hashes, |e| e, |e| e).map_err(|e| e.to_string())?;
+    expect("signed length", signed.len(), data.len() + 2 * 32)?;
+    let mut verified = Vec::new();
+    let ok = verify_stream(&signed[..], &mut verified, 1024, &h0, 2, |e| e, |e| e)
+        .map_err(|e| e.to_string())?;
+    expect("verified", ok, true)?;
+    expect("verified file", verified == data, true)?;
+
+    // In the second block, so the first still verifies
+    signed[1500] ^= 1;
+    let mut verifier = Verifier::new(h0, 1024);
+    let rejected: Vec<_> = signed.chunks(verifier.signed_size())
+        .map(|block| verifier.verify(block).err())
+        .collect();
+    expect("blocks with a flipped bit", rejected,
+           vec![None, Some(Rejected::Mismatch), Some(Rejected::Ended)])
+}
+
+// An IV or block of AES in hex
+fn block(hex: &str) -> [u8; w2_aes::BLOCK] {
+    let mut block = [0; w2_aes::BLOCK];
+    block.copy_from_slice(&from_hex(hex).unwrap());
+    block
+}
+
+// FIPS 197, appendix C.1. ECB encrypts the block and then a block of
+// padding.
+fn aes() -> Result<(), String> {
+    let key = w2_aes::parse_key("000102030405060708090a0b0c0d0e0f").unwrap();
+    let block = from_hex("00112233445566778899aabbccddeeff").unwrap();
+    expect_hex("AES-128", &w2_aes::ecb_encrypt(&key, &block)[..16],
+               "69c4e0d86a7b0430d8cdb78070b4c55a")
+}
+
+// SP 800-38A, appendix F: the same key and plaintext for both modes
+const SP800_38A_KEY: &str = "2b7e151628aed2a6abf7158809cf4f3c";
+const SP800_38A_PLAINTEXT: &str = concat!(
+    "6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e51",
+    "30c81c46a35ce411e5fbc1191a0a52eff69f2445df4f9b17ad2b417be66c3710");
+
+// The ciphertext of w2-aes starts with the IV, and CBC ends with a block
+// of padding
+fn aes_cbc() -> Result<(), String> {
+    let key = w2_aes::parse_key(SP800_38A_KEY).unwrap();
+    let plaintext = from_hex(SP800_38A_PLAINTEXT).unwrap();
+    let iv = block("000102030405060708090a0b0c0d0e0f");
+    let ciphertext = w2_aes::cbc_encrypt(&key, &iv, &plaintext);
+    expect_hex("CBC encryption", &ciphertext[16..80], concat!(
+        "7649abac8119b246cee98e9b12e9197d5086cb9b507219ee95db113a917678b2",
+        "73bed6b8e3c1743b7116e69e222295163ff1caa1681fac09120eca307586e1a7"))?;
+    let decrypted = w2_aes::cbc_decrypt(&key, &ciphertext).map_err(|e| e.to_string())?;
+    expect_hex("CBC decryption", &decrypted, SP800_38A_PLAINTEXT)
+}
+
+fn aes_ctr() -> Result<(), String> {
+    let key = w2_aes::parse_key(SP800_38A_KEY).unwrap();
+    let plaintext = from_hex(SP800_38A_PLAINTEXT).unwrap();
+    let counter = block("f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff");
+    let ciphertext = w2_aes::ctr_encrypt(&key, &counter, &plaintext);
+    expect_hex("CTR encryption", &ciphertext[16..], concat!(
+        "874d6191b620e3261bef6864990db6ce9806f66b7970fdff8617187bb9fffdff",
+        "5ae4df3edbd5d35e5b4f09020db03eab1e031dda2fbe03d1792170a0f3009cee"))?;
+    let decrypted = w2_aes::ctr_decrypt(&key, &ciphertext).map_err(|e| e.to_string())?;
+    expect_hex("CTR decryption", &decrypted, SP800_38A_PLAINTEXT)
+}
+
+fn pkcs7() -> Result<(), String> {
+    expect("pad", padding::pad(b"YELLOW SUBMARINE", 20), b"YELLOW SUBMARINE\x04\x04\x04\x04".to_vec())?;
+    expect("pad of a whole block", padding::pad(&[0; 16], 16).len(), 32)?;
+    expect("unpad", padding::unpad(b"ICE ICE BABY\x04\x04\x04\x04", 16), Ok(&b"ICE ICE BABY"[..]))?;
+    expect("unpad of a bad byte", padding::unpad(b"ICE ICE BABY\x01\x02\x03\x04", 16).is_err(), true)
+}
+
+fn modular() -> Result<(), String> {
+    let n = |n: u32| BigUint::from(n);
+    expect("4^13 mod 497", mod_pow(&n(4), &BigInt::from(13), &n(497)), Some(n(445)))?;
+    expect("3^-1 mod 11", mod_pow(&n(3), &BigInt::from(-1), &n(11)), Some(n(4)))?;
+    expect("17^-1 mod 3120", mod_inverse(&n(17), &n(3120)), Some(n(2753)))?;
+    expect("gcd(240, 46)", gcd(&n(240), &n(46)), n(2))?;
+    expect("x = 2 mod 3, 3 mod 5, 2 mod 7", crt(&[(n(2), n(3)), (n(3), n(5)), (n(2), n(7))]),
+           Some((n(23), n(105))))
+}
+
+fn solve<G: Group + Clone>(solver: &dyn DiscreteLogSolver<G>, instance: &Instance<G>,
+                           x: u64) -> Result<(), String> {
+    let found = solver.find(instance).map_err(|e| format!("{}: {}", solver.name(), e))?;
+    expect(solver.name(), found, Some(BigUint::from(x)))
+}
+
+// In the subgroup of prime order q of a 32-bit safe prime, with x below
+// 2^24, so that every solver finishes in well under a second
+fn dlog_zp() -> Result<(), String> {
+    let group = Zp::new(big("2975145227"));
+    let (g, h) = (group.element(&big("3")), group.element(&big("1223262134")));
+    expect("3^2928862", group.value(&group.exp(&g, &big("2928862"))), big("1223262134"))?;
+    let mut instance = Instance::new(group, g, h, 1 << 24);
+    instance.order = Some(big("1487572613"));
+    for name in &["mitm", "bsgs", "rho", "kangaroo"] {
+        solve(&*w5_mitm_dlog::solver(name).unwrap(), &instance, 2_928_862)?;
+    }
+    Ok(())
+}
+
+// p - 1 = 2 * 97 * 503 * 541 * 613 * 673 * 751 * 787, and 5 generates
+// the whole group
+fn dlog_smooth() -> Result<(), String> {
+    let p = big("12872330688362078807");
+    let factors = pohlig_hellman::factor(&(&p - 1u32))
+        .map_err(|rest| format!("p - 1 not factored: {} is left", rest))?;
+    expect("factors of p - 1", factors.clone(),
+           vec![(2, 1), (97, 1), (503, 1), (541, 1), (613, 1), (673, 1), (751, 1), (787, 1)])?;
+    let group = Zp::new(p);
+    let (g, h) = (group.element(&big("5")), group.element(&big("2657220416952088548")));
+    let instance = Instance::new(group, g, h, 12_872_330_688_362_078_806);
+    let solver = PohligHellman::new(factors, w5_mitm_dlog::solver("bsgs").unwrap());
+    solve(&solver, &instance, 12_265_987_618_752_161_152)
+}
+
+fn dlog_curve() -> Result<(), String> {
+    let curve = Curve::toy();
+    let h = Point::Affine(big("2503697"), big("238816599"));
+    expect("8440525 G", curve.exp(&curve.generator, &big("8440525")), h.clone())?;
+    let instance = Instance::new(curve.clone(), curve.generator.clone(), h, 1 << 24);
+    for name in &["mitm", "bsgs", "rho", "kangaroo"] {
+        solve(&*w5_mitm_dlog::solver(name).unwrap(), &instance, 8_440_525)?;
+    }
+    Ok(())
+}
+
+// Fermat's method on primes 2^40 apart, PKCS#1 v1.5 decryption with the
+// key it gives, and Håstad's broadcast attack on e = 3
+fn rsa() -> Result<(), String> {
+    let n = big("75006489492142096377084009439639415991902027277816090103854197225795693289791");
+    let factors = fermat::fermat(&n, (1, 1), 1 << 21).ok_or("N not factored")?;
+    expect("p", factors.p.clone(), big("273873126633742757176281193188567560663"))?;
+    let e = BigUint::from(65_537u32);
+    let d = rsa::private_exponent(&e, &factors.p, &factors.q).ok_or("e not invertible")?;
+    let c = big("8411282384082161928980279122033630777001020108322331970696749797218239387930");
+    let message = rsa::pkcs1_v15_unpad(&c.modpow(&d, &n), rsa::modulus_len(&n))
+        .map_err(|e| e.to_string())?;
+    expect("decryption", message, b"self-test".to_vec())?;
+
+    let pairs = [
+        ("104223835356000515822206265835877946929", "68765061220911704548214167447730942643"),
+        ("217569472265381754545542453783691146897", "129213615743678771208888720396818231672"),
+        ("259906577629066583065373473394709838819", "72844473421556738437477272256241166866"),
+    ];
+    let pairs: Vec<_> = pairs.iter().map(|(n, c)| (big(n), big(c))).collect();
+    let m = hastad::hastad(3, &pairs).map_err(|e| e.to_string())?;
+    expect("broadcast", m.to_bytes_be(), b"broadcast".to_vec())
+}
+
+// A check that panics fails too, its message printed on stderr as usual
+fn check(component: Check) -> Result<(), String> {
+    panic::catch_unwind(component).unwrap_or_else(|_| Err("panicked".to_string()))
+}
+
+pub fn run(args: Args) -> Result<(), Error> {
+    log::init(&args.log);
+    output::init("self-test", &args.output);
+    output::finish(self_test(&args))
+}
+
+fn self_test(args: &Args) -> Result<(), Error> {
+    let backend = if cfg!(feature = "gmp") { "gmp" } else { "montgomery" };
+    output::parameter("backend", backend);
+    let start = Instant::now();
+    let mut results = Vec::new();
+    let mut failed = 0;
+    for &(name, component) in &COMPONENTS {
+        if !args.components.is_empty() && !args.components.iter().any(|c| c == name) {
+            continue;
+        }
+        let started = Instant::now();
+        let result = check(component);
+        let elapsed = started.elapsed();
+        match &result {
+            Ok(()) => text!("{:<16}ok in {:.1?}", name, elapsed),
+            Err(e) => {
+                failed += 1;
+                text!("{:<16}FAILED: {}", name, e);
+            },
+        }
+        results.push(json!({
+            "component": name,
+            "passed": result.is_ok(),
+            "error": result.err(),
+            "time": elapsed.as_secs_f64(),
+        }));
+    }
+    let total = results.len();
+    text!("{} of {} components passed with the {} backend in {:.1?}", total - failed, total,
+          backend, start.elapsed());
+    output::result("components", results);
+    output::result("passed", total - failed);
+    output::result("failed", failed);
+    output::timing("self_test", start.elapsed());
+
+    if failed > 0 {
+        return Err(Error::SelfTest { failed, components: total });
+    }
+    Ok(())
+}
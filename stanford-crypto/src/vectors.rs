@@ -0,0 +1,163 @@
+use std::fs;
+use std::io::{self, Cursor};
+use std::path::{Path, PathBuf};
+
+use clap::Parser;
+use crypto_utils::encoding::{to_hex, Base};
+use crypto_utils::log::{self, LogArgs};
+use crypto_utils::output::{self, json, OutputArgs, Value};
+use crypto_utils::secret::Zeroizing;
+use crypto_utils::text;
+use num_bigint::BigUint;
+use rand::{Rng, RngCore, SeedableRng};
+// ChaCha rather than StdRng, whose algorithm may change between versions
+// of rand: the same seed must give the same vectors everywhere
+use rand_chacha::ChaCha20Rng;
+use tracing::info;
+use w2_aes::Mode;
+use w3_file_auth::cli::{hash_blocks, sign_stream};
+use w3_file_auth::multihash;
+use w5_mitm_dlog::params;
+
+use crate::error::Error;
+
+// Files of sizes around the block boundaries, signed in blocks of each size
+const FILE_SIZES: [usize; 7] = [0, 1, 1023, 1024, 1025, 4096, 100_000];
+const BLOCK_SIZES: [usize; 2] = [1024, 4096];
+
+// Bits of the safe prime and of the width of the range x is drawn from
+const DLOG_SIZES: [(usize, u32); 4] = [(32, 16), (48, 24), (64, 32), (64, 40)];
+const PRIME_ROUNDS: u32 = 32;
+
+const AES_MODES: [Mode; 2] = [Mode::Cbc, Mode::Ctr];
+const PLAINTEXT_SIZES: [usize; 7] = [0, 1, 15, 16, 17, 32, 100];
+
+#[derive(Parser)]
+#[command(about = "Writes test vectors for other implementations into DIR",
+          long_about = "Writes test vectors for other implementations into DIR: files signed \
+          as in week 3 with their h0, discrete log instances with their solutions as in week 5, \
+          and AES encryptions in CBC and CTR modes as in week 2, all listed in \
+          DIR/vectors.json. The same seed always gives the same vectors.")]
+pub struct Args {
+    #[arg(value_name = "DIR", help = "directory to write to, created if needed")]
+    dir: PathBuf,
+    #[arg(short, long, value_name = "N", default_value_t = 0, help = "seed of the generator")]
+    seed: u64,
+    #[command(flatten)]
+    log: LogArgs,
+    #[command(flatten)]
+    output: OutputArgs,
+}
+
+fn write(path: &Path, contents: &[u8]) -> Result<(), Error> {
+    fs::write(path, contents).map_err(|source| Error::Write { path: path.to_path_buf(), source })
+}
+
+fn random_bytes<R: RngCore>(rng: &mut R, len: usize) -> Vec<u8> {
+    let mut bytes = vec![0; len];
+    rng.fill_bytes(&mut bytes);
+    bytes
+}
+
+// Each file, its signature and h0, the files in the file-auth directory
+fn file_auth<R: RngCore>(rng: &mut R, dir: &Path) -> Result<Vec<Value>, Error> {
+    let files = dir.join("file-auth");
+    fs::create_dir_all(&files).map_err(|source| Error::Write { path: files.clone(), source })?;
+
+    let mut vectors = Vec::new();
+    for &size in &FILE_SIZES {
+        let data = random_bytes(rng, size);
+        let name = format!("{}.bin", size);
+        write(&files.join(&name), &data)?;
+        for &block_size in &BLOCK_SIZES {
+            // In memory, so these can't fail
+            let hashes = hash_blocks(Cursor::new(&data), block_size).unwrap();
+            let mut signed = Vec::new();
+            sign_stream(&data[..], &mut signed, block_size, &hashes, |e| e, |e: io::Error| e)
+                .unwrap();
+            let signed_name = format!("{}.{}.signed", size, block_size);
+            write(&files.join(&signed_name), &signed)?;
+            vectors.push(json!({
+                "file": format!("file-auth/{}", name),
+                "signed": format!("file-auth/{}", signed_name),
+                "block_size": block_size,
+                "h0": to_hex(hashes.last().unwrap()),
+                // As IPFS writes it, for checking multihash support
+                "h0_multihash": multihash::format(hashes.last().unwrap(), Some(Base::Base32)),
+            }));
+        }
+    }
+    Ok(vectors)
+}
+
+// Instances g^x = h modulo a safe prime, with x in a range that doesn't
+// always start at 0, as the arguments of w5-mitm_dlog take them
+fn dlog<R: Rng>(rng: &mut R) -> Vec<Value> {
+    DLOG_SIZES.iter().map(|&(bits, range_bits)| {
+        info!(bits, "Generating a safe prime");
+        let p = params::safe_prime(bits, PRIME_ROUNDS, rng);
+        let g = params::subgroup_generator(&p);
+        let lo = rng.gen_range(0..1u64 << range_bits);
+        let hi = lo + (1 << range_bits) - 1;
+        let x = rng.gen_range(lo..=hi);
+        let h = g.modpow(&BigUint::from(x), &p);
+        json!({
+            "prime": p.to_string(),
+            "base": g.to_string(),
+            "target": h.to_string(),
+            "range": format!("{}..{}", lo, hi),
+            "x": x.to_string(),
+        })
+    }).collect()
+}
+
+// Encryptions in hex, the ciphertexts starting with the IV like those of
+// w2-aes
+fn aes<R: RngCore>(rng: &mut R) -> Vec<Value> {
+    let mut vectors = Vec::new();
+    for &mode in &AES_MODES {
+        for &size in &PLAINTEXT_SIZES {
+            let (mut key, mut iv) = (Zeroizing::new([0; w2_aes::BLOCK]), [0; w2_aes::BLOCK]);
+            rng.fill_bytes(&mut *key);
+            rng.fill_bytes(&mut iv);
+            let plaintext = random_bytes(rng, size);
+            let ciphertext = w2_aes::encrypt(mode, &key, &iv, &plaintext);
+            vectors.push(json!({
+                "mode": mode.name(),
+                "key": to_hex(&key[..]),
+                "iv": to_hex(&iv),
+                "plaintext": to_hex(&plaintext),
+                "ciphertext": to_hex(&ciphertext),
+            }));
+        }
+    }
+    vectors
+}
+
+pub fn run(args: Args) -> Result<(), Error> {
+    log::init(&args.log);
+    output::init("gen-vectors", &args.output);
+    output::finish(generate(&args))
+}
+
+fn generate(args: &Args) -> Result<(), Error> {
+    output::parameter("dir", args.dir.display().to_string());
+    output::parameter("seed", args.seed);
+
+    let mut rng = ChaCha20Rng::seed_from_u64(args.seed);
+    let file_auth = file_auth(&mut rng, &args.dir)?;
+    let dlog = dlog(&mut rng);
+    let aes = aes(&mut rng);
+    let manifest = json!({ "seed": args.seed, "file-auth": &file_auth, "dlog": &dlog, "aes": &aes });
+    let path = args.dir.join("vectors.json");
+    write(&path, format!("{:#}\n", manifest).as_bytes())?;
+    text!("Wrote {} file-auth, {} dlog and {} aes vectors to {}", file_auth.len(), dlog.len(),
+          aes.len(), path.display());
+    output::result("vectors", json!({
+        "file-auth": file_auth.len(),
+        "dlog": dlog.len(),
+        "aes": aes.len(),
+    }));
+    output::result("manifest", path.display().to_string());
+    Ok(())
+}
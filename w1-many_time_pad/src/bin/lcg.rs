@@ -0,0 +1,11 @@
+extern crate crypto_utils;
+extern crate w1_many_time_pad;
+
+use std::process::ExitCode;
+
+use crypto_utils::{config, exit};
+use w1_many_time_pad::lcg;
+
+fn main() -> ExitCode {
+    exit::report(lcg::run(config::parse("lcg")))
+}
@@ -0,0 +1,366 @@
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use clap::Parser;
+use crypto_utils::encoding::from_hex;
+use crypto_utils::log::{self, LogArgs};
+use crypto_utils::output::{self, OutputArgs};
+use crypto_utils::text;
+use tracing::{debug, trace};
+
+use crate::error::Error;
+
+// The assignment: ten ciphertexts and the target, all under the same key
+const CIPHERTEXTS: [&str; 11] = [
+    concat!("315c4eeaa8b5f8aaf9174145bf43e1784b8fa00dc71d885a804e5ee9fa40",
+            "b16349c146fb778cdf2d3aff021dfff5b403b510d0d0455468aeb98622b1",
+            "37dae857553ccd8883a7bc37520e06e515d22c954eba5025b8cc57ee5941",
+            "8ce7dc6bc41556bdb36bbca3e8774301fbcaa3b83b220809560987815f65",
+            "286764703de0f3d524400a19b159610b11ef3e"),
+    concat!("234c02ecbbfbafa3ed18510abd11fa724fcda2018a1a8342cf064bbde548",
+            "b12b07df44ba7191d9606ef4081ffde5ad46a5069d9f7f543bedb9c861bf",
+            "29c7e205132eda9382b0bc2c5c4b45f919cf3a9f1cb74151f6d551f4480c",
+            "82b2cb24cc5b028aa76eb7b4ab24171ab3cdadb8356f"),
+    concat!("32510ba9a7b2bba9b8005d43a304b5714cc0bb0c8a34884dd91304b8ad40",
+            "b62b07df44ba6e9d8a2368e51d04e0e7b207b70b9b8261112bacb6c866a2",
+            "32dfe257527dc29398f5f3251a0d47e503c66e935de81230b59b7afb5f41",
+            "afa8d661cb"),
+    concat!("32510ba9aab2a8a4fd06414fb517b5605cc0aa0dc91a8908c2064ba8ad5e",
+            "a06a029056f47a8ad3306ef5021eafe1ac01a81197847a5c68a1b78769a3",
+            "7bc8f4575432c198ccb4ef63590256e305cd3a9544ee4160ead45aef5204",
+            "89e7da7d835402bca670bda8eb775200b8dabbba246b130f040d8ec6447e",
+            "2c767f3d30ed81ea2e4c1404e1315a1010e7229be6636aaa"),
+    concat!("3f561ba9adb4b6ebec54424ba317b564418fac0dd35f8c08d31a1fe9e24f",
+            "e56808c213f17c81d9607cee021dafe1e001b21ade877a5e68bea88d61b9",
+            "3ac5ee0d562e8e9582f5ef375f0a4ae20ed86e935de81230b59b73fb4302",
+            "cd95d770c65b40aaa065f2a5e33a5a0bb5dcaba43722130f042f8ec85b7c",
+            "2070"),
+    concat!("32510bfbacfbb9befd54415da243e1695ecabd58c519cd4bd2061bbde24e",
+            "b76a19d84aba34d8de287be84d07e7e9a30ee714979c7e1123a8bd9822a3",
+            "3ecaf512472e8e8f8db3f9635c1949e640c621854eba0d79eccf52ff1112",
+            "84b4cc61d11902aebc66f2b2e436434eacc0aba938220b084800c2ca4e69",
+            "3522643573b2c4ce35050b0cf774201f0fe52ac9f26d71b6cf61a711cc22",
+            "9f77ace7aa88a2f19983122b11be87a59c355d25f8e4"),
+    concat!("32510bfbacfbb9befd54415da243e1695ecabd58c519cd4bd90f1fa6ea5b",
+            "a47b01c909ba7696cf606ef40c04afe1ac0aa8148dd066592ded9f8774b5",
+            "29c7ea125d298e8883f5e9305f4b44f915cb2bd05af51373fd9b4af51103",
+            "9fa2d96f83414aaaf261bda2e97b170fb5cce2a53e675c154c0d96815969",
+            "34777e2275b381ce2e40582afe67650b13e72287ff2270abcf73bb028932",
+            "836fbdecfecee0a3b894473c1bbeb6b4913a536ce4f9b13f1efff71ea313",
+            "c8661dd9a4ce"),
+    concat!("315c4eeaa8b5f8bffd11155ea506b56041c6a00c8a08854dd21a4bbde54c",
+            "e56801d943ba708b8a3574f40c00fff9e00fa1439fd0654327a3bfc860b9",
+            "2f89ee04132ecb9298f5fd2d5e4b45e40ecc3b9d59e9417df7c95bba410e",
+            "9aa2ca24c5474da2f276baa3ac325918b2daada43d6712150441c2e04f65",
+            "65517f317da9d3"),
+    concat!("271946f9bbb2aeadec111841a81abc300ecaa01bd8069d5cc91005e9fe4a",
+            "ad6e04d513e96d99de2569bc5e50eeeca709b50a8a987f4264edb6896fb5",
+            "37d0a716132ddc938fb0f836480e06ed0fcd6e9759f40462f9cf57f45641",
+            "86a2c1778f1543efa270bda5e933421cbe88a4a52222190f471e9bd15f65",
+            "2b653b7071aec59a2705081ffe72651d08f822c9ed6d76e48b63ab15d020",
+            "8573a7eef027"),
+    concat!("466d06ece998b7a2fb1d464fed2ced7641ddaa3cc31c9941cf110abbf409",
+            "ed39598005b3399ccfafb61d0315fca0a314be138a9f32503bedac8067f0",
+            "3adbf3575c3b8edc9ba7f537530541ab0f9f3cd04ff50d66f1d559ba520e",
+            "89a2cb2a83"),
+    concat!("32510ba9babebbbefd001547a810e67149caee11d945cd7fc81a05e9f85a",
+            "ac650e9052ba6a8cd8257bf14d13e6f0a803b54fde9e77472dbff89d71b5",
+            "7bddef121336cb85ccb8f3315f4b52e301d16e9f52f904"),
+];
+
+// Bytes that crib dragging accepts as plaintext
+const TEXT_PUNCTUATION: &[u8] = b" .,;:'\"!?-()";
+
+// A space XORed with a letter flips its case, and two letters XOR to
+// something below 0x40, so c_i ^ c_j being a letter (or 0, for two spaces)
+// at a position suggests that one of the two plaintexts has a space there.
+// Counts the other ciphertexts that agree for ciphertext i at pos.
+fn space_votes(ciphertexts: &[Vec<u8>], i: usize, pos: usize) -> (usize, usize) {
+    let mut votes = 0;
+    let mut others = 0;
+    for (j, c) in ciphertexts.iter().enumerate() {
+        if j == i || pos >= c.len() {
+            continue;
+        }
+        others += 1;
+        let x = ciphertexts[i][pos] ^ c[pos];
+        if x == 0 || x.is_ascii_alphabetic() {
+            votes += 1;
+        }
+    }
+    (votes, others)
+}
+
+// Recovers the key byte at every position where some ciphertext looks like
+// a space to more than the given fraction of the others (and at least two)
+fn build_key(ciphertexts: &[Vec<u8>], threshold: f64) -> Vec<Option<u8>> {
+    let keylen = ciphertexts.iter().map(Vec::len).max().unwrap_or(0);
+    let mut key = vec![None; keylen];
+
+    for (pos, k) in key.iter_mut().enumerate() {
+        let best = (0..ciphertexts.len())
+            .filter(|&i| pos < ciphertexts[i].len())
+            .map(|i| (space_votes(ciphertexts, i, pos), i))
+            .max_by_key(|&((votes, _), _)| votes);
+        if let Some(((votes, others), i)) = best {
+            if votes >= 2 && votes as f64 > threshold * others as f64 {
+                *k = Some(ciphertexts[i][pos] ^ b' ');
+            }
+            trace!(pos, ciphertext = i + 1, votes, others, "Best guess for a space");
+        }
+    }
+    debug!(known = key.iter().flatten().count(), len = keylen, "Recovered key bytes from spaces");
+    key
+}
+
+// The plaintext with _ where the key byte is unknown and . for bytes that
+// aren't printable
+fn decode_ciphertext(key: &[Option<u8>], target: &[u8]) -> String {
+    target.iter()
+        .zip(key)
+        .map(|(c, k)| match k {
+            Some(k) if (c ^ k).is_ascii_graphic() || c ^ k == b' ' => char::from(c ^ k),
+            Some(_) => '.',
+            None => '_',
+        })
+        .collect()
+}
+
+fn is_text(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || TEXT_PUNCTUATION.contains(&byte)
+}
+
+// Fixes the key so that ciphertext i decrypts to text at pos
+fn put(key: &mut [Option<u8>], ciphertext: &[u8], pos: usize, text: &[u8]) -> Result<(), String> {
+    if pos + text.len() > ciphertext.len() {
+        return Err(format!("the ciphertext is only {} bytes long", ciphertext.len()));
+    }
+    for (k, (c, p)) in key[pos..].iter_mut().zip(ciphertext[pos..].iter().zip(text)) {
+        *k = Some(c ^ p);
+    }
+    Ok(())
+}
+
+// Slides the crib over ciphertext i and reports every offset at which the
+// key it implies decrypts every other ciphertext to text
+fn drag(ciphertexts: &[Vec<u8>], i: usize, crib: &[u8]) {
+    let c = &ciphertexts[i];
+    if crib.is_empty() || crib.len() > c.len() {
+        return;
+    }
+    for pos in 0..=c.len() - crib.len() {
+        let key: Vec<u8> = crib.iter().zip(&c[pos..]).map(|(p, c)| p ^ c).collect();
+        let others: Vec<String> = ciphertexts.iter().enumerate()
+            .filter(|&(j, other)| j != i && other.len() >= pos + crib.len())
+            .map(|(_, other)| {
+                other[pos..].iter().zip(&key).map(|(c, k)| char::from(c ^ k)).collect()
+            })
+            .collect();
+        if !others.is_empty() && others.iter().all(|text| text.bytes().all(is_text)) {
+            println!("{:>4}: {}", pos, others.join(" | "));
+        }
+    }
+}
+
+fn print_all(ciphertexts: &[Vec<u8>], key: &[Option<u8>]) {
+    for (i, c) in ciphertexts.iter().enumerate() {
+        text!("{:>3}: {}", i + 1, decode_ciphertext(key, c));
+    }
+}
+
+// The key in hex, with __ for unknown bytes
+fn key_hex(key: &[Option<u8>]) -> String {
+    key.iter().map(|k| match k {
+        Some(k) => format!("{:02x}", k),
+        None => "__".to_string(),
+    }).collect()
+}
+
+const HELP: &str = "\
+show              every plaintext under the current key
+drag TEXT         offsets where TEXT in the target gives text in all others
+drag N TEXT       the same for ciphertext N
+put N POS TEXT    fix the key so that ciphertext N has TEXT at POS
+unset POS [LEN]   forget LEN key bytes (default 1) from POS
+key               the key in hex, with __ for unknown bytes
+help              this list
+quit              leave (or end of input)
+Ciphertexts are numbered from 1 and positions from 0; the target is last.";
+
+// Interactive crib dragging, starting from the key found from the spaces
+fn crib_mode(ciphertexts: &[Vec<u8>], key: &mut [Option<u8>]) {
+    let target = ciphertexts.len() - 1;
+    print_all(ciphertexts, key);
+    println!("Type help for the commands");
+
+    let stdin = io::stdin();
+    loop {
+        print!("> ");
+        let _ = io::stdout().flush();
+        let mut line = String::new();
+        match stdin.lock().read_line(&mut line) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {},
+        }
+        let line = line.trim_end_matches(&['\r', '\n'][..]);
+        let (command, rest) = match line.find(' ') {
+            Some(i) => (&line[..i], &line[i + 1..]),
+            None => (line, ""),
+        };
+        let index = |arg: &str| match arg.parse::<usize>() {
+            Ok(n) if n >= 1 && n <= ciphertexts.len() => Ok(n - 1),
+            _ => Err(format!("no ciphertext {}", arg)),
+        };
+
+        let result = match command {
+            "" => Ok(()),
+            "show" => {
+                print_all(ciphertexts, key);
+                Ok(())
+            },
+            "drag" => {
+                // A leading number picks the ciphertext; anything else is
+                // part of the crib
+                let mut words = rest.splitn(2, ' ');
+                match (words.next().map(index), words.next()) {
+                    (Some(Ok(i)), Some(crib)) => drag(ciphertexts, i, crib.as_bytes()),
+                    _ => drag(ciphertexts, target, rest.as_bytes()),
+                }
+                Ok(())
+            },
+            "put" => {
+                let mut words = rest.splitn(3, ' ');
+                match (words.next(), words.next().map(str::parse::<usize>), words.next()) {
+                    (Some(n), Some(Ok(pos)), Some(text)) => index(n).and_then(|i| {
+                        put(key, &ciphertexts[i], pos, text.as_bytes())
+                    }).map(|()| print_all(ciphertexts, key)),
+                    _ => Err("usage: put N POS TEXT".to_string()),
+                }
+            },
+            "unset" => {
+                let words: Vec<_> = rest.split_whitespace().map(str::parse::<usize>).collect();
+                match words[..] {
+                    [Ok(pos)] | [Ok(pos), Ok(_)] if pos < key.len() => {
+                        let len = match words[..] {
+                            [_, Ok(len)] => len,
+                            _ => 1,
+                        };
+                        let end = key.len().min(pos + len);
+                        key[pos..end].iter_mut().for_each(|k| *k = None);
+                        print_all(ciphertexts, key);
+                        Ok(())
+                    },
+                    _ => Err("usage: unset POS [LEN]".to_string()),
+                }
+            },
+            "key" => {
+                println!("{}", key_hex(key));
+                Ok(())
+            },
+            "help" => {
+                println!("{}", HELP);
+                Ok(())
+            },
+            "quit" | "exit" => break,
+            other => Err(format!("unknown command: {} (try help)", other)),
+        };
+        if let Err(e) = result {
+            println!("Error: {}", e);
+        }
+    }
+}
+
+// Hex ciphertexts, one per line, skipping blank lines and # comments
+fn read_ciphertexts(path: &Path) -> Result<Vec<Vec<u8>>, Error> {
+    let contents = fs::read_to_string(path)
+        .map_err(|source| Error::Read { path: path.to_path_buf(), source })?;
+    contents.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            from_hex(line)
+                .map_err(|_| Error::Hex { path: path.to_path_buf(), line: line.to_string() })
+        })
+        .collect()
+}
+
+// Decrypts the target, by default the last ciphertext, from what the
+// spaces in all of them give away about the key
+#[derive(Parser)]
+#[command(name = "w1-many_time_pad", about = "Decrypts ciphertexts that reuse a stream cipher key",
+          long_about = "Decrypts ciphertexts that reuse a stream cipher key. A space XORed \
+          with a letter flips its case, so wherever most of the other ciphertexts XOR to \
+          letters, the key byte is the ciphertext XOR a space.")]
+pub struct Args {
+    #[arg(value_name = "FILE", help = "ciphertexts in hex, one per line (default: the \
+          assignment's)")]
+    file: Option<PathBuf>,
+    #[arg(short, long, value_name = "N", help = "number of the ciphertext to decrypt, \
+          from 1 (default: the last one)")]
+    target: Option<usize>,
+    #[arg(long, value_name = "F", default_value_t = 0.5, value_parser = parse_threshold,
+          help = "fraction of the other ciphertexts that must agree on a space")]
+    threshold: f64,
+    #[arg(short, long, help = "print every plaintext, not only the target")]
+    all: bool,
+    #[arg(short, long, conflicts_with = "json", help = "drag cribs interactively after \
+          recovering what the spaces give away")]
+    crib: bool,
+    #[command(flatten)]
+    log: LogArgs,
+    #[command(flatten)]
+    output: OutputArgs,
+}
+
+fn parse_threshold(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(t) if (0.0..1.0).contains(&t) => Ok(t),
+        _ => Err("must be in [0, 1)".to_string()),
+    }
+}
+
+pub fn run(args: Args) -> Result<(), Error> {
+    log::init(&args.log);
+    output::init("many-time-pad", &args.output);
+    output::finish(decrypt(&args))
+}
+
+fn decrypt(args: &Args) -> Result<(), Error> {
+    output::parameter("file", args.file.as_ref().map(|path| path.display().to_string()));
+    output::parameter("threshold", args.threshold);
+    // The assignment's ciphertexts are the default
+    let mut ciphertexts = match &args.file {
+        Some(path) => read_ciphertexts(path)?,
+        None => CIPHERTEXTS.iter().map(|c| from_hex(c).unwrap()).collect(),
+    };
+    if ciphertexts.len() < 3 {
+        return Err(Error::TooFewCiphertexts);
+    }
+    // The target goes last
+    if let Some(n) = args.target {
+        if n < 1 || n > ciphertexts.len() {
+            return Err(Error::Target(n));
+        }
+        let target = ciphertexts.remove(n - 1);
+        ciphertexts.push(target);
+    }
+    output::parameter("target", args.target.unwrap_or(ciphertexts.len()));
+
+    let mut key = build_key(&ciphertexts, args.threshold);
+    output::result("key", key_hex(&key));
+    if args.crib {
+        crib_mode(&ciphertexts, &mut key);
+    } else if args.all {
+        print_all(&ciphertexts, &key);
+        let plaintexts: Vec<String> = ciphertexts.iter()
+            .map(|c| decode_ciphertext(&key, c))
+            .collect();
+        output::result("plaintexts", plaintexts);
+    } else {
+        let plaintext = decode_ciphertext(&key, ciphertexts.last().unwrap());
+        text!("{}", plaintext);
+        output::result("plaintext", plaintext);
+    }
+    Ok(())
+}
@@ -0,0 +1,37 @@
+use std::io;
+use std::path::PathBuf;
+
+use crypto_utils::exit::{self, Status};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Could not read {}: {source}", path.display())]
+    Read { path: PathBuf, source: io::Error },
+    #[error("Invalid hex in {}: {line}", path.display())]
+    Hex { path: PathBuf, line: String },
+    #[error("Need at least 3 ciphertexts under the same key")]
+    TooFewCiphertexts,
+    #[error("Invalid value for --target: {0}")]
+    Target(usize),
+    #[error("Not an output: {0}")]
+    Output(i128),
+    #[error("A truncated LCG needs its parameters")]
+    TruncatedNeedsParameters,
+    #[error("Recovering the {0} needs at least {1} outputs")]
+    TooFewOutputs(&'static str, usize),
+    #[error("{0} states fit these outputs; give more of them")]
+    Ambiguous(usize),
+    #[error("No LCG with a modulus up to 2^63 outputs these")]
+    NoLcg,
+}
+
+impl Status for Error {
+    fn exit_code(&self) -> u8 {
+        match self {
+            Error::Read { .. } => exit::IO,
+            Error::Ambiguous(_) | Error::NoLcg => exit::FAILED,
+            _ => exit::USAGE,
+        }
+    }
+}
@@ -0,0 +1,351 @@
+use clap::Parser;
+use crypto_utils::log::{self, LogArgs};
+use crypto_utils::output::{self, json, OutputArgs};
+use crypto_utils::random::{self, RandomArgs};
+use crypto_utils::text;
+use rand::Rng;
+use tracing::{debug, trace};
+
+use crate::error::Error;
+
+// java.util.Random: a 48-bit state, of which nextInt() returns the top 32
+// bits as a signed int
+const JAVA: Lcg = Lcg { a: 0x5DEECE66D, c: 0xB, m: 1 << 48 };
+const JAVA_SHIFT: u32 = 16;
+
+// Keeps every product of two numbers below the modulus within an i128
+const MAX_MODULUS: i128 = 1 << 63;
+
+// Brute force over the dropped bits stays under a few billion candidates
+const MAX_SHIFT: u32 = 32;
+
+// Multipliers to try at most when the step between outputs shares a factor
+// with the modulus
+const MAX_CANDIDATES: i128 = 1 << 16;
+
+// Recovering an unknown modulus needs a few gcds to be reliable
+const MIN_OUTPUTS: usize = 6;
+
+// Outputs that all share a residue leave the recovered modulus off by a
+// small factor, tried up to this
+const MAX_COFACTOR: i128 = 1000;
+
+const DEFAULT_PREDICT: usize = 5;
+
+const DEMO_OUTPUTS: usize = 10;
+
+// The state s goes to a s + c mod m at every step
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Lcg {
+    a: i128,
+    c: i128,
+    m: i128,
+}
+
+impl Lcg {
+    fn next(&self, state: i128) -> i128 {
+        (self.a * state + self.c).rem_euclid(self.m)
+    }
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    if b == 0 { a.abs() } else { gcd(b, a % b) }
+}
+
+fn mod_inverse(a: i128, m: i128) -> Option<i128> {
+    let (mut r0, mut r1) = (a.rem_euclid(m), m);
+    let (mut x0, mut x1) = (1, 0);
+    while r1 != 0 {
+        let q = r0 / r1;
+        (r0, r1) = (r1, r0 - q * r1);
+        (x0, x1) = (x1, x0 - q * x1);
+    }
+    if r0 == 1 { Some(x0.rem_euclid(m)) } else { None }
+}
+
+// The differences t_i = x_{i+1} - x_i of full outputs satisfy t_{i+1} = a t_i
+// mod m, so every t_{i+2} t_i - t_{i+1}^2 is a multiple of m, and a few of
+// them rarely have a larger common factor unless the outputs do
+fn recover_modulus(outputs: &[i128]) -> i128 {
+    let t: Vec<i128> = outputs.windows(2).map(|w| w[1] - w[0]).collect();
+    t.windows(3).map(|w| w[2] * w[0] - w[1] * w[1]).fold(0, gcd)
+}
+
+// Every LCG modulo m that generates the full outputs, from a t_i = t_{i+1}
+// mod m for a step t_i that shares few factors with m
+fn recover_params(outputs: &[i128], m: i128) -> Vec<Lcg> {
+    for w in outputs.windows(3) {
+        let t0 = (w[1] - w[0]).rem_euclid(m);
+        let t1 = (w[2] - w[1]).rem_euclid(m);
+        let g = gcd(t0, m);
+        if t1 % g != 0 {
+            return Vec::new();
+        }
+        if g > MAX_CANDIDATES {
+            continue;
+        }
+        let step = m / g;
+        let a0 = (t1 / g) * mod_inverse(t0 / g, step).unwrap() % step;
+        return (0..g)
+            .map(|k| {
+                let a = a0 + k * step;
+                Lcg { a, c: (outputs[1] - a * outputs[0]).rem_euclid(m), m }
+            })
+            .filter(|lcg| generates(lcg, 0, outputs[0], outputs))
+            .collect();
+    }
+    Vec::new()
+}
+
+// Whether the LCG from state outputs the outputs, each one the state
+// without its shift low bits
+fn generates(lcg: &Lcg, shift: u32, mut state: i128, outputs: &[i128]) -> bool {
+    outputs.iter().all(|&x| {
+        let ok = state >> shift == x;
+        state = lcg.next(state);
+        ok
+    })
+}
+
+// Every state that outputs the outputs, trying all values of the low bits
+// the first output doesn't show
+fn recover_states(lcg: &Lcg, shift: u32, outputs: &[i128]) -> Vec<i128> {
+    (0..1i128 << shift)
+        .map(|low| outputs[0] << shift | low)
+        .take_while(|&state| state < lcg.m)
+        .filter(|&state| generates(lcg, shift, state, outputs))
+        .collect()
+}
+
+// The LCG behind the outputs and its state after the last one, from
+// whatever is known of its parameters. Of the moduli that fit, the
+// largest wins.
+fn recover(outputs: &[i128], m: Option<i128>, ac: Option<(i128, i128)>, shift: u32)
+           -> Result<(Lcg, i128), Error> {
+    let moduli: Vec<i128> = match m {
+        Some(m) => vec![m],
+        None if shift > 0 => return Err(Error::TruncatedNeedsParameters),
+        None if outputs.len() < MIN_OUTPUTS => {
+            return Err(Error::TooFewOutputs("modulus", MIN_OUTPUTS));
+        },
+        None => {
+            let multiple = recover_modulus(outputs);
+            debug!(multiple, "Recovered a multiple of the modulus");
+            (1..=MAX_COFACTOR).filter(|k| multiple % k == 0).map(|k| multiple / k).collect()
+        },
+    };
+    let max_state = outputs.iter().max().map_or(0, |&x| x << shift);
+
+    for m in moduli.into_iter().filter(|&m| m > max_state && m <= MAX_MODULUS) {
+        let lcgs = match ac {
+            Some((a, c)) => vec![Lcg { a, c, m }],
+            None if shift > 0 => return Err(Error::TruncatedNeedsParameters),
+            None if outputs.len() < 3 => return Err(Error::TooFewOutputs("multiplier", 3)),
+            None => recover_params(outputs, m),
+        };
+        trace!(m, candidates = lcgs.len(), "Trying a modulus");
+        let mut fits: Vec<(Lcg, i128)> = lcgs.iter()
+            .flat_map(|lcg| recover_states(lcg, shift, outputs).into_iter().map(move |s| (*lcg, s)))
+            .collect();
+        // Multipliers that fit and differ by a multiple of m / g, with g
+        // dividing every step, make the same sequence from the same state
+        fits.dedup_by_key(|&mut (_, first)| first);
+        debug!(m, states = fits.len(), "States that fit the outputs");
+        match fits[..] {
+            [] => continue,
+            [(lcg, first)] => {
+                let state = (0..outputs.len()).fold(first, |s, _| lcg.next(s));
+                return Ok((lcg, state));
+            },
+            _ => return Err(Error::Ambiguous(fits.len())),
+        }
+    }
+    Err(Error::NoLcg)
+}
+
+// The next n outputs from state
+fn predict(lcg: &Lcg, shift: u32, mut state: i128, n: usize) -> Vec<i128> {
+    (0..n)
+        .map(|_| {
+            let x = state >> shift;
+            state = lcg.next(state);
+            x
+        })
+        .collect()
+}
+
+// As strings in JSON too, since they may not fit in a double
+fn strings(outputs: &[i128], show: fn(i128) -> String) -> Vec<String> {
+    outputs.iter().map(|&x| show(x)).collect()
+}
+
+fn join(outputs: &[i128], show: fn(i128) -> String) -> String {
+    strings(outputs, show).join(" ")
+}
+
+fn show_unsigned(x: i128) -> String {
+    x.to_string()
+}
+
+// As Java prints an int
+fn show_java(x: i128) -> String {
+    (x as u32 as i32).to_string()
+}
+
+// Against generators with secret seeds: one with secret parameters that
+// outputs its whole state, and java.util.Random
+fn demo(n: usize) {
+    let mut rng = random::rng();
+    let m = rng.gen_range(1i128 << 31..1 << 32);
+    let lcg = Lcg { a: rng.gen_range(2..m), c: rng.gen_range(0..m), m };
+    let all = predict(&lcg, 0, rng.gen_range(0..m), DEMO_OUTPUTS + n);
+    let (outputs, future) = all.split_at(DEMO_OUTPUTS);
+    text!("An LCG with secret parameters output: {}", join(outputs, show_unsigned));
+    let recovered = recover(outputs, None, None, 0);
+    match &recovered {
+        Ok((found, state)) => {
+            text!("  m = {}, a = {}, c = {}", found.m, found.a, found.c);
+            text!("  predicted: {}", join(&predict(found, 0, *state, n), show_unsigned));
+        },
+        Err(e) => text!("  {}", e),
+    }
+    text!("  actual:    {}", join(future, show_unsigned));
+    output::result("lcg", json!({
+        "outputs": strings(outputs, show_unsigned),
+        "recovered": recovered.as_ref().ok().map(|(found, state)| json!({
+            "m": found.m.to_string(),
+            "a": found.a.to_string(),
+            "c": found.c.to_string(),
+            "predicted": strings(&predict(found, 0, *state, n), show_unsigned),
+        })),
+        "error": recovered.as_ref().err().map(ToString::to_string),
+        "actual": strings(future, show_unsigned),
+    }));
+
+    let all = predict(&JAVA, JAVA_SHIFT, rng.gen_range(0..JAVA.m), 3 + n);
+    let (outputs, future) = all.split_at(3);
+    text!("java.util.Random's nextInt() output: {}", join(outputs, show_java));
+    let recovered = recover(outputs, Some(JAVA.m), Some((JAVA.a, JAVA.c)), JAVA_SHIFT);
+    match &recovered {
+        Ok((_, state)) => {
+            text!("  next state: {:#014x}", state);
+            text!("  predicted: {}", join(&predict(&JAVA, JAVA_SHIFT, *state, n), show_java));
+        },
+        Err(e) => text!("  {}", e),
+    }
+    text!("  actual:    {}", join(future, show_java));
+    output::result("java", json!({
+        "outputs": strings(outputs, show_java),
+        "recovered": recovered.as_ref().ok().map(|(_, state)| json!({
+            "next_state": state.to_string(),
+            "predicted": strings(&predict(&JAVA, JAVA_SHIFT, *state, n), show_java),
+        })),
+        "error": recovered.as_ref().err().map(ToString::to_string),
+        "actual": strings(future, show_java),
+    }));
+}
+
+// Decimal or, with 0x, hex
+fn parse_number(s: &str) -> Result<i128, String> {
+    match s.strip_prefix("0x") {
+        Some(hex) => i128::from_str_radix(hex, 16),
+        None => s.parse(),
+    }.map_err(|_| "not a number".to_string())
+}
+
+fn parse_shift(s: &str) -> Result<u32, String> {
+    match parse_number(s)? {
+        shift if (0..=MAX_SHIFT as i128).contains(&shift) => Ok(shift as u32),
+        _ => Err(format!("must be at most {}", MAX_SHIFT)),
+    }
+}
+
+#[derive(Parser)]
+#[command(name = "lcg", about = "Predicts a linear congruential generator from its outputs",
+          long_about = "Recovers the state of a linear congruential generator from its \
+          consecutive OUTPUTs and predicts the next ones. Unknown parameters are recovered \
+          from full outputs; a truncated LCG needs them all. Without outputs, runs a demo.")]
+pub struct Args {
+    #[arg(value_name = "OUTPUT", value_parser = parse_number, allow_negative_numbers = true,
+          help = "consecutive outputs, in decimal or 0x hex")]
+    outputs: Vec<i128>,
+    #[arg(short, long, value_name = "M", value_parser = parse_number,
+          help = "modulus, up to 2^63 (default: recovered)")]
+    modulus: Option<i128>,
+    #[arg(short = 'a', long, value_name = "A", value_parser = parse_number, requires = "increment",
+          help = "multiplier (default: recovered)")]
+    multiplier: Option<i128>,
+    #[arg(short = 'c', long, value_name = "C", value_parser = parse_number,
+          requires = "multiplier", help = "increment (default: recovered)")]
+    increment: Option<i128>,
+    #[arg(short, long, value_name = "BITS", default_value_t = 0, value_parser = parse_shift,
+          help = "low bits of the state missing from each output")]
+    shift: u32,
+    #[arg(short, long, conflicts_with_all = ["modulus", "multiplier", "increment", "shift"],
+          help = "the outputs are from java.util.Random's nextInt()")]
+    java: bool,
+    #[arg(short = 'n', long, value_name = "N", default_value_t = DEFAULT_PREDICT,
+          help = "number of outputs to predict")]
+    predict: usize,
+    #[command(flatten)]
+    log: LogArgs,
+    #[command(flatten)]
+    output: OutputArgs,
+    #[command(flatten)]
+    random: RandomArgs,
+}
+
+pub fn run(args: Args) -> Result<(), Error> {
+    log::init(&args.log);
+    output::init("lcg", &args.output);
+    random::init(&args.random);
+    if args.outputs.is_empty() {
+        output::parameter("demo", true);
+        output::parameter("predict", args.predict);
+        demo(args.predict);
+        return output::finish(Ok(()));
+    }
+    output::finish(crack(&args))
+}
+
+fn crack(args: &Args) -> Result<(), Error> {
+    let n = args.predict;
+    let show_option = |x: Option<i128>| x.map(show_unsigned);
+    output::parameter("outputs", strings(&args.outputs, show_unsigned));
+    output::parameter("modulus", show_option(args.modulus));
+    output::parameter("multiplier", show_option(args.multiplier));
+    output::parameter("increment", show_option(args.increment));
+    output::parameter("shift", args.shift);
+    output::parameter("java", args.java);
+    output::parameter("predict", n);
+
+    let (m, ac, shift, show): (_, _, _, fn(i128) -> String) = if args.java {
+        (Some(JAVA.m), Some((JAVA.a, JAVA.c)), JAVA_SHIFT, show_java)
+    } else {
+        let ac = args.multiplier.zip(args.increment);
+        (args.modulus, ac, args.shift, show_unsigned)
+    };
+
+    // Outputs only reduce modulo their range once it is known, so that
+    // Java's negative ints read as their top bits
+    let range = m.map(|m| ((m - 1) >> shift) + 1);
+    let outputs: Vec<i128> = args.outputs.iter()
+        .map(|&x| match range {
+            Some(range) => Ok(x.rem_euclid(range)),
+            None if x >= 0 => Ok(x),
+            None => Err(Error::Output(x)),
+        })
+        .collect::<Result<_, _>>()?;
+
+    let (lcg, state) = recover(&outputs, m, ac, shift)?;
+    let predicted = predict(&lcg, shift, state, n);
+    text!("m = {}, a = {}, c = {}", lcg.m, lcg.a, lcg.c);
+    text!("Next state: {}", state);
+    text!("Next outputs: {}", join(&predicted, show));
+    output::result("m", lcg.m.to_string());
+    output::result("a", lcg.a.to_string());
+    output::result("c", lcg.c.to_string());
+    output::result("next_state", state.to_string());
+    output::result("predicted", strings(&predicted, show));
+    Ok(())
+}
@@ -0,0 +1,9 @@
+extern crate clap;
+extern crate crypto_utils;
+extern crate rand;
+extern crate thiserror;
+extern crate tracing;
+
+pub mod cli;
+pub mod error;
+pub mod lcg;
@@ -0,0 +1,11 @@
+extern crate crypto_utils;
+extern crate w2_aes;
+
+use std::process::ExitCode;
+
+use crypto_utils::{config, exit};
+use w2_aes::cbc_mac_forgery;
+
+fn main() -> ExitCode {
+    exit::report(cbc_mac_forgery::run(config::parse("cbc-mac-forgery")))
+}
@@ -0,0 +1,11 @@
+extern crate crypto_utils;
+extern crate w2_aes;
+
+use std::process::ExitCode;
+
+use crypto_utils::{config, exit};
+use w2_aes::ecb_penguin;
+
+fn main() -> ExitCode {
+    exit::report(ecb_penguin::run(config::parse("ecb-penguin")))
+}
@@ -0,0 +1,129 @@
+use clap::Parser;
+use crypto_utils::log::{self, LogArgs};
+use crypto_utils::output::{self, json, OutputArgs};
+use crypto_utils::random::{self, RandomArgs};
+use crypto_utils::secret::{self, SecretKey};
+use crypto_utils::text;
+use tracing::debug;
+
+use crate::error::Error;
+use crate::{cbc_decrypt, cbc_encrypt, gen_iv, show, BLOCK};
+
+const PREFIX: &str = "comment1=cooking%20MCs;userdata=";
+const SUFFIX: &str = ";comment2=%20like%20a%20pound%20of%20bacon";
+
+const PAYLOAD: &str = ";admin=true;";
+
+// Issues cookies of key=value pairs separated by ;, encrypted in CBC mode
+// under a key only it knows, around user data it escapes
+struct CookieServer {
+    key: SecretKey<BLOCK>,
+    prefix: String,
+}
+
+impl CookieServer {
+    fn new(prefix: &str) -> CookieServer {
+        CookieServer { key: secret::random_secret_key(), prefix: prefix.to_string() }
+    }
+
+    fn cookie(&self, userdata: &str) -> Vec<u8> {
+        let userdata = userdata.replace(';', "%3B").replace('=', "%3D");
+        let cookie = format!("{}{}{}", self.prefix, userdata, SUFFIX);
+        cbc_encrypt(&self.key, &gen_iv(), cookie.as_bytes())
+    }
+
+    // Only for showing what the attack did
+    fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>, Error> {
+        cbc_decrypt(&self.key, ciphertext)
+    }
+
+    fn is_admin(&self, ciphertext: &[u8]) -> Result<bool, Error> {
+        Ok(String::from_utf8_lossy(&self.decrypt(ciphertext)?)
+            .split(';')
+            .any(|pair| pair == "admin=true"))
+    }
+}
+
+// Prints what the server makes of a cookie, and returns it for the report
+fn inspect(server: &CookieServer, ciphertext: &[u8]) -> Result<(String, bool), Error> {
+    let (cookie, admin) = (show(&server.decrypt(ciphertext)?), server.is_admin(ciphertext)?);
+    text!("  cookie: {}", cookie);
+    text!("  admin: {}", admin);
+    Ok((cookie, admin))
+}
+
+// The user data that puts a variant of the payload at the start of a block,
+// with the payload's ; and = changed in their lowest bit so they aren't
+// escaped, and where that block starts in the cookie. Unless the payload
+// lands in the first block, whose previous block is the IV, a block of
+// filler comes first so that garbling it on decryption spares the prefix.
+fn attack_userdata(prefix_len: usize) -> (String, usize) {
+    let align = (BLOCK - prefix_len % BLOCK) % BLOCK;
+    let sacrifice = if prefix_len + align == 0 { 0 } else { BLOCK };
+    let disguised: String = PAYLOAD.chars()
+        .map(|c| if c == ';' || c == '=' { (c as u8 ^ 1) as char } else { c })
+        .collect();
+    let userdata = format!("{}{}", "A".repeat(align + sacrifice), disguised);
+    (userdata, prefix_len + align + sacrifice)
+}
+
+#[derive(Parser)]
+#[command(name = "cbc-bitflip", about = "Forges an admin cookie by flipping CBC ciphertext bits",
+          long_about = "Injects \";admin=true;\" into a CBC-encrypted cookie by flipping bits of \
+          the ciphertext, or of the IV if the payload lands in the first block.")]
+pub struct Args {
+    #[arg(short, long, value_name = "TEXT", default_value = PREFIX,
+          help = "what the cookie starts with, before the user data; try \"\" to flip the IV")]
+    prefix: String,
+    #[command(flatten)]
+    log: LogArgs,
+    #[command(flatten)]
+    output: OutputArgs,
+    #[command(flatten)]
+    random: RandomArgs,
+}
+
+pub fn run(args: Args) -> Result<(), Error> {
+    log::init(&args.log);
+    output::init("cbc-bitflip", &args.output);
+    random::init(&args.random);
+    output::finish(forge(&args.prefix))
+}
+
+fn forge(prefix: &str) -> Result<(), Error> {
+    output::parameter("prefix", prefix);
+    let server = CookieServer::new(prefix);
+
+    // Asking for the payload outright gets it escaped
+    let honest = server.cookie(PAYLOAD);
+    text!("Sent {:?}", PAYLOAD);
+    let (cookie, admin) = inspect(&server, &honest)?;
+    output::result("honest", json!({ "sent": PAYLOAD, "cookie": cookie, "admin": admin }));
+
+    let (userdata, start) = attack_userdata(prefix.len());
+    debug!(prefix = prefix.len(), start, "Padded the user data to flip from a block boundary");
+    let mut ciphertext = server.cookie(&userdata);
+    text!("Sent {:?}", userdata);
+    let (cookie, admin) = inspect(&server, &ciphertext)?;
+    output::result("disguised", json!({ "sent": userdata, "cookie": cookie, "admin": admin }));
+
+    // Plaintext block i is the decryption of ciphertext block i XORed with
+    // the block before it, the IV for i = 0, which comes first in the
+    // ciphertext: flipping a bit there flips the same bit of the plaintext.
+    // The flipped block itself decrypts to garbage, unless it is the IV.
+    let flipped = &userdata.as_bytes()[userdata.len() - PAYLOAD.len()..];
+    for (i, (a, b)) in flipped.iter().zip(PAYLOAD.as_bytes()).enumerate() {
+        ciphertext[start + i] ^= a ^ b;
+    }
+    let target = if start == 0 { "the IV" } else { "a ciphertext block" };
+    text!("Flipped bytes {}..{} of the ciphertext, in {}", start, start + PAYLOAD.len(), target);
+    let (cookie, admin) = inspect(&server, &ciphertext)?;
+    output::result("flipped", json!({
+        "start": start,
+        "end": start + PAYLOAD.len(),
+        "in_iv": start == 0,
+        "cookie": cookie,
+        "admin": admin,
+    }));
+    Ok(())
+}
@@ -0,0 +1,121 @@
+use std::sync::Mutex;
+
+use clap::Parser;
+use crypto_utils::ct::ct_eq;
+use crypto_utils::encoding::to_hex;
+use crypto_utils::log::{self, LogArgs};
+use crypto_utils::oracle::{Oracle, OracleError};
+use crypto_utils::output::{self, OutputArgs};
+use crypto_utils::random::{self, RandomArgs};
+use crypto_utils::secret::{self, SecretKey};
+use crypto_utils::text;
+
+use crate::error::Error;
+use crate::{cbc_mac, show, xor, BLOCK};
+
+const FIRST: &str = "to=bob&amount=10";
+const SECOND: &str = "to=eve&amount=99;memo=thank you!";
+
+// Tags any message of whole blocks with raw CBC-MAC under a key only it
+// knows, and remembers what it tagged
+struct MacOracle {
+    key: SecretKey<BLOCK>,
+    tagged: Mutex<Vec<Vec<u8>>>,
+}
+
+impl MacOracle {
+    fn new() -> MacOracle {
+        MacOracle { key: secret::random_secret_key(), tagged: Mutex::new(Vec::new()) }
+    }
+
+    fn verify(&self, message: &[u8], tag: &[u8]) -> bool {
+        cbc_mac(&self.key, message).is_ok_and(|t| ct_eq(&t, tag))
+    }
+
+    fn has_tagged(&self, message: &[u8]) -> bool {
+        self.tagged.lock().unwrap().iter().any(|m| m == message)
+    }
+}
+
+impl Oracle for MacOracle {
+    type Response = Vec<u8>;
+
+    fn query(&self, message: &[u8]) -> Result<Vec<u8>, OracleError> {
+        let tag = cbc_mac(&self.key, message).map_err(|e| OracleError::Rejected(e.to_string()))?;
+        self.tagged.lock().unwrap().push(message.to_vec());
+        Ok(tag)
+    }
+}
+
+// The tag of first is the chaining value after it, so appending second
+// with its first block XORed with that tag puts the chain back where
+// second alone starts, from a zero IV: the result has second's tag
+fn splice(first: &[u8], first_tag: &[u8], second: &[u8]) -> Vec<u8> {
+    let mut forged = first.to_vec();
+    forged.extend(xor(&second[..BLOCK], first_tag));
+    forged.extend_from_slice(&second[BLOCK..]);
+    forged
+}
+
+fn parse_message(s: &str) -> Result<String, String> {
+    if s.is_empty() || !s.len().is_multiple_of(BLOCK) {
+        return Err(format!("must be a multiple of {} bytes long", BLOCK));
+    }
+    Ok(s.to_string())
+}
+
+#[derive(Parser)]
+#[command(name = "cbc-mac-forgery", about = "Forges a raw CBC-MAC tag from two tagged messages",
+          long_about = "Asks a raw CBC-MAC oracle for the tags of the messages FIRST and SECOND, \
+          each a multiple of 16 bytes long, and forges the tag of a message it never tagged from \
+          them.")]
+pub struct Args {
+    #[arg(value_name = "FIRST", requires = "second", value_parser = parse_message,
+          help = "the first message (default: a payment to bob)")]
+    first: Option<String>,
+    #[arg(value_name = "SECOND", value_parser = parse_message,
+          help = "the second message (default: a payment to eve)")]
+    second: Option<String>,
+    #[command(flatten)]
+    log: LogArgs,
+    #[command(flatten)]
+    output: OutputArgs,
+    #[command(flatten)]
+    random: RandomArgs,
+}
+
+pub fn run(args: Args) -> Result<(), Error> {
+    log::init(&args.log);
+    output::init("cbc-mac-forgery", &args.output);
+    random::init(&args.random);
+    let first = args.first.as_deref().unwrap_or(FIRST);
+    let second = args.second.as_deref().unwrap_or(SECOND);
+    output::finish(forge(first, second))
+}
+
+fn forge(first: &str, second: &str) -> Result<(), Error> {
+    output::parameter("first", first);
+    output::parameter("second", second);
+    let oracle = MacOracle::new();
+    let mut tags = Vec::new();
+    for message in &[first, second] {
+        let tag = oracle.query(message.as_bytes())?;
+        text!("Tag of {:?}: {}", message, to_hex(&tag));
+        tags.push(tag);
+    }
+    output::result("tags", tags.iter().map(|tag| to_hex(tag)).collect::<Vec<_>>());
+
+    let forged = splice(first.as_bytes(), &tags[0], second.as_bytes());
+    let (accepted, tagged) = (oracle.verify(&forged, &tags[1]), oracle.has_tagged(&forged));
+    text!("Forged message: {}", show(&forged));
+    text!("  hex: {}", to_hex(&forged));
+    text!("  tag: {}", to_hex(&tags[1]));
+    text!("  accepted: {}", accepted);
+    text!("  tagged by the oracle: {}", tagged);
+    output::result("forged", show(&forged));
+    output::result("forged_hex", to_hex(&forged));
+    output::result("tag", to_hex(&tags[1]));
+    output::result("accepted", accepted);
+    output::result("tagged_by_oracle", tagged);
+    Ok(())
+}
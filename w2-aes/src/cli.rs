@@ -0,0 +1,151 @@
+use std::convert::TryInto;
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use clap::Parser;
+use crypto_utils::encoding::{from_hex, to_hex};
+use crypto_utils::log::{self, LogArgs};
+use crypto_utils::output::{self, json, OutputArgs};
+use crypto_utils::random::{self, RandomArgs};
+use crypto_utils::secret::SecretKey;
+use crypto_utils::text;
+
+use crate::error::Error;
+use crate::{decrypt, encrypt, gen_iv, parse_key, Mode, BLOCK};
+
+// The assignment: (mode, key, ciphertext) with the IV prepended
+const PROBLEMS: [(Mode, &str, &str); 4] = [
+    (Mode::Cbc, "140b41b22a29beb4061bda66b6747e14",
+     concat!("4ca00ff4c898d61e1edbf1800618fb2828a226d160dad07883d04e008a7897ee",
+             "2e4b7465d5290d0c0e6c6822236e1daafb94ffe0c5da05d9476be028ad7c1d81")),
+    (Mode::Cbc, "140b41b22a29beb4061bda66b6747e14",
+     concat!("5b68629feb8606f9a6667670b75b38a5b4832d0f26e1ab7da33249de7d4afc48",
+             "e713ac646ace36e872ad5fb8a512428a6e21364b0c374df45503473c5242a253")),
+    (Mode::Ctr, "36f18357be4dbd77f050515c73fcf9f2",
+     concat!("69dda8455c7dd4254bf353b773304eec0ec7702330098ce7f7520d1cbbb20fc3",
+             "88d1b0adb5054dbd7370849dbf0b88d393f252e764f1f5f7ad97ef79d59ce29f",
+             "5f51eeca32eabedd9afa9329")),
+    (Mode::Ctr, "36f18357be4dbd77f050515c73fcf9f2",
+     concat!("770b80259ec33beb2561358a9f2dc617e46218c0a53cbeca695ae45faa8952aa",
+             "0e311bde9d4e01726d3184c34451")),
+];
+
+// Decrypts the assignment's ciphertexts, then checks that encryption
+// round-trips in both modes
+fn run_problems() {
+    let mut problems = Vec::new();
+    for (i, &(mode, key, ciphertext)) in PROBLEMS.iter().enumerate() {
+        let (key, ciphertext) = (parse_key(key).unwrap(), from_hex(ciphertext).unwrap());
+        let plaintext = decrypt(mode, &key, &ciphertext).unwrap();
+        let plaintext = String::from_utf8_lossy(&plaintext);
+        text!("Problem {} ({}): {:?}", i + 1, mode.name().to_uppercase(), plaintext);
+        problems.push(json!({ "mode": mode.name(), "plaintext": plaintext }));
+    }
+    output::result("problems", problems);
+
+    let key = parse_key(PROBLEMS[0].1).unwrap();
+    for &mode in &[Mode::Cbc, Mode::Ctr] {
+        let plaintext = format!("Hello {} mode!", mode.name().to_uppercase());
+        let ciphertext = encrypt(mode, &key, &gen_iv(), plaintext.as_bytes());
+        assert_eq!(decrypt(mode, &key, &ciphertext).unwrap(), plaintext.as_bytes());
+        text!("Round trip ({}): {:?}", mode.name().to_uppercase(), plaintext);
+    }
+    output::result("round_trips", true);
+}
+
+// An IV: one block in hex
+fn parse_block(s: &str) -> Result<[u8; BLOCK], String> {
+    from_hex(s.trim())
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| "must be 16 bytes in hex".to_string())
+}
+
+// Without a key, solves the assignment
+#[derive(Parser)]
+#[command(name = "w2-aes", about = "Encrypts and decrypts with AES-128 in CBC or CTR mode",
+          long_about = "Encrypts and decrypts with AES-128 in CBC or CTR mode. Ciphertexts are \
+          in hex, IV first; plaintexts are raw bytes unless --hex. Without a key, decrypts the \
+          assignment's ciphertexts.")]
+pub struct Args {
+    #[arg(value_name = "INPUT", requires = "key", help = "the plaintext or ciphertext")]
+    input: Option<String>,
+    #[arg(short, long, value_enum, default_value_t = Mode::Cbc,
+          help = "cbc, with PKCS#7 padding, or ctr")]
+    mode: Mode,
+    #[arg(short, long, help = "decrypt INPUT instead of encrypting it")]
+    decrypt: bool,
+    #[arg(short, long, value_name = "KEY", value_parser = parse_key, help = "AES-128 key in hex")]
+    key: Option<SecretKey<BLOCK>>,
+    #[arg(long, value_name = "IV", value_parser = parse_block, conflicts_with = "decrypt",
+          help = "IV for CBC, or initial counter block for CTR, in hex, when encrypting \
+          (default: random)")]
+    iv: Option<[u8; BLOCK]>,
+    #[arg(short, long, value_name = "FILE", requires = "key", conflicts_with = "input",
+          help = "read INPUT from FILE")]
+    file: Option<PathBuf>,
+    #[arg(short = 'x', long, help = "the plaintext is hex, not raw bytes")]
+    hex: bool,
+    #[command(flatten)]
+    log: LogArgs,
+    #[command(flatten)]
+    output: OutputArgs,
+    #[command(flatten)]
+    random: RandomArgs,
+}
+
+pub fn run(args: Args) -> Result<(), Error> {
+    log::init(&args.log);
+    output::init("aes", &args.output);
+    random::init(&args.random);
+    output::finish(encrypt_or_decrypt(&args))
+}
+
+fn encrypt_or_decrypt(args: &Args) -> Result<(), Error> {
+    let key = match &args.key {
+        Some(key) => key,
+        None if args.input.is_none() && args.file.is_none() => {
+            output::parameter("assignment", true);
+            run_problems();
+            return Ok(());
+        },
+        None => return Err(Error::MissingKey),
+    };
+    output::parameter("mode", args.mode.name());
+    output::parameter("decrypt", args.decrypt);
+    output::parameter("key", to_hex(&key[..]));
+    output::parameter("file", args.file.as_ref().map(|path| path.display().to_string()));
+
+    // Ciphertexts are always in hex; plaintexts are raw bytes unless --hex
+    let input = match (&args.file, &args.input) {
+        (Some(path), _) => fs::read(path)
+            .map_err(|source| Error::Read { path: path.clone(), source })?,
+        (None, Some(input)) => input.clone().into_bytes(),
+        (None, None) => return Err(Error::MissingInput),
+    };
+    let input = if args.decrypt || args.hex {
+        let text = String::from_utf8_lossy(&input);
+        from_hex(text.trim()).map_err(|_| Error::Hex)?
+    } else {
+        input
+    };
+
+    if args.decrypt {
+        let plaintext = decrypt(args.mode, key, &input)?;
+        if output::is_json() {
+            output::result("plaintext", String::from_utf8_lossy(&plaintext));
+            output::result("plaintext_hex", to_hex(&plaintext));
+        } else if args.hex {
+            println!("{}", to_hex(&plaintext));
+        } else {
+            io::stdout().write_all(&plaintext).map_err(Error::Output)?;
+        }
+    } else {
+        let iv = args.iv.unwrap_or_else(gen_iv);
+        let ciphertext = encrypt(args.mode, key, &iv, &input);
+        text!("{}", to_hex(&ciphertext));
+        output::result("ciphertext", to_hex(&ciphertext));
+    }
+    Ok(())
+}
@@ -0,0 +1,178 @@
+use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use clap::{Parser, Subcommand};
+use crypto_utils::encoding::{from_hex, to_hex};
+use crypto_utils::log::{self, LogArgs};
+use crypto_utils::output::{self, json, OutputArgs, Value};
+use crypto_utils::random::{self, RandomArgs};
+use crypto_utils::secret::{self, SecretKey};
+use crypto_utils::text;
+use tracing::debug;
+
+use crate::error::Error;
+use crate::{cbc_encrypt, ecb_encrypt, gen_iv, parse_key, BLOCK};
+
+// Where the pixels of a BMP image start, after its headers
+fn pixel_offset(bmp: &[u8]) -> Result<usize, &'static str> {
+    if bmp.len() < 14 || &bmp[..2] != b"BM" {
+        return Err("not a BMP image");
+    }
+    let offset = u32::from_le_bytes([bmp[10], bmp[11], bmp[12], bmp[13]]) as usize;
+    if offset > bmp.len() {
+        return Err("truncated BMP image");
+    }
+    Ok(offset)
+}
+
+// The image with its pixels swapped for the first bytes of their
+// encryption, which still views as an image of the same size
+fn with_pixels(bmp: &[u8], offset: usize, encrypted: &[u8]) -> Vec<u8> {
+    let mut image = bmp[..offset].to_vec();
+    image.extend_from_slice(&encrypted[..bmp.len() - offset]);
+    image
+}
+
+// How many blocks repeat an earlier one, out of how many
+fn repeated_blocks(ciphertext: &[u8]) -> (usize, usize) {
+    let mut seen = HashSet::new();
+    let blocks = ciphertext.chunks_exact(BLOCK);
+    let total = blocks.len();
+    let repeated = blocks.filter(|block| !seen.insert(*block)).count();
+    (repeated, total)
+}
+
+// The fraction of repeated blocks, and the verdict, as text and for the
+// report. Under CBC or CTR, n blocks look random and repeat with
+// probability about n^2 / 2^129, so a single repeat all but proves ECB;
+// this is the log2 of that chance.
+fn detect(ciphertext: &[u8]) -> (String, Value) {
+    let (repeated, total) = repeated_blocks(ciphertext);
+    let score = if total == 0 { 0.0 } else { repeated as f64 / total as f64 };
+    let verdict = if repeated == 0 {
+        "no repeated blocks, so probably not ECB".to_string()
+    } else {
+        let pairs = (total as f64) * (total as f64 - 1.0) / 2.0;
+        format!("ECB, or a 2^{:.0} fluke", pairs.log2() - 128.0)
+    };
+    let text = format!("{} of {} blocks repeated (score {:.3}): {}",
+                       repeated, total, score, verdict);
+    (text, json!({ "repeated": repeated, "blocks": total, "score": score, "ecb": repeated > 0 }))
+}
+
+fn encrypt_image(path: &Path, key: &SecretKey<BLOCK>) -> Result<(), Error> {
+    let bmp = fs::read(path).map_err(|source| Error::Read { path: path.to_path_buf(), source })?;
+    let offset = pixel_offset(&bmp)
+        .map_err(|reason| Error::Bmp { path: path.to_path_buf(), reason })?;
+    let pixels = &bmp[offset..];
+    debug!(offset, bytes = pixels.len(), "Found the pixels");
+    let mut stem = path.to_path_buf();
+    if stem.extension() == Some(OsStr::new("bmp")) {
+        stem.set_extension("");
+    }
+
+    let ecb = ecb_encrypt(key, pixels);
+    // Without the IV, so the encrypted pixels line up with the plain ones
+    let cbc = cbc_encrypt(key, &gen_iv(), pixels).split_off(BLOCK);
+    let mut images = Vec::new();
+    for (mode, ciphertext) in &[("ecb", ecb), ("cbc", cbc)] {
+        let mut out = stem.clone().into_os_string();
+        out.push(format!(".{}.bmp", mode));
+        let out = PathBuf::from(out);
+        fs::write(&out, with_pixels(&bmp, offset, ciphertext))
+            .map_err(|source| Error::Write { path: out.clone(), source })?;
+        let (verdict, detection) = detect(ciphertext);
+        text!("{}: {}", out.display(), verdict);
+        images.push(json!({
+            "mode": mode,
+            "file": out.display().to_string(),
+            "detection": detection,
+        }));
+    }
+    output::result("images", images);
+    Ok(())
+}
+
+// Each file is a ciphertext, or holds one in hex per line
+fn detect_files(paths: &[PathBuf], is_hex: bool) -> Result<(), Error> {
+    let files: Vec<String> = paths.iter().map(|path| path.display().to_string()).collect();
+    output::parameter("files", files.as_slice());
+    output::parameter("hex", is_hex);
+    let mut ciphertexts = Vec::new();
+    for (path, file) in paths.iter().zip(&files) {
+        let data = fs::read(path).map_err(|source| Error::Read { path: path.clone(), source })?;
+        if !is_hex {
+            let (verdict, detection) = detect(&data);
+            text!("{}: {}", file, verdict);
+            ciphertexts.push(json!({ "file": file, "line": null, "detection": detection }));
+            continue;
+        }
+        for (i, line) in String::from_utf8_lossy(&data).lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let ciphertext = from_hex(line.trim())
+                .map_err(|_| Error::HexLine { path: path.clone(), line: i + 1 })?;
+            let (verdict, detection) = detect(&ciphertext);
+            text!("{}:{}: {}", file, i + 1, verdict);
+            ciphertexts.push(json!({ "file": file, "line": i + 1, "detection": detection }));
+        }
+    }
+    output::result("ciphertexts", ciphertexts);
+    Ok(())
+}
+
+#[derive(Parser)]
+#[command(name = "ecb-penguin", about = "Encrypts a BMP image under ECB and CBC, or detects ECB",
+          long_about = "Encrypts the pixels of a BMP image under ECB and CBC, into \
+          IMAGE.ecb.bmp and IMAGE.cbc.bmp, or tells whether ciphertexts were encrypted under ECB \
+          by their repeated 16-byte blocks.",
+          subcommand_negates_reqs = true, args_conflicts_with_subcommands = true)]
+pub struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[arg(value_name = "IMAGE", required = true, help = "the BMP image to encrypt")]
+    image: Option<PathBuf>,
+    #[arg(short, long, value_name = "KEY", value_parser = parse_key,
+          help = "key in hex to encrypt with (default: random)")]
+    key: Option<SecretKey<BLOCK>>,
+    #[command(flatten)]
+    log: LogArgs,
+    #[command(flatten)]
+    output: OutputArgs,
+    #[command(flatten)]
+    random: RandomArgs,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    #[command(about = "Tells whether ciphertexts were encrypted under ECB")]
+    Detect {
+        #[arg(value_name = "FILE", required = true, help = "ciphertexts to look at")]
+        files: Vec<PathBuf>,
+        #[arg(short = 'x', long, help = "FILE holds one ciphertext in hex per line")]
+        hex: bool,
+    },
+}
+
+pub fn run(args: Args) -> Result<(), Error> {
+    log::init(&args.log);
+    match (args.command, args.image) {
+        (Some(Command::Detect { files, hex }), _) => {
+            output::init("ecb-penguin detect", &args.output);
+            random::init(&args.random);
+            output::finish(detect_files(&files, hex))
+        },
+        (None, Some(image)) => {
+            output::init("ecb-penguin", &args.output);
+            random::init(&args.random);
+            let key = args.key.unwrap_or_else(secret::random_secret_key);
+            output::parameter("image", image.display().to_string());
+            output::parameter("key", to_hex(&key[..]));
+            output::finish(encrypt_image(&image, &key))
+        },
+        (None, None) => unreachable!("clap requires an image"),
+    }
+}
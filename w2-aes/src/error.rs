@@ -0,0 +1,48 @@
+use std::io;
+use std::path::PathBuf;
+
+use crypto_utils::exit::{self, Status};
+use crypto_utils::oracle::OracleError;
+use crypto_utils::padding::PaddingError;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("CBC ciphertext must be an IV and at least one whole block")]
+    CbcLength,
+    #[error("CTR ciphertext must start with a 16-byte IV")]
+    CtrLength,
+    #[error("Invalid padding: {0}")]
+    Padding(#[from] PaddingError),
+    #[error("CBC-MAC message must be one or more whole blocks")]
+    MacLength,
+    #[error("The MAC oracle failed: {0}")]
+    Oracle(#[from] OracleError),
+    #[error("A key is required: pass --key KEY")]
+    MissingKey,
+    #[error("Pass the input either as an argument or with --file")]
+    MissingInput,
+    #[error("The input is not valid hex")]
+    Hex,
+    #[error("{}:{line}: not valid hex", path.display())]
+    HexLine { path: PathBuf, line: usize },
+    #[error("{}: {reason}", path.display())]
+    Bmp { path: PathBuf, reason: &'static str },
+    #[error("Could not read {}: {source}", path.display())]
+    Read { path: PathBuf, source: io::Error },
+    #[error("Could not write {}: {source}", path.display())]
+    Write { path: PathBuf, source: io::Error },
+    #[error("Could not write the plaintext: {0}")]
+    Output(io::Error),
+}
+
+impl Status for Error {
+    fn exit_code(&self) -> u8 {
+        match self {
+            Error::Padding(_) => exit::FAILED,
+            Error::Read { .. } | Error::Write { .. } | Error::Output(_) => exit::IO,
+            Error::Oracle(_) => exit::ORACLE,
+            _ => exit::USAGE,
+        }
+    }
+}
@@ -0,0 +1,180 @@
+extern crate aes;
+extern crate clap;
+extern crate crypto_utils;
+extern crate rand;
+extern crate thiserror;
+extern crate tracing;
+
+pub mod cbc_bitflip;
+pub mod cbc_mac_forgery;
+pub mod cli;
+pub mod ecb_penguin;
+pub mod error;
+
+use aes::cipher::generic_array::GenericArray;
+use aes::cipher::{BlockDecrypt, BlockEncrypt, KeyInit};
+use aes::Aes128;
+use clap::ValueEnum;
+use crypto_utils::padding;
+use crypto_utils::random;
+use crypto_utils::secret::{self, SecretKey};
+use rand::RngCore;
+
+use crate::error::Error;
+
+pub const BLOCK: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Mode {
+    Cbc,
+    Ctr,
+}
+
+impl Mode {
+    pub fn name(self) -> &'static str {
+        match self {
+            Mode::Cbc => "cbc",
+            Mode::Ctr => "ctr",
+        }
+    }
+}
+
+pub fn xor(a: &[u8], b: &[u8]) -> Vec<u8> {
+    a.iter().zip(b).map(|(a, b)| a ^ b).collect()
+}
+
+// Bytes as text, with . for non-printable ones
+pub fn show(bytes: &[u8]) -> String {
+    bytes.iter()
+        .map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' })
+        .collect()
+}
+
+fn cbc_encrypt_block(cipher: &Aes128, block: &[u8], prev_block: &[u8]) -> Vec<u8> {
+    let mut buf = GenericArray::clone_from_slice(&xor(block, prev_block));
+    cipher.encrypt_block(&mut buf);
+    buf.to_vec()
+}
+
+// The IV followed by the CBC encryption of the padded plaintext
+pub fn cbc_encrypt(key: &SecretKey<BLOCK>, iv: &[u8; BLOCK], plaintext: &[u8]) -> Vec<u8> {
+    let cipher = Aes128::new(GenericArray::from_slice(&key[..]));
+
+    let mut ciphertext = iv.to_vec();
+    for block in padding::pad(plaintext, BLOCK).chunks(BLOCK) {
+        let prev_block = ciphertext[ciphertext.len() - BLOCK..].to_vec();
+        ciphertext.extend(cbc_encrypt_block(&cipher, block, &prev_block));
+    }
+    ciphertext
+}
+
+fn cbc_decrypt_block(cipher: &Aes128, prev_block: &[u8], block: &[u8]) -> Vec<u8> {
+    let mut buf = GenericArray::clone_from_slice(block);
+    cipher.decrypt_block(&mut buf);
+    xor(&buf, prev_block)
+}
+
+pub fn cbc_decrypt(key: &SecretKey<BLOCK>, ciphertext: &[u8]) -> Result<Vec<u8>, Error> {
+    if ciphertext.len() < 2 * BLOCK || !ciphertext.len().is_multiple_of(BLOCK) {
+        return Err(Error::CbcLength);
+    }
+    let cipher = Aes128::new(GenericArray::from_slice(&key[..]));
+
+    let padded_msg: Vec<u8> = ciphertext.chunks(BLOCK)
+        .collect::<Vec<_>>()
+        .windows(2)
+        .flat_map(|pair| cbc_decrypt_block(&cipher, pair[0], pair[1]))
+        .collect();
+    Ok(padding::unpad(&padded_msg, BLOCK)?.to_vec())
+}
+
+// Every block of the padded plaintext encrypted on its own, so equal
+// blocks encrypt equally
+pub fn ecb_encrypt(key: &SecretKey<BLOCK>, plaintext: &[u8]) -> Vec<u8> {
+    let cipher = Aes128::new(GenericArray::from_slice(&key[..]));
+
+    padding::pad(plaintext, BLOCK)
+        .chunks(BLOCK)
+        .flat_map(|block| {
+            let mut buf = GenericArray::clone_from_slice(block);
+            cipher.encrypt_block(&mut buf);
+            buf.to_vec()
+        })
+        .collect()
+}
+
+// Raw CBC-MAC: the last block of the CBC encryption of the message with a
+// zero IV and no padding, so the message must be whole blocks
+pub fn cbc_mac(key: &SecretKey<BLOCK>, message: &[u8]) -> Result<Vec<u8>, Error> {
+    if message.is_empty() || !message.len().is_multiple_of(BLOCK) {
+        return Err(Error::MacLength);
+    }
+    let cipher = Aes128::new(GenericArray::from_slice(&key[..]));
+
+    Ok(message.chunks(BLOCK)
+        .fold(vec![0u8; BLOCK], |prev_block, block| cbc_encrypt_block(&cipher, block, &prev_block)))
+}
+
+// An AES-128 key in hex, for the --key options
+pub fn parse_key(s: &str) -> Result<SecretKey<BLOCK>, String> {
+    secret::key_from_hex(s).ok_or_else(|| "must be 16 bytes in hex".to_string())
+}
+
+pub fn gen_iv() -> [u8; BLOCK] {
+    let mut iv = [0u8; BLOCK];
+    random::rng().fill_bytes(&mut iv);
+    iv
+}
+
+// The counter of a BLOCK-byte IV
+fn bytes_to_u128(bytes: &[u8]) -> u128 {
+    let mut fixed = [0u8; BLOCK];
+    fixed.copy_from_slice(bytes);
+    u128::from_be_bytes(fixed)
+}
+
+// XORs the data with the encryptions of iv, iv + 1, ... as 128-bit
+// big-endian counters, wrapping around
+fn ctr_process(key: &SecretKey<BLOCK>, iv: u128, data: &[u8]) -> Vec<u8> {
+    let cipher = Aes128::new(GenericArray::from_slice(&key[..]));
+
+    data.chunks(BLOCK)
+        .enumerate()
+        .flat_map(|(i, block)| {
+            let mut buf = GenericArray::from(iv.wrapping_add(i as u128).to_be_bytes());
+            cipher.encrypt_block(&mut buf);
+            xor(&buf, block)
+        })
+        .collect()
+}
+
+// The IV followed by the CTR encryption of the plaintext, which needs no
+// padding
+pub fn ctr_encrypt(key: &SecretKey<BLOCK>, iv: &[u8; BLOCK], plaintext: &[u8]) -> Vec<u8> {
+    let mut ciphertext = iv.to_vec();
+    ciphertext.extend(ctr_process(key, u128::from_be_bytes(*iv), plaintext));
+    ciphertext
+}
+
+pub fn ctr_decrypt(key: &SecretKey<BLOCK>, ciphertext: &[u8]) -> Result<Vec<u8>, Error> {
+    if ciphertext.len() < BLOCK {
+        return Err(Error::CtrLength);
+    }
+    let (iv, data) = ciphertext.split_at(BLOCK);
+    Ok(ctr_process(key, bytes_to_u128(iv), data))
+}
+
+pub fn encrypt(mode: Mode, key: &SecretKey<BLOCK>, iv: &[u8; BLOCK], plaintext: &[u8])
+               -> Vec<u8> {
+    match mode {
+        Mode::Cbc => cbc_encrypt(key, iv, plaintext),
+        Mode::Ctr => ctr_encrypt(key, iv, plaintext),
+    }
+}
+
+pub fn decrypt(mode: Mode, key: &SecretKey<BLOCK>, ciphertext: &[u8]) -> Result<Vec<u8>, Error> {
+    match mode {
+        Mode::Cbc => cbc_decrypt(key, ciphertext),
+        Mode::Ctr => ctr_decrypt(key, ciphertext),
+    }
+}
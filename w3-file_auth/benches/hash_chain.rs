@@ -0,0 +1,66 @@
+extern crate criterion;
+extern crate w3_file_auth;
+
+use std::hint::black_box;
+use std::io::{self, Cursor};
+use std::thread;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use w3_file_auth::cli::{hash_blocks, sign_stream, verify_stream};
+
+fn show(size: usize) -> String {
+    if size >= 1 << 20 {
+        format!("{}M", size >> 20)
+    } else {
+        format!("{}K", size >> 10)
+    }
+}
+
+// Hashing the chain of blocks from the last one to the first, for files up
+// to the size of the assignment's video and for small and large blocks
+fn hash_chain(c: &mut Criterion) {
+    let mut group = c.benchmark_group("hash_chain");
+    for &size in &[64 << 10, 1 << 20, 16 << 20] {
+        let data: Vec<u8> = (0..size).map(|i| (i * 31 % 251) as u8).collect();
+        group.throughput(Throughput::Bytes(size as u64));
+        for &block_size in &[1 << 10, 64 << 10] {
+            let id = BenchmarkId::new(format!("{} blocks", show(block_size)), show(size));
+            group.bench_with_input(id, &data, |b, data| {
+                b.iter(|| hash_blocks(Cursor::new(black_box(data)), block_size))
+            });
+        }
+    }
+    group.finish();
+}
+
+// Verifying a signed file of the size of the assignment's video, with one
+// thread hashing the blocks and with one per CPU
+fn verify(c: &mut Criterion) {
+    let mut group = c.benchmark_group("verify");
+    let size = 16 << 20;
+    let data: Vec<u8> = (0..size).map(|i| (i * 31 % 251) as u8).collect();
+    group.throughput(Throughput::Bytes(size as u64));
+    let mut threads = vec![1, thread::available_parallelism().map_or(1, |n| n.get())];
+    threads.dedup();
+    for &block_size in &[1 << 10, 64 << 10] {
+        let hashes = hash_blocks(Cursor::new(&data), block_size).unwrap();
+        let mut signed = Vec::new();
+        sign_stream(&data[..], &mut signed, block_size, &hashes, |e| e, |e: io::Error| e).unwrap();
+        let h0 = hashes.last().unwrap();
+        for &threads in &threads {
+            let id = BenchmarkId::new(format!("{} blocks", show(block_size)),
+                                      format!("{} threads", threads));
+            group.bench_with_input(id, &signed, |b, signed| {
+                b.iter(|| {
+                    let mut output = Vec::with_capacity(size);
+                    verify_stream(black_box(&signed[..]), &mut output, block_size, h0, threads,
+                                  |e| e, |e: io::Error| e)
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, hash_chain, verify);
+criterion_main!(benches);
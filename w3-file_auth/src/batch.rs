@@ -0,0 +1,263 @@
+use std::cmp;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Instant;
+
+use clap::builder::RangedU64ValueParser;
+use clap::{CommandFactory, FromArgMatches, Parser};
+use crypto_utils::encoding::Base;
+use crypto_utils::interrupt;
+use crypto_utils::output::{self, json};
+use crypto_utils::text;
+use tracing::{debug, info};
+
+use crate::chain::Hash;
+use crate::cli::{default_threads, hash_blocks, parse_block_size, sign, verify, Files, Job,
+                 DEFAULT_BLOCK_SIZE};
+use crate::error::Error;
+use crate::multihash;
+
+// A manifest lists sign and verify commands, one per line, with the
+// arguments they take on the command line:
+//
+//     # The hashes the students handed in
+//     verify 03c08f4e... alice/video.signed alice/video.mp4
+//     verify 5be4ea4b... bob/video.signed bob/video.mp4 --block-size 4K
+//
+// Words are separated by whitespace, with no quoting. Blank lines and lines
+// starting with # are skipped, relative paths are relative to the
+// manifest, and commands without --block-size take the one of the batch.
+
+#[derive(clap::Args)]
+pub struct Args {
+    #[arg(value_name = "MANIFEST", help = "file of sign and verify commands, one per line")]
+    manifest: PathBuf,
+    #[arg(short, long, value_name = "SIZE", default_value = DEFAULT_BLOCK_SIZE,
+          value_parser = parse_block_size,
+          help = "size of the blocks of the commands without --block-size")]
+    block_size: usize,
+    #[arg(short, long, value_name = "N", value_parser = RangedU64ValueParser::<usize>::new().range(1..),
+          help = "files processed at once (default: one per CPU)")]
+    threads: Option<usize>,
+}
+
+// A line of the manifest
+#[derive(Parser)]
+#[command(no_binary_name = true, disable_help_subcommand = true)]
+struct Line {
+    #[command(subcommand)]
+    job: Job,
+}
+
+struct Entry {
+    line: usize,
+    job: Job,
+}
+
+impl Entry {
+    fn files(&self) -> &Files {
+        match &self.job {
+            Job::Sign { files } | Job::Verify { files, .. } => files,
+        }
+    }
+}
+
+// The commands of the manifest, all checked before any runs
+fn parse_manifest(args: &Args) -> Result<Vec<Entry>, Error> {
+    let path = &args.manifest;
+    let text = fs::read_to_string(path)
+        .map_err(|source| Error::Read { path: path.clone(), source })?;
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+
+    let mut parser = Line::command();
+    for name in &["sign", "verify"] {
+        parser = parser.mut_subcommand(name, |sub| {
+            sub.mut_arg("block_size", |arg| arg.default_value(args.block_size.to_string()))
+        });
+    }
+
+    let mut entries = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut job = parser.try_get_matches_from_mut(line.split_whitespace())
+            .and_then(|matches| Line::from_arg_matches(&matches))
+            .map(|line| line.job)
+            .map_err(|e| {
+                // clap's error on one line, without the usage after it
+                let message = e.to_string();
+                let message: Vec<&str> = message.lines()
+                    .take_while(|line| !line.is_empty())
+                    .map(str::trim)
+                    .collect();
+                Error::Manifest {
+                    path: path.clone(),
+                    line: i + 1,
+                    message: message.join(" ").trim_start_matches("error: ").to_string(),
+                }
+            })?;
+        let files = match &mut job {
+            Job::Sign { files } => files,
+            Job::Verify { files, checkpoint, .. } => {
+                if let Some(checkpoint) = checkpoint {
+                    *checkpoint = dir.join(&checkpoint);
+                }
+                files
+            },
+        };
+        files.input = dir.join(&files.input);
+        files.output = dir.join(&files.output);
+        entries.push(Entry { line: i + 1, job });
+    }
+    Ok(entries)
+}
+
+// h0 of a signed file, or nothing for a verified one
+fn execute(job: &Job) -> Result<Option<Hash>, Error> {
+    match job {
+        Job::Sign { files } => {
+            let read_err = |source| Error::Read { path: files.input.clone(), source };
+            let hashes = File::open(&files.input)
+                .and_then(|input| hash_blocks(input, files.block_size))
+                .map_err(read_err)?;
+            sign(&files.input, &files.output, files.block_size, &hashes)?;
+            Ok(hashes.last().copied())
+        },
+        Job::Verify { hash, files, checkpoint, threads } => {
+            let threads = threads.expect("set by run");
+            if !verify(&files.input, &files.output, files.block_size, hash, threads,
+                       checkpoint.as_deref())? {
+                return Err(Error::Unverified { path: files.input.clone() });
+            }
+            Ok(None)
+        },
+    }
+}
+
+// Line numbers with runs of consecutive ones shortened, e.g. 3, 5-7
+fn format_lines(lines: &[usize]) -> String {
+    let mut runs: Vec<(usize, usize)> = Vec::new();
+    for &line in lines {
+        match runs.last_mut() {
+            Some((_, last)) if *last + 1 == line => *last = line,
+            _ => runs.push((line, line)),
+        }
+    }
+    let runs: Vec<String> = runs.iter().map(|&(first, last)| match last - first {
+        0 => first.to_string(),
+        _ => format!("{}-{}", first, last),
+    }).collect();
+    runs.join(", ")
+}
+
+// On Ctrl-C, the commands that finished, and the ones to run again: their
+// outputs are removed
+fn on_interrupt(manifest: &Path, entries: &[Entry], finished: Arc<Mutex<Vec<usize>>>) {
+    let manifest = manifest.to_path_buf();
+    let lines: Vec<usize> = entries.iter().map(|entry| entry.line).collect();
+    interrupt::on_interrupt(move || {
+        let mut finished = finished.lock().unwrap().clone();
+        finished.sort_unstable();
+        eprintln!("{} of {} commands finished", finished.len(), lines.len());
+        output::result("finished", finished.clone());
+        let left: Vec<usize> = lines.iter().copied()
+            .filter(|line| finished.binary_search(line).is_err())
+            .collect();
+        if !left.is_empty() {
+            interrupt::resume(Some(format!("run the commands on lines {} of {}",
+                                           format_lines(&left), manifest.display())));
+        }
+    });
+}
+
+// Each thread takes the next entry until there are none left. The results
+// are in the order of the entries.
+fn execute_all(entries: &[Entry], threads: usize, finished: &Mutex<Vec<usize>>)
+               -> Vec<Result<Option<Hash>, Error>> {
+    let next = AtomicUsize::new(0);
+    let mut results: Vec<_> = thread::scope(|s| {
+        let workers: Vec<_> = (0..threads).map(|_| s.spawn(|| {
+            let mut done = Vec::new();
+            while let Some(entry) = entries.get(next.fetch_add(1, Ordering::Relaxed)) {
+                let result = execute(&entry.job);
+                debug!(line = entry.line, success = result.is_ok(), "Ran a command");
+                finished.lock().unwrap().push(entry.line);
+                done.push((entry.line, result));
+            }
+            done
+        })).collect();
+        workers.into_iter().flat_map(|worker| worker.join().unwrap()).collect()
+    });
+    results.sort_by_key(|(line, _)| *line);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+pub fn run(args: &Args, multibase: Option<Base>) -> Result<(), Error> {
+    output::parameter("manifest", args.manifest.display().to_string());
+    output::parameter("block_size", args.block_size);
+    let mut entries = parse_manifest(args)?;
+    let threads = args.threads
+        .unwrap_or_else(default_threads)
+        .min(entries.len())
+        .max(1);
+    output::parameter("threads", threads);
+    // Commands without --threads share the CPUs with the others running
+    for entry in &mut entries {
+        if let Job::Verify { threads: verify_threads @ None, .. } = &mut entry.job {
+            *verify_threads = Some(cmp::max(1, default_threads() / threads));
+        }
+    }
+
+    info!("Running {} commands on {} thread{}", entries.len(), threads,
+          if threads == 1 { "" } else { "s" });
+    let finished = Arc::new(Mutex::new(Vec::new()));
+    on_interrupt(&args.manifest, &entries, finished.clone());
+    let start = Instant::now();
+    let results = execute_all(&entries, threads, &finished);
+    output::timing("commands", start.elapsed());
+
+    let mut commands = Vec::new();
+    let mut failed = 0;
+    for (entry, result) in entries.iter().zip(&results) {
+        let files = entry.files();
+        let (command, hash) = match (&entry.job, result) {
+            (Job::Verify { hash, .. }, _) => ("verify", Some(hash)),
+            (Job::Sign { .. }, Ok(h0)) => ("sign", h0.as_ref()),
+            (Job::Sign { .. }, Err(_)) => ("sign", None),
+        };
+        match result {
+            Ok(Some(h0)) => text!("{}: signed {}, h0 {}", entry.line, files.input.display(),
+                                  multihash::format(h0, multibase)),
+            Ok(None) => text!("{}: verified {}", entry.line, files.input.display()),
+            Err(e) => {
+                failed += 1;
+                text!("{}: failed: {}", entry.line, e);
+            },
+        }
+        commands.push(json!({
+            "line": entry.line,
+            "command": command,
+            "input": files.input.display().to_string(),
+            "output": files.output.display().to_string(),
+            "block_size": files.block_size,
+            "hash": hash.map(|hash| multihash::format(hash, multibase)),
+            "success": result.is_ok(),
+            "error": result.as_ref().err().map(ToString::to_string),
+        }));
+    }
+    text!("{} of {} commands succeeded in {:.1?}", entries.len() - failed, entries.len(),
+          start.elapsed());
+    output::result("commands", commands);
+    output::result("succeeded", entries.len() - failed);
+    output::result("failed", failed);
+
+    if failed > 0 {
+        return Err(Error::Batch { failed, commands: entries.len() });
+    }
+    Ok(())
+}
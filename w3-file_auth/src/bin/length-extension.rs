@@ -0,0 +1,11 @@
+extern crate crypto_utils;
+extern crate w3_file_auth;
+
+use std::process::ExitCode;
+
+use crypto_utils::{config, exit};
+use w3_file_auth::length_extension;
+
+fn main() -> ExitCode {
+    exit::report(length_extension::run(config::parse("length-extension")))
+}
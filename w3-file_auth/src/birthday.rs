@@ -0,0 +1,254 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Instant;
+
+use clap::builder::RangedU64ValueParser;
+use clap::Parser;
+use crypto_utils::encoding::to_hex;
+use crypto_utils::log::{self, LogArgs};
+use crypto_utils::output::{self, json, OutputArgs};
+use crypto_utils::random::{self, RandomArgs};
+use crypto_utils::text;
+use rand::Rng;
+use sha2::{Digest, Sha256};
+use tracing::{debug, info, trace};
+
+use crate::error::Error;
+
+const DEFAULT_BITS: u32 = 32;
+
+// A trail with no distinguished point after this many times the expected
+// length is probably stuck in a cycle
+const MAX_TRAIL_FACTOR: u64 = 20;
+
+// The first bits bits of SHA-256 of x as 8 big-endian bytes. Its outputs
+// are valid inputs, so iterating it from any point eventually cycles, and
+// where the path enters the cycle two different inputs hash to the same
+// value.
+fn truncated(bits: u32, x: u64) -> u64 {
+    let digest = Sha256::digest(&x.to_be_bytes());
+    let mut first = [0u8; 8];
+    first.copy_from_slice(&digest[..8]);
+    u64::from_be_bytes(first) >> (64 - bits)
+}
+
+// Brent's cycle detection from x0: the cycle length is the first power of
+// two window in which the hare meets the tortoise. Then a second pointer
+// that many steps ahead meets the first one where the path joins the
+// cycle. None if x0 is on the cycle, as nothing joins it there. Counts the
+// hashes in evaluations.
+fn brent(bits: u32, x0: u64, evaluations: &mut u64) -> Option<(u64, u64)> {
+    let mut f = |x| {
+        *evaluations += 1;
+        truncated(bits, x)
+    };
+
+    let (mut power, mut lambda) = (1u64, 1u64);
+    let mut tortoise = x0;
+    let mut hare = f(x0);
+    while tortoise != hare {
+        if power == lambda {
+            tortoise = hare;
+            power *= 2;
+            lambda = 0;
+        }
+        hare = f(hare);
+        lambda += 1;
+    }
+
+    let mut tortoise = x0;
+    let mut hare = x0;
+    for _ in 0..lambda {
+        hare = f(hare);
+    }
+    if tortoise == hare {
+        return None;
+    }
+    loop {
+        let (next_tortoise, next_hare) = (f(tortoise), f(hare));
+        if next_tortoise == next_hare {
+            return Some((tortoise, hare));
+        }
+        tortoise = next_tortoise;
+        hare = next_hare;
+    }
+}
+
+fn find_brent(bits: u32) -> (u64, u64, u64) {
+    let mut rng = random::rng();
+    let mut evaluations = 0;
+    loop {
+        let x0 = rng.gen::<u64>() >> (64 - bits);
+        if let Some((a, b)) = brent(bits, x0, &mut evaluations) {
+            return (a, b, evaluations);
+        }
+        debug!(hashes = evaluations, "Started on the cycle; trying another start");
+    }
+}
+
+// A walk from start that reached a distinguished point after len steps
+#[derive(Debug, Clone, Copy)]
+struct Trail {
+    start: u64,
+    len: u64,
+}
+
+// Two trails ending at the same distinguished point merge somewhere: lined
+// up at the same distance from it, they step together until they do. None
+// if one starts on the other, so they never differ.
+fn merge(bits: u32, a: Trail, b: Trail, evaluations: &AtomicU64) -> Option<(u64, u64)> {
+    let mut count = 0;
+    let mut f = |x| {
+        count += 1;
+        truncated(bits, x)
+    };
+    let (mut long, short) = if a.len >= b.len { (a, b) } else { (b, a) };
+    for _ in 0..long.len - short.len {
+        long.start = f(long.start);
+    }
+    let (mut x, mut y) = (long.start, short.start);
+    let collision = if x == y {
+        None
+    } else {
+        loop {
+            let (next_x, next_y) = (f(x), f(y));
+            if next_x == next_y {
+                break Some((x, y));
+            }
+            x = next_x;
+            y = next_y;
+        }
+    };
+    evaluations.fetch_add(count, Ordering::Relaxed);
+    collision
+}
+
+// Parallel collision search with distinguished points: each thread walks
+// from random starts until a point whose top dp_bits bits are zero, and
+// only those endpoints are stored. Two walks ending at the same point have
+// merged on the way, giving a collision, in memory proportional to the
+// number of trails rather than of hashes.
+fn find_distinguished(bits: u32, dp_bits: u32, threads: usize) -> (u64, u64, u64) {
+    let max_len = MAX_TRAIL_FACTOR << dp_bits;
+    let trails: Mutex<HashMap<u64, Trail>> = Mutex::new(HashMap::new());
+    let found = AtomicBool::new(false);
+    let collision = Mutex::new(None);
+    let evaluations = AtomicU64::new(0);
+
+    thread::scope(|s| {
+        let (trails, found, collision, evaluations) = (&trails, &found, &collision, &evaluations);
+        for _ in 0..threads {
+            // Taken here rather than in the thread, so each gets the same
+            // stream in every run with the same seed
+            let mut rng = random::rng();
+            s.spawn(move || {
+                while !found.load(Ordering::Relaxed) {
+                    let start = rng.gen::<u64>() >> (64 - bits);
+                    let mut x = start;
+                    let mut len = 0;
+                    while len < max_len && (len == 0 || x >> (bits - dp_bits) != 0) {
+                        x = truncated(bits, x);
+                        len += 1;
+                    }
+                    evaluations.fetch_add(len, Ordering::Relaxed);
+                    if len == max_len {
+                        debug!(len, "Dropped a trail with no distinguished point");
+                        continue;
+                    }
+
+                    trace!(len, point = x, "Trail reached a distinguished point");
+                    let trail = Trail { start, len };
+                    let other = trails.lock().unwrap().insert(x, trail);
+                    if other.is_some() {
+                        debug!(point = x, "Two trails met at a distinguished point");
+                    }
+                    let pair = other.and_then(|other| merge(bits, other, trail, evaluations));
+                    if let Some(pair) = pair {
+                        *collision.lock().unwrap() = Some(pair);
+                        found.store(true, Ordering::Relaxed);
+                    }
+                }
+            });
+        }
+    });
+
+    let (a, b) = collision.into_inner().unwrap().unwrap();
+    (a, b, evaluations.into_inner())
+}
+
+#[derive(Parser)]
+#[command(name = "birthday", about = "Finds collisions on truncated SHA-256",
+          long_about = "Finds two 8-byte messages whose SHA-256 digests agree on their first BITS \
+          bits, in about 2^(BITS/2) hashes and next to no memory.")]
+pub struct Args {
+    #[arg(short, long, default_value_t = DEFAULT_BITS,
+          value_parser = clap::value_parser!(u32).range(2..=64),
+          help = "number of leading bits to collide")]
+    bits: u32,
+    #[arg(short, long, help = "search in parallel with distinguished points instead of Brent's \
+          cycle detection")]
+    parallel: bool,
+    #[arg(short, long, value_name = "N", value_parser = RangedU64ValueParser::<usize>::new().range(1..),
+          help = "threads for --parallel (default: one per CPU)")]
+    threads: Option<usize>,
+    #[arg(short, long, value_name = "N", help = "leading zero bits of a distinguished point for \
+          --parallel (default: BITS/4)")]
+    dp_bits: Option<u32>,
+    #[command(flatten)]
+    log: LogArgs,
+    #[command(flatten)]
+    output: OutputArgs,
+    #[command(flatten)]
+    random: RandomArgs,
+}
+
+pub fn run(args: Args) -> Result<(), Error> {
+    log::init(&args.log);
+    output::init("birthday", &args.output);
+    random::init(&args.random);
+    output::finish(collide(&args))
+}
+
+fn collide(args: &Args) -> Result<(), Error> {
+    let bits = args.bits;
+    let threads = args.threads
+        .unwrap_or_else(|| thread::available_parallelism().map_or(1, |n| n.get()));
+    let dp_bits = match args.dp_bits {
+        None => bits / 4,
+        Some(n) if n < bits => n,
+        Some(_) => return Err(Error::DpBits),
+    };
+    output::parameter("bits", bits);
+    output::parameter("parallel", args.parallel);
+    if args.parallel {
+        output::parameter("threads", threads);
+        output::parameter("dp_bits", dp_bits);
+    }
+
+    let start = Instant::now();
+    let (a, b, evaluations) = if args.parallel {
+        info!("Colliding the first {} bits of SHA-256 with {} thread{}, on points \
+               starting with {} zero bits", bits, threads, if threads == 1 { "" } else { "s" },
+              dp_bits);
+        find_distinguished(bits, dp_bits, threads)
+    } else {
+        info!("Colliding the first {} bits of SHA-256 with Brent's cycle detection", bits);
+        find_brent(bits)
+    };
+    debug!("{:.0} hashes/s", evaluations as f64 / start.elapsed().as_secs_f64());
+    text!("Found after {} hashes (2^{:.1}, against a birthday bound of 2^{}) in {:.1?}",
+          evaluations, (evaluations as f64).log2(), bits as f64 / 2.0, start.elapsed());
+    output::timing("search", start.elapsed());
+    output::result("hashes", evaluations);
+    let mut collision = Vec::new();
+    for x in &[a, b] {
+        let message = x.to_be_bytes();
+        let digest = Sha256::digest(&message);
+        text!("  SHA-256({}) = {}", to_hex(&message), to_hex(&digest));
+        collision.push(json!({ "message": to_hex(&message), "digest": to_hex(&digest) }));
+    }
+    output::result("collision", collision);
+    Ok(())
+}
@@ -0,0 +1,178 @@
+use core::fmt;
+use core::hint::black_box;
+use core::marker::PhantomData;
+
+pub use digest::Digest;
+use digest::generic_array::GenericArray;
+use sha2::Sha256;
+
+// The hash chain of signed files, with nothing from std, so that a device
+// can verify blocks as it receives them. A signed file is its blocks, each
+// followed by the hash of the next block with its own appended hash, and
+// h0 is the hash of the first of these.
+//
+// The tools sign with SHA-256, but the chain works with any digest::Digest,
+// such as a hash the hardware does: hash_block_with and
+// Verifier::with_digest take the digest as a type parameter, and the
+// functions without it are these for SHA-256.
+
+pub const HASH_SIZE: usize = 32;
+
+pub type Hash = [u8; HASH_SIZE];
+
+// A hash of the digest D
+pub type Output<D> = GenericArray<u8, <D as Digest>::OutputSize>;
+
+// The hash of a block with the hash of the block after it appended, if
+// there is one
+pub fn hash_block_with<D: Digest>(block: &[u8], next: Option<&[u8]>) -> Output<D> {
+    let mut hasher = D::new();
+    hasher.input(block);
+    if let Some(next) = next {
+        hasher.input(next);
+    }
+    hasher.result()
+}
+
+pub fn hash_block(block: &[u8], next: Option<&Hash>) -> Hash {
+    let mut hash = [0; HASH_SIZE];
+    hash.copy_from_slice(&hash_block_with::<Sha256>(block, next.map(|next| &next[..])));
+    hash
+}
+
+// Like crypto_utils::ct::ct_eq, which needs std
+fn ct_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | black_box(x ^ y)) == 0
+}
+
+// A verified block, without the hash that followed it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Block<'a> {
+    Next(&'a [u8]),
+    // Shorter than a block and a hash, so the file is verified
+    Last(&'a [u8]),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rejected {
+    // The block doesn't match the hash before it
+    Mismatch,
+    // Longer than a block and a hash
+    TooLong,
+    // After the last block or a rejected one
+    Ended,
+}
+
+impl fmt::Display for Rejected {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Rejected::Mismatch => "Block does not match its hash",
+            Rejected::TooLong => "Block is longer than a block and a hash",
+            Rejected::Ended => "Block comes after the end of the file",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum State<H> {
+    // Expecting a block with this hash
+    Next(H),
+    Verified,
+    Rejected,
+}
+
+// Checks the signed blocks of a file in order, starting from h0. A file of
+// whole blocks ends with an empty block, which must be passed in too.
+#[derive(Clone)]
+pub struct Verifier<D: Digest = Sha256> {
+    state: State<Output<D>>,
+    block_size: usize,
+    blocks: u64,
+    digest: PhantomData<D>,
+}
+
+// Digests need not implement Debug
+impl<D: Digest> fmt::Debug for Verifier<D> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Verifier")
+            .field("state", &self.state)
+            .field("block_size", &self.block_size)
+            .field("blocks", &self.blocks)
+            .finish()
+    }
+}
+
+impl Verifier {
+    pub fn new(h0: Hash, block_size: usize) -> Verifier {
+        Verifier::with_digest(h0.into(), block_size)
+    }
+
+    // Picks up after the given number of verified blocks, the last of which
+    // ended with next
+    pub fn resume(next: Hash, block_size: usize, blocks: u64) -> Verifier {
+        Verifier::resume_with_digest(next.into(), block_size, blocks)
+    }
+}
+
+impl<D: Digest> Verifier<D> {
+    pub fn with_digest(h0: Output<D>, block_size: usize) -> Verifier<D> {
+        Verifier::resume_with_digest(h0, block_size, 0)
+    }
+
+    pub fn resume_with_digest(next: Output<D>, block_size: usize, blocks: u64) -> Verifier<D> {
+        Verifier { state: State::Next(next), block_size, blocks, digest: PhantomData }
+    }
+
+    // The size of a signed block but the last
+    pub fn signed_size(&self) -> usize {
+        self.block_size.saturating_add(D::output_size())
+    }
+
+    // Blocks verified so far
+    pub fn blocks(&self) -> u64 {
+        self.blocks
+    }
+
+    // The hash the next block must have, until the last block is verified
+    // or one is rejected
+    pub fn expected(&self) -> Option<&Output<D>> {
+        match self.state {
+            State::Next(ref hash) => Some(hash),
+            State::Verified | State::Rejected => None,
+        }
+    }
+
+    // Whether the last block has been verified
+    pub fn is_verified(&self) -> bool {
+        self.state == State::Verified
+    }
+
+    pub fn verify<'a>(&mut self, signed: &'a [u8]) -> Result<Block<'a>, Rejected> {
+        self.verify_hashed(signed, &hash_block_with::<D>(signed, None))
+    }
+
+    // verify with hash_block_with::<D>(signed, None) computed already, such
+    // as by another thread
+    pub fn verify_hashed<'a>(&mut self, signed: &'a [u8], hashed: &[u8])
+            -> Result<Block<'a>, Rejected> {
+        let hash = match self.state {
+            State::Next(ref hash) => hash.clone(),
+            State::Verified | State::Rejected => return Err(Rejected::Ended),
+        };
+        self.state = State::Rejected;
+        if signed.len() > self.signed_size() {
+            return Err(Rejected::TooLong);
+        }
+        if !ct_eq(hashed, &hash) {
+            return Err(Rejected::Mismatch);
+        }
+        self.blocks += 1;
+        if signed.len() < self.signed_size() {
+            self.state = State::Verified;
+            return Ok(Block::Last(signed));
+        }
+        let (block, next) = signed.split_at(self.block_size);
+        self.state = State::Next(GenericArray::clone_from_slice(next));
+        Ok(Block::Next(block))
+    }
+}
@@ -0,0 +1,108 @@
+use std::convert::TryInto;
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crypto_utils::encoding::{from_hex, to_hex};
+use crypto_utils::output::{json, Value};
+use tracing::debug;
+
+use crate::chain::Hash;
+use crate::error::Error;
+
+// How far verify --checkpoint got through a signed file: the number of
+// blocks verified and the hash the next one must have. It is only good for
+// the file it was made for, the signed file of that size with that h0 and
+// block size, so that it can't be used to skip blocks of another file.
+#[derive(Debug)]
+pub struct Checkpoint {
+    path: PathBuf,
+    pub size: u64,
+    pub h0: Hash,
+    pub block_size: usize,
+    pub blocks: u64,
+    pub next: Hash,
+}
+
+fn hash(state: &Value, name: &str) -> Option<Hash> {
+    let hex = state.get(name)?.as_str()?;
+    from_hex(hex).ok()?.try_into().ok()
+}
+
+fn number(state: &Value, name: &str) -> Option<u64> {
+    state.get(name)?.as_u64()
+}
+
+impl Checkpoint {
+    // At the start of the signed file
+    pub fn new(path: &Path, size: u64, h0: Hash, block_size: usize) -> Checkpoint {
+        Checkpoint { path: path.to_path_buf(), size, h0, block_size, blocks: 0, next: h0 }
+    }
+
+    // The checkpoint saved at path, or None if there is none yet
+    pub fn load(path: &Path) -> Result<Option<Checkpoint>, Error> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(source) => return Err(Error::Read { path: path.to_path_buf(), source }),
+        };
+        let invalid = || Error::Checkpoint {
+            path: path.to_path_buf(),
+            reason: "is not a checkpoint of verify".to_string(),
+        };
+        let state: Value = text.parse().map_err(|_| invalid())?;
+        let checkpoint = Checkpoint {
+            path: path.to_path_buf(),
+            size: number(&state, "size").ok_or_else(invalid)?,
+            h0: hash(&state, "h0").ok_or_else(invalid)?,
+            block_size: number(&state, "block_size").and_then(|n| n.try_into().ok())
+                .ok_or_else(invalid)?,
+            blocks: number(&state, "blocks").ok_or_else(invalid)?,
+            next: hash(&state, "next").ok_or_else(invalid)?,
+        };
+        debug!(blocks = checkpoint.blocks, "Loaded the checkpoint");
+        Ok(Some(checkpoint))
+    }
+
+    // Fails unless the checkpoint was made for the signed file of this size
+    // with this h0 and block size
+    pub fn check(&self, size: u64, h0: &Hash, block_size: usize) -> Result<(), Error> {
+        let reason = if self.h0 != *h0 {
+            "was made for another h0"
+        } else if self.size != size {
+            "was made for a file of another size"
+        } else if self.block_size != block_size {
+            "was made with another block size"
+        } else if self.blocks.saturating_mul(block_size as u64 + h0.len() as u64) > size {
+            "goes past the end of the file"
+        } else {
+            return Ok(());
+        };
+        Err(Error::Checkpoint { path: self.path.clone(), reason: reason.to_string() })
+    }
+
+    // Written to a file beside it first and renamed over it, so that a
+    // crash while saving leaves the previous checkpoint
+    pub fn save(&self) -> Result<(), Error> {
+        let write_err = |source| Error::Write { path: self.path.clone(), source };
+        let state = json!({
+            "size": self.size,
+            "h0": to_hex(&self.h0),
+            "block_size": self.block_size,
+            "blocks": self.blocks,
+            "next": to_hex(&self.next),
+        });
+        let mut temp = OsString::from(self.path.as_os_str());
+        temp.push(".tmp");
+        fs::write(&temp, format!("{:#}\n", state)).map_err(write_err)?;
+        fs::rename(&temp, &self.path).map_err(write_err)?;
+        debug!(blocks = self.blocks, "Saved the checkpoint");
+        Ok(())
+    }
+
+    pub fn remove(&self) -> Result<(), Error> {
+        fs::remove_file(&self.path)
+            .map_err(|source| Error::Write { path: self.path.clone(), source })
+    }
+}
@@ -0,0 +1,569 @@
+use std::fs::{OpenOptions, File};
+use std::io;
+use std::io::prelude::*;
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::sync_channel;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use clap::builder::RangedU64ValueParser;
+use clap::{Parser, Subcommand};
+use crypto_utils::encoding::Base;
+use crypto_utils::interrupt::{self, Partial};
+use crypto_utils::log::{self, LogArgs};
+use crypto_utils::output::{self, OutputArgs};
+use crypto_utils::size::parse_size;
+use crypto_utils::text;
+use sha2::Sha256;
+use tracing::{debug, trace, warn};
+
+use crate::batch;
+use crate::chain::{hash_block_with, Block, Digest, Hash, Output, Verifier, HASH_SIZE};
+use crate::checkpoint::Checkpoint;
+use crate::error::Error;
+use crate::multihash;
+use crate::pipeline::{self, signed_blocks, Chunk, RevChunks};
+
+// The assignment's
+pub(crate) const DEFAULT_BLOCK_SIZE: &str = "1K";
+// Blocks are held in memory, so a bad --block-size can't exhaust it
+const MAX_BLOCK_SIZE: u64 = 1 << 30;
+// How often verify --checkpoint saves how far it got
+const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(1);
+
+pub type HashVec = Vec<Hash>;
+
+impl Files {
+    fn report(&self) {
+        output::parameter("input", self.input.display().to_string());
+        output::parameter("output", self.output.display().to_string());
+        output::parameter("block_size", self.block_size);
+    }
+}
+
+// The hashes of the blocks of input from the last to the first, so that
+// h0 comes last
+pub fn hash_blocks<R: Read + Seek + Send>(input: R, block_size: usize) -> io::Result<HashVec> {
+    let hashes = hash_blocks_with::<Sha256, R>(input, block_size)?;
+    Ok(hashes.into_iter().map(Into::into).collect())
+}
+
+// hash_blocks with the digest D. Each hash covers the next one, so the
+// blocks are hashed one after the other, while a thread reads the ones
+// before them.
+pub fn hash_blocks_with<D: Digest, R: Read + Seek + Send>(input: R, block_size: usize)
+        -> io::Result<Vec<Output<D>>> {
+    let chunks = RevChunks::new(input, block_size, pipeline::chunk_size(block_size))?;
+    thread::scope(|s| {
+        let (tx, rx) = sync_channel(1);
+        s.spawn(move || {
+            for chunk in chunks {
+                let failed = chunk.is_err();
+                if tx.send(chunk).is_err() || failed {
+                    return;
+                }
+            }
+        });
+
+        let mut hashes: Vec<Output<D>> = Vec::new();
+        for chunk in rx {
+            let chunk = chunk?;
+            // The last block comes alone
+            if hashes.is_empty() {
+                hashes.push(hash_block_with::<D>(&chunk, None));
+                continue;
+            }
+            for block in chunk.rchunks_exact(block_size) {
+                let hash = hash_block_with::<D>(block, hashes.last().map(|next| &next[..]));
+                hashes.push(hash);
+            }
+        }
+        Ok(hashes)
+    })
+}
+
+// Writes the blocks of input to output, each with the hash of the next
+// block appended, from the hashes of hash_blocks or hash_blocks_with. A
+// thread reads the blocks while the signed ones are written.
+pub fn sign_stream<R, W, E, H>(input: R, mut output: W, block_size: usize, hashes: &[H],
+                               read_err: impl Fn(io::Error) -> E, write_err: impl Fn(io::Error) -> E)
+                               -> Result<(), E>
+    where R: Read + Send, W: Write, H: AsRef<[u8]>
+{
+    // A block per hash, the last without one, as h0 is not included
+    let len = hashes.len() as u64 * block_size as u64;
+    let mut next_hashes = hashes.iter().rev().skip(1);
+
+    thread::scope(|s| {
+        let (tx, rx) = sync_channel(1);
+        s.spawn(move || pipeline::read_chunks(input.take(len), pipeline::chunk_size(block_size),
+                                              &[tx]));
+
+        let mut signed = Vec::new();
+        for chunk in rx {
+            let chunk = chunk.map_err(&read_err)?;
+            signed.clear();
+            for block in chunk.chunks(block_size) {
+                signed.extend_from_slice(block);
+                if let Some(hash) = next_hashes.next() {
+                    signed.extend_from_slice(hash.as_ref());
+                }
+            }
+            output.write_all(&signed).map_err(&write_err)?;
+        }
+        Ok(())
+    })
+}
+
+pub(crate) fn sign(input_path: &Path, output_path: &Path, block_size: usize, hashes: &HashVec)
+        -> Result<(), Error> {
+    let read_err = |source| Error::Read { path: input_path.to_path_buf(), source };
+    let write_err = |source| Error::Write { path: output_path.to_path_buf(), source };
+    let output_file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(output_path)
+        .map_err(write_err)?;
+    let _partial = Partial::new(output_path);
+
+    let input_file = File::open(input_path).map_err(read_err)?;
+    sign_stream(input_file, output_file, block_size, hashes, read_err, write_err)
+}
+
+// Checks the signed blocks of input against h0 with threads hashing them,
+// writing each block without its hash to output once it is verified
+pub fn verify_stream<R, W, E>(input: R, output: W, block_size: usize, hash: &Hash, threads: usize,
+                              read_err: impl Fn(io::Error) -> E, write_err: impl Fn(io::Error) -> E)
+                              -> Result<bool, E>
+    where R: Read + Send, W: Write
+{
+    let mut verifier = Verifier::new(*hash, block_size);
+    verify_from(input, output, &mut verifier, threads, read_err, write_err, |_, _| Ok(()))
+}
+
+// verify_stream from wherever verifier is, with any digest and input at
+// the next block, calling verified whenever blocks but the last have been
+// written. Unlike when signing, each signed block can be hashed on its own,
+// so a thread reads chunks of them and hands them to the workers in turn,
+// and they are checked and written in the same order.
+pub fn verify_from<D, R, W, E, F>(input: R, mut output: W, verifier: &mut Verifier<D>,
+                                  threads: usize, read_err: impl Fn(io::Error) -> E,
+                                  write_err: impl Fn(io::Error) -> E, mut verified: F)
+                                  -> Result<bool, E>
+    where D: Digest, R: Read + Send, W: Write, F: FnMut(&Verifier<D>, &mut W) -> Result<(), E>
+{
+    let signed_size = verifier.signed_size();
+    let chunk_size = pipeline::chunk_size(signed_size);
+    let workers = pipeline::workers(threads, chunk_size);
+    debug!(workers, chunk_size, "Verifying");
+
+    thread::scope(|s| {
+        let (to_workers, from_workers): (Vec<_>, Vec<_>) = (0..workers).map(|_| {
+            let (to_worker, chunks) = sync_channel::<Chunk>(1);
+            let (hashed, from_worker) = sync_channel(1);
+            s.spawn(move || {
+                for chunk in chunks {
+                    let chunk = chunk.map(|chunk| {
+                        let last = chunk.len() < chunk_size;
+                        let hashes: Vec<u8> = signed_blocks(&chunk, signed_size, last)
+                            .flat_map(|signed| hash_block_with::<D>(signed, None))
+                            .collect();
+                        (chunk, hashes)
+                    });
+                    if hashed.send(chunk).is_err() {
+                        return;
+                    }
+                }
+            });
+            (to_worker, from_worker)
+        }).unzip();
+        s.spawn(move || pipeline::read_chunks(input, chunk_size, &to_workers));
+
+        // The last chunk ends with the last block, which ends verifying
+        let mut data = Vec::new();
+        for from_worker in from_workers.iter().cycle() {
+            let (chunk, hashes) = from_worker.recv().expect("a chunk up to the last block")
+                .map_err(&read_err)?;
+            let last = chunk.len() < chunk_size;
+            let hashes = hashes.chunks(D::output_size());
+            data.clear();
+            for (signed, hash) in signed_blocks(&chunk, signed_size, last).zip(hashes) {
+                let block = verifier.blocks();
+                let verified = match verifier.verify_hashed(signed, hash) {
+                    Ok(Block::Next(block_data)) => {
+                        data.extend_from_slice(block_data);
+                        continue;
+                    },
+                    Ok(Block::Last(block_data)) => {
+                        data.extend_from_slice(block_data);
+                        true
+                    },
+                    Err(_) if signed.is_empty() => {
+                        warn!(block, "File ends before the last block");
+                        false
+                    },
+                    Err(rejected) => {
+                        warn!(block, "{}", rejected);
+                        false
+                    },
+                };
+                output.write_all(&data).map_err(&write_err)?;
+                return Ok(verified);
+            }
+            trace!(blocks = verifier.blocks(), "Blocks verified");
+            output.write_all(&data).map_err(&write_err)?;
+            verified(verifier, &mut output)?;
+        }
+        unreachable!("the workers take turns forever")
+    })
+}
+
+pub(crate) fn verify(input_path: &Path, output_path: &Path, block_size: usize, hash: &Hash,
+                     threads: usize, checkpoint: Option<&Path>) -> Result<bool, Error> {
+    if let Some(checkpoint) = checkpoint {
+        return verify_checkpointed(input_path, output_path, block_size, hash, threads, checkpoint);
+    }
+    let read_err = |source| Error::Read { path: input_path.to_path_buf(), source };
+    let write_err = |source| Error::Write { path: output_path.to_path_buf(), source };
+    let input_file = File::open(input_path).map_err(read_err)?;
+
+    let output_file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(output_path)
+        .map_err(write_err)?;
+    let _partial = Partial::new(output_path);
+
+    verify_stream(input_file, output_file, block_size, hash, threads, read_err, write_err)
+}
+
+// verify, picking up from the checkpoint at state_path if there is one, and
+// saving one there as it goes. The output is kept on failure and Ctrl-C,
+// cut back to what the checkpoint covers when resuming. The checkpoint is
+// only removed once the whole file is verified.
+fn verify_checkpointed(input_path: &Path, output_path: &Path, block_size: usize, hash: &Hash,
+                       threads: usize, state_path: &Path) -> Result<bool, Error> {
+    let read_err = |source| Error::Read { path: input_path.to_path_buf(), source };
+    let write_err = |source| Error::Write { path: output_path.to_path_buf(), source };
+    let mut input_file = File::open(input_path).map_err(read_err)?;
+    let size = input_file.metadata().map_err(read_err)?.len();
+
+    let (mut checkpoint, mut output_file) = match Checkpoint::load(state_path)? {
+        Some(checkpoint) => {
+            checkpoint.check(size, hash, block_size)?;
+            // The hash that ended the last verified block, as a last check
+            // that this is the file the checkpoint was made for
+            let signed_size = block_size as u64 + HASH_SIZE as u64;
+            if checkpoint.blocks > 0 {
+                let mut last = [0; HASH_SIZE];
+                input_file.seek(SeekFrom::Start(checkpoint.blocks * signed_size - HASH_SIZE as u64))
+                    .and_then(|_| input_file.read_exact(&mut last))
+                    .map_err(read_err)?;
+                if last != checkpoint.next {
+                    return Err(Error::Checkpoint {
+                        path: state_path.to_path_buf(),
+                        reason: format!("does not match {}", input_path.display()),
+                    });
+                }
+            }
+            let verified = checkpoint.blocks * block_size as u64;
+            let output_file = OpenOptions::new().write(true).open(output_path).map_err(write_err)?;
+            if output_file.metadata().map_err(write_err)?.len() < verified {
+                return Err(Error::Checkpoint {
+                    path: state_path.to_path_buf(),
+                    reason: format!("is ahead of {}", output_path.display()),
+                });
+            }
+            output_file.set_len(verified).map_err(write_err)?;
+            (&output_file).seek(SeekFrom::End(0)).map_err(write_err)?;
+            input_file.seek(SeekFrom::Start(checkpoint.blocks * signed_size)).map_err(read_err)?;
+            text!("Resuming after block {}", checkpoint.blocks);
+            output::result("resumed_from", checkpoint.blocks);
+            (checkpoint, output_file)
+        },
+        None => {
+            let output_file = OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(output_path)
+                .map_err(write_err)?;
+            // Right away, so that the output is never there without it
+            let checkpoint = Checkpoint::new(state_path, size, *hash, block_size);
+            checkpoint.save()?;
+            (checkpoint, output_file)
+        },
+    };
+    interrupt::resume(Some(format!("run the same command again, which picks up from {}",
+                                   state_path.display())));
+
+    let mut verifier = Verifier::resume(checkpoint.next, block_size, checkpoint.blocks);
+    let mut saved = Instant::now();
+    let verified = verify_from(input_file, &mut output_file, &mut verifier, threads, read_err, write_err,
+                               |verifier, output| {
+        if saved.elapsed() < CHECKPOINT_INTERVAL {
+            return Ok(());
+        }
+        // What the checkpoint covers must be on disk before it is
+        output.sync_data().map_err(write_err)?;
+        checkpoint.blocks = verifier.blocks();
+        checkpoint.next = (*verifier.expected().expect("a verified block but the last")).into();
+        checkpoint.save()?;
+        saved = Instant::now();
+        Ok(())
+    })?;
+    interrupt::resume(None);
+    if verified {
+        checkpoint.remove()?;
+    }
+    Ok(verified)
+}
+
+pub(crate) fn default_threads() -> usize {
+    thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+pub(crate) fn parse_block_size(val: &str) -> Result<usize, String> {
+    match parse_size(val) {
+        Some(0) => Err("must be at least 1 byte".to_string()),
+        Some(size) if size > MAX_BLOCK_SIZE => Err("must be at most 1G".to_string()),
+        Some(size) => Ok(size as usize),
+        None => Err("not a size, e.g. 1024 or 64K".to_string()),
+    }
+}
+
+#[derive(Parser)]
+#[command(name = "w3-file_auth", about = "Signs and verifies files with a chain of SHA-256 hashes",
+          long_about = "Signs and verifies files with a chain of SHA-256 hashes. Signing \
+          appends to each block the hash of the next one, hashing from the last block back, and \
+          prints the hash of the first block, h0. Verifying checks every block against the hash \
+          before it, starting from h0, and writes out the original file.",
+          override_usage = "w3-file_auth [OPTIONS] <INPUT_FILE> <OUTPUT_FILE>\n       \
+          w3-file_auth -v <HASH> [OPTIONS] <INPUT_FILE> <OUTPUT_FILE>\n       \
+          w3-file_auth [OPTIONS] <COMMAND>",
+          subcommand_negates_reqs = true,
+          // -v is the hash to verify against, as it always was here
+          mut_arg("verbose", |arg| arg.short(None).help("log more of what happens on stderr: \
+          debug events such as the phases of a run, and every step with --verbose --verbose")))]
+pub struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+    // The command line from before the subcommands: the files to sign,
+    // or with -v to verify
+    #[arg(short, long, value_name = "HASH", value_parser = multihash::parse, requires = "input",
+          help = "verify INPUT_FILE against h0, as the verify command does, instead of signing it")]
+    verify: Option<Hash>,
+    #[command(flatten)]
+    files: Option<Files>,
+    #[arg(long, value_name = "BASE", global = true,
+          help = "print hashes as multihashes in this multibase encoding instead of hex")]
+    multibase: Option<Base>,
+    #[command(flatten)]
+    log: LogArgs,
+    #[command(flatten)]
+    output: OutputArgs,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    #[command(flatten)]
+    Job(Job),
+    #[command(about = "Runs the sign and verify commands listed in MANIFEST, several files at \
+              once")]
+    Batch(batch::Args),
+}
+
+#[derive(Subcommand)]
+pub(crate) enum Job {
+    #[command(about = "Signs INPUT_FILE into OUTPUT_FILE and prints h0")]
+    Sign {
+        #[command(flatten)]
+        files: Files,
+    },
+    #[command(about = "Verifies the signed INPUT_FILE against h0 and writes the original \
+              OUTPUT_FILE")]
+    Verify {
+        #[arg(value_name = "HASH", value_parser = multihash::parse,
+              help = "h0, in hex or as a multibase multihash")]
+        hash: Hash,
+        #[command(flatten)]
+        files: Files,
+        #[arg(long, value_name = "FILE",
+              help = "save how far verifying got in FILE, and pick up from there if it exists")]
+        checkpoint: Option<PathBuf>,
+        #[arg(short, long, value_name = "N", value_parser = RangedU64ValueParser::<usize>::new().range(1..),
+              help = "threads hashing blocks (default: one per CPU)")]
+        threads: Option<usize>,
+    },
+}
+
+#[derive(clap::Args)]
+pub(crate) struct Files {
+    #[arg(value_name = "INPUT_FILE", help = "the file to sign, or the signed file to verify")]
+    pub(crate) input: PathBuf,
+    #[arg(value_name = "OUTPUT_FILE", help = "created, so it must not exist yet")]
+    pub(crate) output: PathBuf,
+    #[arg(short, long, value_name = "SIZE", default_value = DEFAULT_BLOCK_SIZE,
+          value_parser = parse_block_size, help = "size of the blocks, e.g. 1024 or 64K")]
+    pub(crate) block_size: usize,
+}
+
+// The subcommand, or without one the files to sign, or to verify with -v
+fn command(command: Option<Command>, files: Option<Files>, verify: Option<Hash>) -> Result<Command, Error> {
+    match (command, files, verify) {
+        (Some(_), _, Some(_)) => Err(Error::VerifyWithCommand),
+        (Some(command), _, None) => Ok(command),
+        (None, Some(files), Some(hash)) => {
+            Ok(Command::Job(Job::Verify { hash, files, checkpoint: None, threads: None }))
+        },
+        (None, Some(files), None) => Ok(Command::Job(Job::Sign { files })),
+        (None, None, _) => unreachable!("the files are required without a command"),
+    }
+}
+
+pub fn run(args: Args) -> Result<(), Error> {
+    log::init(&args.log);
+    let command = command(args.command, args.files, args.verify);
+    let operation = match &command {
+        Ok(Command::Job(Job::Sign { .. })) => "file-auth sign",
+        Ok(Command::Job(Job::Verify { .. })) => "file-auth verify",
+        Ok(Command::Batch(_)) => "file-auth batch",
+        Err(_) => "file-auth",
+    };
+    output::init(operation, &args.output);
+    let multibase = args.multibase;
+    output::finish(command.and_then(|command| run_command(command, multibase)))
+}
+
+fn run_command(command: Command, multibase: Option<Base>) -> Result<(), Error> {
+    interrupt::init()?;
+    match command {
+        Command::Job(job) => sign_or_verify(job, multibase),
+        Command::Batch(batch) => batch::run(&batch, multibase),
+    }
+}
+
+fn sign_or_verify(job: Job, multibase: Option<Base>) -> Result<(), Error> {
+    match job {
+        Job::Verify { hash, files, checkpoint, threads } => {
+            output::parameter("hash", multihash::format(&hash, multibase));
+            files.report();
+            if let Some(checkpoint) = &checkpoint {
+                output::parameter("checkpoint", checkpoint.display().to_string());
+            }
+            let threads = threads.unwrap_or_else(default_threads);
+            output::parameter("threads", threads);
+            let result = verify(&files.input, &files.output, files.block_size, &hash, threads,
+                                checkpoint.as_deref())?;
+            text!("Verified: {}", result);
+            output::result("verified", result);
+            if !result {
+                return Err(Error::Unverified { path: files.input });
+            }
+            text!("File created: {}", files.output.display());
+        },
+        Job::Sign { files } => {
+            files.report();
+            let read_err = |source| Error::Read { path: files.input.clone(), source };
+            let hashes = File::open(&files.input)
+                .and_then(|input| hash_blocks(input, files.block_size))
+                .map_err(read_err)?;
+            debug!(blocks = hashes.len(), block_size = files.block_size, "Hashed the file");
+
+            if let Some(val) = hashes.last() {
+                let hash = multihash::format(val, multibase);
+                text!("Hash 0: {}", hash);
+                output::result("hash", hash);
+            }
+            output::result("blocks", hashes.len());
+
+            sign(&files.input, &files.output, files.block_size, &hashes)?;
+            text!("File created: {}", files.output.display());
+        },
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const H0: &str = "03c08f4ee0b576fe319338139c045c89c3e8e9409633bea29442e21425006ea8";
+
+    fn parse(argv: &[&str]) -> Result<Command, Error> {
+        let args = Args::try_parse_from(argv).unwrap();
+        command(args.command, args.files, args.verify)
+    }
+
+    // The command lines of the binary from before the subcommands
+    #[test]
+    fn signs_without_a_command() {
+        match parse(&["w3-file_auth", "in", "out"]) {
+            Ok(Command::Job(Job::Sign { files })) => {
+                assert_eq!((files.input, files.output), ("in".into(), "out".into()));
+                assert_eq!(files.block_size, 1024);
+            },
+            _ => panic!("not signing"),
+        }
+    }
+
+    #[test]
+    fn verifies_with_v() {
+        for argv in [["w3-file_auth", "-v", H0, "in", "out"], ["w3-file_auth", "--verify", H0, "in", "out"]] {
+            match parse(&argv) {
+                Ok(Command::Job(Job::Verify { hash, files, checkpoint: None, threads: None })) => {
+                    assert_eq!(multihash::format(&hash, None), H0);
+                    assert_eq!((files.input, files.output), ("in".into(), "out".into()));
+                },
+                _ => panic!("not verifying"),
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_v_with_a_command() {
+        assert!(matches!(parse(&["w3-file_auth", "-v", H0, "sign", "in", "out"]),
+                         Err(Error::VerifyWithCommand)));
+        assert!(Args::try_parse_from(["w3-file_auth", "in"]).is_err());
+        assert!(Args::try_parse_from(["w3-file_auth"]).is_err());
+    }
+
+    // Options may come anywhere among the files, and paths need not be UTF-8
+    #[test]
+    fn options_go_anywhere() {
+        let argvs = [
+            &["w3-file_auth", "-v", H0, "-b", "4K", "in", "out"][..],
+            &["w3-file_auth", "in", "-b", "4K", "out", "-v", H0],
+            &["w3-file_auth", "in", "out", "--block-size=4K", "--verify", H0],
+            &["w3-file_auth", "--json", "in", "-v", H0, "out", "-b4K"],
+        ];
+        for argv in argvs {
+            match parse(argv) {
+                Ok(Command::Job(Job::Verify { hash, files, .. })) => {
+                    assert_eq!(multihash::format(&hash, None), H0);
+                    assert_eq!((files.input, files.output), ("in".into(), "out".into()));
+                    assert_eq!(files.block_size, 4096);
+                },
+                _ => panic!("not verifying with {:?}", argv),
+            }
+        }
+        for argv in [&["w3-file_auth", "--json", "sign", "-b", "4K", "in", "out"][..],
+                     &["w3-file_auth", "sign", "in", "out", "-b", "4K", "--json"]] {
+            let signed = matches!(parse(argv), Ok(Command::Job(Job::Sign { files })) if files.block_size == 4096);
+            assert!(signed, "not signing with {:?}", argv);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn keeps_paths_as_given() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let input = OsStr::from_bytes(b"in\xff");
+        let argv = [OsStr::new("w3-file_auth"), input, OsStr::new("out")];
+        let args = Args::try_parse_from(argv).unwrap();
+        assert_eq!(args.files.unwrap().input.as_os_str(), input);
+    }
+}
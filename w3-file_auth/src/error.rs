@@ -0,0 +1,42 @@
+use std::io;
+use std::path::PathBuf;
+
+use crypto_utils::exit::{self, Status};
+use crypto_utils::interrupt;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Could not read {}: {source}", path.display())]
+    Read { path: PathBuf, source: io::Error },
+    #[error("Could not write {}: {source}", path.display())]
+    Write { path: PathBuf, source: io::Error },
+    #[error("{} does not verify against the hash", path.display())]
+    Unverified { path: PathBuf },
+    #[error("{}:{line}: {message}", path.display())]
+    Manifest { path: PathBuf, line: usize, message: String },
+    #[error("The checkpoint {} {reason}", path.display())]
+    Checkpoint { path: PathBuf, reason: String },
+    #[error("{failed} of {commands} commands failed")]
+    Batch { failed: usize, commands: usize },
+    #[error("Could not handle Ctrl-C: {0}")]
+    CtrlC(#[from] interrupt::Error),
+    #[error("-v takes INPUT_FILE and OUTPUT_FILE, without a command")]
+    VerifyWithCommand,
+    #[error("--dp-bits must be less than --bits")]
+    DpBits,
+    #[error("No key length up to {0} worked")]
+    NoKeyLength(usize),
+}
+
+impl Status for Error {
+    fn exit_code(&self) -> u8 {
+        match self {
+            Error::Read { .. } | Error::Write { .. } => exit::IO,
+            Error::Unverified { .. } | Error::Batch { .. } | Error::NoKeyLength(_)
+                | Error::CtrlC(_) => exit::FAILED,
+            Error::Manifest { .. } | Error::Checkpoint { .. } | Error::VerifyWithCommand
+                | Error::DpBits => exit::USAGE,
+        }
+    }
+}
@@ -0,0 +1,211 @@
+use std::convert::TryInto;
+
+use clap::Parser;
+use crypto_utils::ct::ct_eq;
+use crypto_utils::encoding::{from_hex, to_hex};
+use crypto_utils::log::{self, LogArgs};
+use crypto_utils::output::{self, OutputArgs};
+use crypto_utils::random::{self, RandomArgs};
+use crypto_utils::secret::{self, SecretBytes};
+use crypto_utils::text;
+use rand::Rng;
+use sha2::{Digest, Sha256};
+use tracing::trace;
+
+use crate::error::Error;
+
+const DEFAULT_MESSAGE: &str = "comment=hello&uid=1001&role=user";
+const DEFAULT_APPEND: &str = "&role=admin";
+
+// Longest key the demo tries
+const MAX_KEY_LEN: usize = 64;
+
+const K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+// The SHA-256 compression function on one 64-byte block
+fn compress(state: &mut [u32; 8], block: &[u8]) {
+    let mut w = [0u32; 64];
+    for (i, word) in block.chunks(4).enumerate() {
+        w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+    }
+    for i in 16..64 {
+        let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+        let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+        w[i] = w[i - 16].wrapping_add(s0).wrapping_add(w[i - 7]).wrapping_add(s1);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+    for (k, w) in K.iter().zip(&w) {
+        let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+        let ch = (e & f) ^ (!e & g);
+        let t1 = h.wrapping_add(s1).wrapping_add(ch).wrapping_add(*k).wrapping_add(*w);
+        let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+        let maj = (a & b) ^ (a & c) ^ (b & c);
+        let t2 = s0.wrapping_add(maj);
+        h = g;
+        g = f;
+        f = e;
+        e = d.wrapping_add(t1);
+        d = c;
+        c = b;
+        b = a;
+        a = t1.wrapping_add(t2);
+    }
+    for (s, x) in state.iter_mut().zip(&[a, b, c, d, e, f, g, h]) {
+        *s = s.wrapping_add(*x);
+    }
+}
+
+// What SHA-256 appends to a message of len bytes before hashing it: 0x80,
+// zeros up to 56 mod 64, and the length in bits as a 64-bit big-endian
+// integer
+fn glue_padding(len: usize) -> Vec<u8> {
+    let mut padding = vec![0x80];
+    padding.resize(1 + (119 - len % 64) % 64, 0);
+    padding.extend_from_slice(&(8 * len as u64).to_be_bytes());
+    padding
+}
+
+// The SHA-256 digest is the final state itself, so hashing can resume from
+// it as if the prefix_len bytes it covers, padding included, were followed
+// by data
+fn sha256_resume(digest: &[u8], prefix_len: usize, data: &[u8]) -> Vec<u8> {
+    let mut state = [0u32; 8];
+    for (s, word) in state.iter_mut().zip(digest.chunks(4)) {
+        *s = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+    }
+    let mut padded = data.to_vec();
+    padded.extend(glue_padding(prefix_len + data.len()));
+    for block in padded.chunks(64) {
+        compress(&mut state, block);
+    }
+    state.iter().flat_map(|s| s.to_be_bytes()).collect()
+}
+
+// Given the tag SHA-256(key || message) and the length of key || message,
+// the message to append after message to sign append, and its tag
+fn extend(tag: &[u8], signed_len: usize, append: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    let glue = glue_padding(signed_len);
+    let forged_tag = sha256_resume(tag, signed_len + glue.len(), append);
+    let mut suffix = glue;
+    suffix.extend_from_slice(append);
+    (suffix, forged_tag)
+}
+
+// Signs messages with the broken MAC SHA-256(key || message)
+struct Server {
+    key: SecretBytes,
+}
+
+impl Server {
+    fn new() -> Server {
+        let len = random::rng().gen_range(8..=32);
+        Server { key: secret::random_key(len) }
+    }
+
+    fn sign(&self, message: &[u8]) -> Vec<u8> {
+        Sha256::new().chain(&self.key).chain(message).result().to_vec()
+    }
+
+    fn verify(&self, message: &[u8], tag: &[u8]) -> bool {
+        ct_eq(&self.sign(message), tag)
+    }
+}
+
+// Against a local server with a key of unknown length, guessing the length
+// by whether the server accepts the forgery
+fn demo(message: &str, append: &str) -> Result<(), Error> {
+    let server = Server::new();
+    let tag = server.sign(message.as_bytes());
+    text!("The server signs {:?} with tag {}", message, to_hex(&tag));
+    output::result("signed_tag", to_hex(&tag));
+
+    for key_len in 0..=MAX_KEY_LEN {
+        let (suffix, forged_tag) = extend(&tag, key_len + message.len(), append.as_bytes());
+        let mut forged = message.as_bytes().to_vec();
+        forged.extend(suffix);
+        if server.verify(&forged, &forged_tag) {
+            text!("With a key of {} bytes, the server accepts", key_len);
+            text!("  message: {}", forged.escape_ascii());
+            text!("  tag: {}", to_hex(&forged_tag));
+            output::result("key_len", key_len);
+            report_forgery(&forged, &forged_tag);
+            return Ok(());
+        }
+        trace!(key_len, "The server rejects the forgery");
+    }
+    Err(Error::NoKeyLength(MAX_KEY_LEN))
+}
+
+fn report_forgery(forged: &[u8], tag: &[u8]) {
+    output::result("message", forged.escape_ascii().to_string());
+    output::result("message_hex", to_hex(forged));
+    output::result("tag", to_hex(tag));
+}
+
+fn parse_tag(val: &str) -> Result<[u8; 32], String> {
+    from_hex(val)
+        .map_err(|e| e.to_string())?
+        .try_into()
+        .map_err(|_| "must be a SHA-256 digest in hex".to_string())
+}
+
+#[derive(Parser)]
+#[command(name = "length-extension", about = "Forges a secret-prefix SHA-256 MAC",
+          long_about = "Forges SHA-256(key || message || padding || APPEND) from the tag \
+          SHA-256(key || message) without the key. Without --tag, runs a demo against a local \
+          server.")]
+pub struct Args {
+    #[arg(short, long, value_name = "HEX", value_parser = parse_tag, requires = "key_len",
+          help = "known tag SHA-256(key || message), in hex")]
+    tag: Option<[u8; 32]>,
+    #[arg(short, long, value_name = "TEXT", default_value = DEFAULT_MESSAGE,
+          help = "the signed message")]
+    message: String,
+    #[arg(short, long, value_name = "N", help = "length of the key in bytes (needed with --tag)")]
+    key_len: Option<usize>,
+    #[arg(short, long, value_name = "TEXT", default_value = DEFAULT_APPEND,
+          help = "data to append")]
+    append: String,
+    #[command(flatten)]
+    log: LogArgs,
+    #[command(flatten)]
+    output: OutputArgs,
+    #[command(flatten)]
+    random: RandomArgs,
+}
+
+pub fn run(args: Args) -> Result<(), Error> {
+    log::init(&args.log);
+    output::init("length-extension", &args.output);
+    random::init(&args.random);
+    output::parameter("message", args.message.as_str());
+    output::parameter("append", args.append.as_str());
+    let (tag, key_len) = match (args.tag, args.key_len) {
+        (Some(tag), Some(key_len)) => (tag, key_len),
+        _ => {
+            output::parameter("demo", true);
+            return output::finish(demo(&args.message, &args.append));
+        },
+    };
+    output::parameter("tag", to_hex(&tag));
+    output::parameter("key_len", key_len);
+
+    let (suffix, forged_tag) = extend(&tag, key_len + args.message.len(), args.append.as_bytes());
+    let mut forged = args.message.into_bytes();
+    forged.extend(suffix);
+    text!("Message: {}", forged.escape_ascii());
+    text!("Message (hex): {}", to_hex(&forged));
+    text!("Tag: {}", to_hex(&forged_tag));
+    report_forgery(&forged, &forged_tag);
+    output::finish(Ok(()))
+}
@@ -0,0 +1,34 @@
+// Without the std feature, only the hash chain of signed files, for
+// devices with no operating system
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "std")]
+extern crate clap;
+#[cfg(feature = "std")]
+extern crate crypto_utils;
+extern crate digest;
+#[cfg(feature = "std")]
+extern crate rand;
+extern crate sha2;
+#[cfg(feature = "std")]
+extern crate thiserror;
+#[cfg(feature = "std")]
+extern crate tracing;
+
+#[cfg(feature = "std")]
+pub mod batch;
+#[cfg(feature = "std")]
+pub mod birthday;
+pub mod chain;
+#[cfg(feature = "std")]
+pub mod checkpoint;
+#[cfg(feature = "std")]
+pub mod cli;
+#[cfg(feature = "std")]
+pub mod error;
+#[cfg(feature = "std")]
+pub mod length_extension;
+#[cfg(feature = "std")]
+pub mod multihash;
+#[cfg(feature = "std")]
+pub mod pipeline;
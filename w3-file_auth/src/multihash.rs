@@ -0,0 +1,56 @@
+use std::convert::TryInto;
+
+use crypto_utils::encoding::{from_hex, from_multibase, to_hex, to_multibase, Base};
+
+use crate::chain::{Hash, HASH_SIZE};
+
+// A multihash is a hash prefixed with the code of its function and its
+// length, both as varints, so that tools such as IPFS can tell what it is.
+// Ours are always SHA-256, whose code and length fit in a byte each.
+const SHA2_256: u8 = 0x12;
+
+pub fn encode(hash: &Hash) -> Vec<u8> {
+    let mut bytes = vec![SHA2_256, HASH_SIZE as u8];
+    bytes.extend_from_slice(hash);
+    bytes
+}
+
+pub fn decode(bytes: &[u8]) -> Result<Hash, String> {
+    match bytes {
+        [SHA2_256, len, hash @ ..] if usize::from(*len) == HASH_SIZE && hash.len() == HASH_SIZE => {
+            let mut out = [0; HASH_SIZE];
+            out.copy_from_slice(hash);
+            Ok(out)
+        },
+        [SHA2_256, ..] => Err("not a SHA-256 multihash: wrong length".to_string()),
+        [code, ..] if *code < 0x80 => Err(format!("multihash 0x{:02x} is not SHA-256", code)),
+        _ => Err("not a SHA-256 multihash".to_string()),
+    }
+}
+
+// Raw hex, or with a base the multihash in that multibase encoding
+pub fn format(hash: &Hash, base: Option<Base>) -> String {
+    match base {
+        None => to_hex(hash),
+        Some(base) => to_multibase(base, &encode(hash)),
+    }
+}
+
+// Either raw hex or a multihash in any multibase encoding. Raw hex is
+// exactly 2 digits per byte and a base16 multihash is longer, so only hex
+// of another length may be meant as either.
+pub fn parse(s: &str) -> Result<Hash, String> {
+    let s = s.trim();
+    let hex = s.bytes().all(|b| b.is_ascii_hexdigit());
+    if !hex || s.len() != 2 * HASH_SIZE {
+        let multihash = from_multibase(s).map_err(|e| e.to_string())
+            .and_then(|bytes| decode(&bytes));
+        if !hex || multihash.is_ok() {
+            return multihash;
+        }
+    }
+    let hash = from_hex(s).map_err(|e| e.to_string())?;
+    hash.try_into().map_err(|hash: Vec<u8>| {
+        format!("a SHA-256 hash is {} bytes, not {}", HASH_SIZE, hash.len())
+    })
+}
@@ -0,0 +1,110 @@
+use std::cmp;
+use std::io;
+use std::io::prelude::*;
+use std::io::SeekFrom;
+use std::sync::mpsc::SyncSender;
+
+// Signing and verifying run as a pipeline, so that the disk and the cores
+// all keep busy: a thread reads the input in large chunks of whole blocks,
+// the blocks are hashed, and the calling thread writes the output in order.
+// The chunks go through channels holding one each, so that a slow stage
+// holds the others back rather than filling memory.
+
+pub type Chunk = io::Result<Vec<u8>>;
+
+// Chunks are at least this big, so that reads and writes are large
+const READ_SIZE: usize = 1 << 20;
+// Bounds the chunks in flight, each worker having up to 3: one waiting to
+// be hashed, one being hashed and one waiting to be written
+const MAX_IN_FLIGHT: usize = 256 << 20;
+
+// Whole blocks of block_size bytes
+pub fn chunk_size(block_size: usize) -> usize {
+    block_size * cmp::max(1, READ_SIZE / block_size)
+}
+
+// No more workers than fit in MAX_IN_FLIGHT with chunks of chunk_size,
+// and at least one
+pub fn workers(threads: usize, chunk_size: usize) -> usize {
+    cmp::max(1, cmp::min(threads, MAX_IN_FLIGHT / (3 * chunk_size)))
+}
+
+// Reads until buf is full or the input ends, returning how much was read:
+// a single read may stop short in the middle of a file
+fn read_block<R: Read>(input: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut len = 0;
+    while len < buf.len() {
+        match input.read(&mut buf[len..]) {
+            Ok(0) => break,
+            Ok(n) => len += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {},
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(len)
+}
+
+// Sends input in chunks of chunk_size to each of senders in turn. The last
+// chunk is shorter, possibly empty, and nothing is sent after it or after
+// an error, or once the other end is gone.
+pub fn read_chunks<R: Read>(mut input: R, chunk_size: usize, senders: &[SyncSender<Chunk>]) {
+    for sender in senders.iter().cycle() {
+        let mut chunk = vec![0; chunk_size];
+        let read = read_block(&mut input, &mut chunk).map(|len| {
+            chunk.truncate(len);
+            chunk
+        });
+        let last = !matches!(read, Ok(ref chunk) if chunk.len() == chunk_size);
+        if sender.send(read).is_err() || last {
+            return;
+        }
+    }
+}
+
+// The signed blocks of a chunk. The last chunk ends with the last block,
+// which is shorter than the others, possibly empty.
+pub fn signed_blocks(chunk: &[u8], signed_size: usize, last: bool) -> impl Iterator<Item = &[u8]> {
+    let (blocks, rest) = chunk.split_at(chunk.len() / signed_size * signed_size);
+    blocks.chunks_exact(signed_size).chain(if last { Some(rest) } else { None })
+}
+
+// A file from the end back to the start: first the last block alone,
+// which holds what is left over after the whole blocks and is empty if
+// nothing is, then the whole blocks before it in chunks
+#[derive(Debug)]
+pub struct RevChunks<R> {
+    input: R,
+    // Where the next chunk ends
+    end: u64,
+    started: bool,
+    block_size: usize,
+    chunk_size: usize,
+}
+
+impl<R: Read + Seek> RevChunks<R> {
+    pub fn new(mut input: R, block_size: usize, chunk_size: usize) -> io::Result<Self> {
+        let end = input.seek(SeekFrom::End(0))?;
+        Ok(RevChunks { input, end, started: false, block_size, chunk_size })
+    }
+}
+
+impl<R: Read + Seek> Iterator for RevChunks<R> {
+    type Item = Chunk;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let len = if !self.started {
+            self.end % self.block_size as u64
+        } else if self.end == 0 {
+            return None;
+        } else {
+            cmp::min(self.end, self.chunk_size as u64)
+        };
+        self.started = true;
+        self.end -= len;
+        let mut buf = vec![0; len as usize];
+        let read = self.input.seek(SeekFrom::Start(self.end))
+            .and_then(|_| self.input.read_exact(&mut buf));
+
+        Some(read.map(|()| buf))
+    }
+}
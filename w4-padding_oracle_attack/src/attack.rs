@@ -0,0 +1,165 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use crypto_utils::oracle::{Oracle, OracleError, OracleResponse};
+
+use crate::error::Error;
+use crate::BLOCK;
+
+// Counts the queries made to an oracle
+pub struct Counted<O> {
+    oracle: O,
+    queries: AtomicUsize,
+}
+
+impl<O: Oracle> Counted<O> {
+    pub fn new(oracle: O) -> Counted<O> {
+        Counted { oracle, queries: AtomicUsize::new(0) }
+    }
+
+    pub fn queries(&self) -> usize {
+        self.queries.load(Ordering::Relaxed)
+    }
+}
+
+impl<O: Oracle> Oracle for Counted<O> {
+    type Response = O::Response;
+
+    fn query(&self, ciphertext: &[u8]) -> Result<O::Response, OracleError> {
+        self.queries.fetch_add(1, Ordering::Relaxed);
+        self.oracle.query(ciphertext)
+    }
+}
+
+// Padding bytes, space and letters first, then everything else
+fn guesses() -> Vec<u8> {
+    let likely: Vec<u8> = (1..=16)
+        .chain(Some(b' '))
+        .chain(b'a'..=b'z')
+        .chain(b'A'..=b'Z')
+        .collect();
+    likely.iter().cloned()
+        .chain((0..=255).filter(|g| !likely.contains(g)))
+        .collect()
+}
+
+// Hands the guesses out in order to `threads` workers and returns the
+// first one `accept` confirms, or None if none is
+fn find_byte<F>(guesses: &[u8], threads: usize, accept: F) -> Result<Option<u8>, OracleError>
+    where F: Fn(u8) -> Result<bool, OracleError> + Sync
+{
+    let next = AtomicUsize::new(0);
+    let stop = AtomicBool::new(false);
+    let found = Mutex::new(None);
+    thread::scope(|s| {
+        for _ in 0..threads {
+            s.spawn(|| {
+                while !stop.load(Ordering::Relaxed) {
+                    let guess = match guesses.get(next.fetch_add(1, Ordering::Relaxed)) {
+                        Some(&guess) => guess,
+                        None => break,
+                    };
+                    match accept(guess) {
+                        Ok(false) => {},
+                        result => {
+                            stop.store(true, Ordering::Relaxed);
+                            found.lock().unwrap().get_or_insert(result.map(|_| guess));
+                        },
+                    }
+                }
+            });
+        }
+    });
+    found.into_inner().unwrap().transpose()
+}
+
+// The block as recovered so far, with _ for bytes not yet known and . for
+// non-printable ones
+pub fn show(plaintext: &[Option<u8>]) -> String {
+    plaintext.iter()
+        .map(|b| match b {
+            None => '_',
+            Some(b) if b.is_ascii_graphic() || *b == b' ' => *b as char,
+            Some(_) => '.',
+        })
+        .collect()
+}
+
+// Recovers the plaintext of block from its last byte back: with the bytes
+// after index known, a forged previous block makes them decrypt to the
+// padding value, and the guess at index that the oracle accepts is the
+// plaintext byte. Sends up to threads queries at once, and calls report
+// after each byte.
+pub fn decrypt_block<F>(oracle: &dyn Oracle<Response = OracleResponse>, prev_block: &[u8],
+                        block: &[u8], threads: usize, mut report: F) -> Result<Vec<u8>, Error>
+    where F: FnMut(&[Option<u8>])
+{
+    let guesses = guesses();
+    let mut plaintext = vec![None; BLOCK];
+    for index in (0..BLOCK).rev() {
+        let pad = (BLOCK - index) as u8;
+        let forged: Vec<u8> = prev_block.iter()
+            .zip(&plaintext)
+            .map(|(c, p)| match p {
+                Some(p) => c ^ pad ^ p,
+                None => *c,
+            })
+            .collect();
+
+        let byte = find_byte(&guesses, threads, |guess| {
+            let mut query = forged.clone();
+            query[index] ^= pad ^ guess;
+            query.extend_from_slice(block);
+            if !oracle.query(&query)?.valid_padding() {
+                return Ok(false);
+            }
+            // The last byte may also have found a longer padding, such as
+            // 02 02 in the last block; changing the byte before it tells
+            if index == BLOCK - 1 {
+                query[index - 1] ^= 1;
+                return Ok(oracle.query(&query)?.valid_padding());
+            }
+            Ok(true)
+        })?;
+        let byte = byte.ok_or(Error::NoGuess(index))?;
+        plaintext[index] = Some(byte);
+        report(&plaintext);
+    }
+    Ok(plaintext.into_iter().map(Option::unwrap).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use crypto_utils::padding;
+    use crypto_utils::random::{self, RandomArgs};
+
+    use super::*;
+    use crate::serve::LocalOracle;
+
+    // The last block of message, recovered from a LocalOracle holding it
+    fn last_block(message: &[u8]) -> (Vec<u8>, Vec<u8>) {
+        random::init(&RandomArgs { seed: Some(4) });
+        let oracle = LocalOracle::new(message);
+        let ciphertext = oracle.encrypt();
+        let blocks: Vec<&[u8]> = ciphertext.chunks(BLOCK).collect();
+        let [.., prev_block, block] = blocks[..] else { unreachable!() };
+        let recovered = decrypt_block(&oracle, prev_block, block, 4, |_| {}).unwrap();
+        let padded_msg = padding::pad(message, BLOCK);
+        (recovered, padded_msg[padded_msg.len() - BLOCK..].to_vec())
+    }
+
+    #[test]
+    fn padded_02_02() {
+        let (recovered, expected) = last_block(b"The Magic Words are Squeamish!");
+        assert_eq!(&expected[BLOCK - 3..], b"!\x02\x02");
+        assert_eq!(recovered, expected);
+    }
+
+    #[test]
+    fn block_of_padding() {
+        let (recovered, expected) = last_block(b"Ossifrage, with a twist of lime.");
+        assert_eq!(expected, [0x10; BLOCK]);
+        assert_eq!(recovered, expected);
+    }
+}
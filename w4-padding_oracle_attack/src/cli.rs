@@ -0,0 +1,171 @@
+use std::time::Instant;
+
+use clap::builder::RangedU64ValueParser;
+use clap::{Parser, Subcommand};
+use crypto_utils::encoding::{from_hex, to_hex};
+use crypto_utils::log::{self, LogArgs};
+use crypto_utils::oracle::{CommandOracle, Oracle, OracleResponse};
+use crypto_utils::output::{self, OutputArgs};
+use crypto_utils::random::{self, RandomArgs};
+use crypto_utils::padding;
+use crypto_utils::text;
+use tracing::{debug, info, trace};
+
+use crate::attack::{self, Counted};
+use crate::error::Error;
+use crate::http::HttpOracle;
+use crate::serve::{self, LocalOracle};
+use crate::BLOCK;
+
+const TARGET: &str = "http://crypto-class.appspot.com/po?er=";
+
+// The intercepted ciphertext, with its random IV as the first block
+const CIPHERTEXT: &str = concat!("f20bdba6ff29eed7b046d1df9fb70000",
+                                 "58b1ffb4210a580f748b4ac714c001bd",
+                                 "4a61044426fb515dad3f21f18aa577c0",
+                                 "bdf302936266926ff37dbf7035d5eeb4");
+
+const DEFAULT_THREADS: usize = 16;
+
+const DEFAULT_PORT: u16 = 8000;
+
+const DEFAULT_MESSAGE: &str = "Never tell anyone why their ciphertext was rejected";
+
+#[derive(Parser)]
+#[command(name = "w4-padding_oracle_attack", about = "Decrypts a CBC ciphertext with a padding oracle",
+          long_about = "Decrypts the hex CIPHERTEXT, IV first, by appending forged ciphertexts to \
+          URL (default: the assignment's, or --url), by running CMD on them, or by querying an \
+          oracle in this process. serve runs a local padding oracle to practice on.",
+          override_usage = "w4-padding_oracle_attack [OPTIONS] [URL [CIPHERTEXT]]\n       \
+          w4-padding_oracle_attack --url <URL> [OPTIONS] [CIPHERTEXT]\n       \
+          w4-padding_oracle_attack --command <CMD> [OPTIONS] <CIPHERTEXT>\n       \
+          w4-padding_oracle_attack --local [OPTIONS]\n       \
+          w4-padding_oracle_attack serve [OPTIONS]",
+          args_conflicts_with_subcommands = true)]
+pub struct Args {
+    #[command(subcommand)]
+    serve: Option<Serve>,
+    #[arg(value_names = ["URL", "CIPHERTEXT"], num_args = 0..=2,
+          help = "URL and CIPHERTEXT, or only CIPHERTEXT with --url or --command")]
+    args: Vec<String>,
+    #[arg(short, long, value_name = "URL", conflicts_with_all = ["command", "local"],
+          help = "the oracle to attack, when the arguments give no URL")]
+    url: Option<String>,
+    #[arg(short, long, value_name = "N", default_value_t = DEFAULT_THREADS,
+          value_parser = RangedU64ValueParser::<usize>::new().range(1..),
+          help = "concurrent requests per byte")]
+    threads: usize,
+    #[arg(short, long, value_name = "CMD", help = "query the oracle by running CMD with the \
+          ciphertext in hex as its last argument; it exits with 0 for a valid message, 1 for a \
+          bad MAC and 2 for a bad padding")]
+    command: Option<String>,
+    #[arg(short, long, conflicts_with_all = ["command", "args"],
+          help = "attack an oracle simulated in this process, over a ciphertext of --message")]
+    local: bool,
+    #[arg(short, long, value_name = "TEXT", default_value = DEFAULT_MESSAGE, requires = "local",
+          help = "message to encrypt for --local")]
+    message: String,
+    #[command(flatten)]
+    log: LogArgs,
+    #[command(flatten)]
+    output: OutputArgs,
+    #[command(flatten)]
+    random: RandomArgs,
+}
+
+#[derive(Subcommand)]
+enum Serve {
+    #[command(about = "Runs a local padding oracle to practice on")]
+    Serve {
+        #[arg(short, long, default_value_t = DEFAULT_PORT, help = "port to serve on")]
+        port: u16,
+        #[arg(short, long, value_name = "TEXT", default_value = DEFAULT_MESSAGE,
+              help = "message whose encryption the oracle hands out")]
+        message: String,
+    },
+}
+
+pub fn run(args: Args) -> Result<(), Error> {
+    log::init(&args.log);
+    if let Some(Serve::Serve { port, message }) = &args.serve {
+        output::init("padding-oracle serve", &args.output);
+        random::init(&args.random);
+        output::parameter("port", *port);
+        output::parameter("message", message.as_str());
+        return serve::serve(*port, message.as_bytes());
+    }
+    output::init("padding-oracle", &args.output);
+    random::init(&args.random);
+    output::finish(attack(args))
+}
+
+fn attack(args: Args) -> Result<(), Error> {
+    let threads = args.threads;
+    // The oracle to attack, a description of it and the target ciphertext
+    let (oracle, name, ciphertext): (Box<dyn Oracle<Response = OracleResponse>>, String, String) =
+        match (args.command, args.local, &args.args[..]) {
+            (Some(command), _, [ciphertext]) => match CommandOracle::new(&command) {
+                Some(oracle) => (Box::new(oracle), command, ciphertext.clone()),
+                None => return Err(Error::EmptyCommand),
+            },
+            (Some(_), _, _) => return Err(Error::CommandArgs),
+            (None, true, _) => {
+                let oracle = LocalOracle::new(args.message.as_bytes());
+                let ciphertext = to_hex(&oracle.encrypt());
+                (Box::new(oracle), "a local oracle".to_string(), ciphertext)
+            },
+            (None, false, free) => {
+                // With --url, a single argument is the ciphertext
+                let (target, ciphertext) = match (args.url.as_deref(), free) {
+                    (Some(url), [ciphertext]) => (url, ciphertext.as_str()),
+                    (url, free) => (free.first().map_or(url.unwrap_or(TARGET), String::as_str),
+                                    free.get(1).map_or(CIPHERTEXT, String::as_str)),
+                };
+                (Box::new(HttpOracle::new(target)), target.to_string(), ciphertext.to_string())
+            },
+        };
+    output::parameter("oracle", name.as_str());
+    output::parameter("ciphertext", ciphertext.as_str());
+    output::parameter("threads", threads);
+    let ciphertext = from_hex(ciphertext.trim()).map_err(|_| Error::Hex)?;
+    if ciphertext.len() < 2 * BLOCK || !ciphertext.len().is_multiple_of(BLOCK) {
+        return Err(Error::CiphertextLength);
+    }
+
+    let oracle = Counted::new(oracle);
+    let blocks: Vec<&[u8]> = ciphertext.chunks(BLOCK).collect();
+    info!("Decrypting {} blocks through {} with {} threads", blocks.len() - 1, name, threads);
+
+    let mut padded_msg = Vec::new();
+    for (i, pair) in blocks.windows(2).enumerate() {
+        let label = format!("block {}/{}", i + 1, blocks.len() - 1);
+        let (start, queries) = (Instant::now(), oracle.queries());
+        let plaintext = attack::decrypt_block(&oracle, pair[0], pair[1], threads, |p| {
+            trace!(block = i + 1, queries = oracle.queries(), "Recovered a byte");
+            text!("{} [{}]", label, attack::show(p));
+        })?;
+        let (elapsed, queries) = (start.elapsed(), oracle.queries() - queries);
+        debug!(block = i + 1, queries, elapsed = ?elapsed, "Block decrypted at {:.0} queries/s",
+               queries as f64 / elapsed.as_secs_f64());
+        text!("{}: {:?} ({} queries so far)", label, String::from_utf8_lossy(&plaintext),
+              oracle.queries());
+        output::timing(&format!("block_{}", i + 1), elapsed);
+        padded_msg.extend(plaintext);
+    }
+    output::result("queries", oracle.queries());
+    output::result("plaintext_hex", to_hex(&padded_msg));
+
+    match padding::unpad(&padded_msg, BLOCK) {
+        Ok(msg) => {
+            text!("The secret message is: {}", String::from_utf8_lossy(msg));
+            output::result("message", String::from_utf8_lossy(msg));
+        },
+        Err(e) => {
+            text!("The recovered plaintext is not validly padded ({}): {}",
+                  e, String::from_utf8_lossy(&padded_msg));
+            output::result("message", None::<String>);
+            output::result("padding_error", e.to_string());
+        },
+    }
+    Ok(())
+}
@@ -0,0 +1,34 @@
+use std::error;
+
+use crypto_utils::exit::{self, Status};
+use crypto_utils::oracle::OracleError;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("The oracle failed: {0}")]
+    Oracle(#[from] OracleError),
+    #[error("No guess for byte {0} gave a valid padding")]
+    NoGuess(usize),
+    #[error("--command must not be empty")]
+    EmptyCommand,
+    #[error("--command takes exactly one CIPHERTEXT")]
+    CommandArgs,
+    #[error("The ciphertext is not valid hex")]
+    Hex,
+    #[error("The ciphertext must be an IV and at least one whole block")]
+    CiphertextLength,
+    #[error("Could not serve on port {port}: {source}")]
+    Serve { port: u16, source: Box<dyn error::Error + Send + Sync> },
+}
+
+impl Status for Error {
+    fn exit_code(&self) -> u8 {
+        match self {
+            Error::Oracle(_) => exit::ORACLE,
+            Error::NoGuess(_) => exit::FAILED,
+            Error::Serve { .. } => exit::IO,
+            _ => exit::USAGE,
+        }
+    }
+}
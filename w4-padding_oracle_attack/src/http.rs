@@ -0,0 +1,46 @@
+use std::time::Duration;
+
+use crypto_utils::encoding::to_hex;
+use crypto_utils::oracle::{Oracle, OracleError, OracleResponse};
+
+// Attempts per query before a network error is fatal
+const ATTEMPTS: usize = 3;
+
+// A web server like the course's, sent the ciphertext in hex appended to
+// the target URL. It answers 403 for a bad padding, 404 for a valid
+// padding but a malformed message, and 200 for a valid message.
+pub struct HttpOracle {
+    agent: ureq::Agent,
+    target: String,
+}
+
+impl HttpOracle {
+    pub fn new(target: &str) -> HttpOracle {
+        let agent = ureq::AgentBuilder::new()
+            .timeout(Duration::from_secs(30))
+            .build();
+        HttpOracle { agent, target: target.to_string() }
+    }
+}
+
+impl Oracle for HttpOracle {
+    type Response = OracleResponse;
+
+    fn query(&self, ciphertext: &[u8]) -> Result<OracleResponse, OracleError> {
+        let url = format!("{}{}", self.target, to_hex(ciphertext));
+        let mut attempt = 1;
+        loop {
+            match self.agent.get(&url).call() {
+                Ok(_) => return Ok(OracleResponse::Valid),
+                Err(ureq::Error::Status(404, _)) => return Ok(OracleResponse::BadMac),
+                Err(ureq::Error::Status(403, _)) => return Ok(OracleResponse::BadPadding),
+                Err(ureq::Error::Status(code, _)) => {
+                    let e = format!("unexpected HTTP status {} from {}", code, url);
+                    return Err(OracleError::Unreachable(e));
+                },
+                Err(e) if attempt == ATTEMPTS => return Err(OracleError::Unreachable(e.to_string())),
+                Err(_) => attempt += 1,
+            }
+        }
+    }
+}
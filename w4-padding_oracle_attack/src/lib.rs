@@ -0,0 +1,15 @@
+extern crate clap;
+extern crate crypto_utils;
+extern crate thiserror;
+extern crate tiny_http;
+extern crate tracing;
+extern crate ureq;
+extern crate w2_aes;
+
+mod attack;
+pub mod cli;
+pub mod error;
+mod http;
+mod serve;
+
+pub const BLOCK: usize = 16;
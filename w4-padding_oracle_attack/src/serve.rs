@@ -0,0 +1,80 @@
+use crypto_utils::encoding::{from_hex, to_hex};
+use crypto_utils::oracle::{Oracle, OracleError, OracleResponse};
+use crypto_utils::output;
+use crypto_utils::secret::{self, SecretKey};
+use crypto_utils::text;
+use tiny_http::{Response, Server};
+use tracing::{trace, warn};
+use w2_aes::{cbc_decrypt, cbc_encrypt, gen_iv};
+
+use crate::error::Error;
+use crate::BLOCK;
+
+// A stand-in for the course's server. It holds a message under a random
+// AES-128 key and rejects a CBC ciphertext for a bad padding, or for a bad
+// MAC if the padding is valid but the message isn't the original one, as
+// a MAC check would.
+pub struct LocalOracle {
+    key: SecretKey<BLOCK>,
+    message: Vec<u8>,
+}
+
+impl LocalOracle {
+    pub fn new(message: &[u8]) -> LocalOracle {
+        LocalOracle { key: secret::random_secret_key(), message: message.to_vec() }
+    }
+
+    // A random IV followed by the CBC encryption of the padded message
+    pub fn encrypt(&self) -> Vec<u8> {
+        cbc_encrypt(&self.key, &gen_iv(), &self.message)
+    }
+}
+
+impl Oracle for LocalOracle {
+    type Response = OracleResponse;
+
+    fn query(&self, ciphertext: &[u8]) -> Result<OracleResponse, OracleError> {
+        match cbc_decrypt(&self.key, ciphertext) {
+            Ok(msg) if msg == self.message => Ok(OracleResponse::Valid),
+            Ok(_) => Ok(OracleResponse::BadMac),
+            Err(w2_aes::error::Error::Padding(_)) => Ok(OracleResponse::BadPadding),
+            Err(e) => Err(OracleError::Rejected(e.to_string())),
+        }
+    }
+}
+
+// Serves a LocalOracle for the message at http://127.0.0.1:port/po?er=
+// until killed, with the course's status codes: 403 for a bad padding, 404
+// for a bad MAC and 200 for a valid message. Malformed queries get a 400.
+pub fn serve(port: u16, message: &[u8]) -> Result<(), Error> {
+    let server = match Server::http(("127.0.0.1", port)) {
+        Ok(server) => server,
+        Err(source) => return output::finish(Err(Error::Serve { port, source })),
+    };
+    let oracle = LocalOracle::new(message);
+    let url = format!("http://127.0.0.1:{}/po?er=", port);
+    let ciphertext = to_hex(&oracle.encrypt());
+    text!("Serving a padding oracle at {}", url);
+    text!("Target ciphertext: {}", ciphertext);
+    text!("Attack it with: ./target/debug/w4-padding_oracle_attack \"{}\" {}", url, ciphertext);
+    // The report comes as soon as the server listens, since it never ends
+    output::result("url", url);
+    output::result("ciphertext", ciphertext);
+    let listening: Result<(), Error> = Ok(());
+    output::finish(listening)?;
+
+    for request in server.incoming_requests() {
+        let query = request.url().strip_prefix("/po?er=").and_then(|c| from_hex(c).ok());
+        let status = match query.map(|c| oracle.query(&c)) {
+            Some(Ok(OracleResponse::Valid)) => 200,
+            Some(Ok(OracleResponse::BadMac)) => 404,
+            Some(Ok(OracleResponse::BadPadding)) => 403,
+            _ => 400,
+        };
+        trace!(status, "Answered a query");
+        if let Err(e) = request.respond(Response::empty(status)) {
+            warn!("Could not respond: {}", e);
+        }
+    }
+    Ok(())
+}
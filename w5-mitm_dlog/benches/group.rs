@@ -0,0 +1,65 @@
+extern crate criterion;
+extern crate num_bigint;
+extern crate w5_mitm_dlog;
+
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use num_bigint::BigUint;
+use w5_mitm_dlog::group::{Group, Zp};
+use w5_mitm_dlog::mitm::build_table;
+use w5_mitm_dlog::Progress;
+
+// The primes of dh-demo, subgroup-attack and the assignment, each with its
+// generator
+fn groups() -> Vec<(Zp, BigUint)> {
+    let primes: [(&[u8], &[u8]); 3] = [
+        (b"18446744073709550147", b"2"),
+        (b"321357171713570770250973175486410895067", b"2"),
+        (b"1340780792994259709957402499820584612747\
+            9365820592393377723561443721764030073546\
+            9768018742981669034276900318581864860508\
+            53753882811946569946433649006084171",
+         b"1171782988036620700951611759633536708855\
+           8084999998952205599979459063929499736583\
+           7466705721764714603129285948296754282794\
+           66566527115212748467589894601965568"),
+    ];
+    primes.iter().map(|&(p, g)| {
+        let p = BigUint::parse_bytes(p, 10).unwrap();
+        let g = BigUint::parse_bytes(g, 10).unwrap();
+        (Zp::new(p), g)
+    }).collect()
+}
+
+// One exponentiation by a full-size exponent, as for every inverse and
+// every shift of an instance
+fn exp(c: &mut Criterion) {
+    let mut group = c.benchmark_group("exp");
+    for (zp, g) in groups() {
+        let a = zp.element(&g);
+        let n = &zp.p - BigUint::from(2u32);
+        group.bench_with_input(BenchmarkId::from_parameter(format!("{} bits", zp.p.bits())), &n,
+                               |b, n| b.iter(|| zp.exp(black_box(&a), n)));
+    }
+    group.finish();
+}
+
+// Building the MITM table of h * g^(-x1) in the assignment's group, the
+// bulk of a run, for tables up to 2^16 entries (the assignment's has 2^20)
+fn table(c: &mut Criterion) {
+    let (zp, g) = groups().pop().unwrap();
+    let g_inverse = zp.inverse(&zp.element(&g));
+    let h = zp.element(&BigUint::from(3u32));
+    let progress = Progress::default();
+    let mut group = c.benchmark_group("table");
+    for &entries in &[1u64 << 10, 1 << 12, 1 << 14, 1 << 16] {
+        group.throughput(Throughput::Elements(entries));
+        group.bench_with_input(BenchmarkId::from_parameter(entries), &entries,
+                               |b, &entries| b.iter(|| build_table(&zp, &h, &g_inverse, entries, &progress)));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, exp, table);
+criterion_main!(benches);
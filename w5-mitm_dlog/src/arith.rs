@@ -0,0 +1,36 @@
+use std::cmp;
+
+use num_bigint::BigUint;
+
+// splitmix64 finalizer
+pub fn mix(mut z: u64) -> u64 {
+    z = z.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+// Mixes the last 8 bytes of a big-endian encoding
+pub fn bytes_hash(bytes: &[u8]) -> u64 {
+    let mut low = [0u8; 8];
+    let n = cmp::min(bytes.len(), 8);
+    low[8 - n..].copy_from_slice(&bytes[bytes.len() - n..]);
+    mix(u64::from_be_bytes(low))
+}
+
+pub fn low_u64(x: &BigUint) -> u64 {
+    let bytes = x.to_bytes_le();
+    let mut low = [0u8; 8];
+    let n = cmp::min(bytes.len(), 8);
+    low[..n].copy_from_slice(&bytes[..n]);
+    u64::from_le_bytes(low)
+}
+
+// Returns x as a u64 if it fits
+pub fn to_u64(x: &BigUint) -> Option<u64> {
+    if x.bits() <= 64 {
+        Some(low_u64(x))
+    } else {
+        None
+    }
+}
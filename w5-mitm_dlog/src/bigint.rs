@@ -0,0 +1,110 @@
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use num_bigint::BigUint;
+
+#[cfg(not(feature = "gmp"))]
+use crate::montgomery::{Montgomery, Residue};
+
+// The arithmetic modulo a fixed p that Zp needs. The pure Rust default
+// keeps elements in Montgomery form; the gmp feature swaps in GMP through
+// rug, which is faster still for large primes.
+pub trait Modulus: Clone + Debug + Eq {
+    type Int: Clone + Eq + Hash + Debug;
+
+    // Tells representations apart, since hashes of elements depend on it
+    const NAME: &'static str;
+
+    fn new(p: &BigUint) -> Self;
+
+    fn element(&self, n: &BigUint) -> Self::Int;
+
+    fn to_bytes_be(&self, a: &Self::Int) -> Vec<u8>;
+
+    fn mul(&self, a: &Self::Int, b: &Self::Int) -> Self::Int;
+
+    fn pow(&self, a: &Self::Int, e: &BigUint) -> Self::Int;
+
+    // Low word of the representation, for hashing
+    fn low_u64(&self, a: &Self::Int) -> u64;
+}
+
+#[cfg(not(feature = "gmp"))]
+pub type Backend = Montgomery;
+
+#[cfg(feature = "gmp")]
+pub type Backend = Gmp;
+
+pub type Int = <Backend as Modulus>::Int;
+
+#[cfg(not(feature = "gmp"))]
+impl Modulus for Montgomery {
+    type Int = Residue;
+
+    const NAME: &'static str = "montgomery";
+
+    fn new(p: &BigUint) -> Montgomery {
+        Montgomery::new(p)
+    }
+
+    fn element(&self, n: &BigUint) -> Residue {
+        self.residue(n)
+    }
+
+    fn to_bytes_be(&self, a: &Residue) -> Vec<u8> {
+        self.value(a).to_bytes_be()
+    }
+
+    fn mul(&self, a: &Residue, b: &Residue) -> Residue {
+        Montgomery::mul(self, a, b)
+    }
+
+    fn pow(&self, a: &Residue, e: &BigUint) -> Residue {
+        Montgomery::pow(self, a, e)
+    }
+
+    fn low_u64(&self, a: &Residue) -> u64 {
+        Montgomery::low_u64(self, a)
+    }
+}
+
+#[cfg(feature = "gmp")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Gmp {
+    p: rug::Integer,
+}
+
+#[cfg(feature = "gmp")]
+impl Modulus for Gmp {
+    type Int = rug::Integer;
+
+    const NAME: &'static str = "gmp";
+
+    fn new(p: &BigUint) -> Gmp {
+        Gmp { p: rug::Integer::from_digits(&p.to_bytes_be(), rug::integer::Order::Msf) }
+    }
+
+    fn element(&self, n: &BigUint) -> rug::Integer {
+        rug::Integer::from_digits(&n.to_bytes_be(), rug::integer::Order::Msf) % &self.p
+    }
+
+    fn to_bytes_be(&self, a: &rug::Integer) -> Vec<u8> {
+        a.to_digits(rug::integer::Order::Msf)
+    }
+
+    fn mul(&self, a: &rug::Integer, b: &rug::Integer) -> rug::Integer {
+        let mut product = rug::Integer::from(a * b);
+        product %= &self.p;
+        product
+    }
+
+    fn pow(&self, a: &rug::Integer, e: &BigUint) -> rug::Integer {
+        let e = rug::Integer::from_digits(&e.to_bytes_be(), rug::integer::Order::Msf);
+        // Only fails for negative exponents without an inverse
+        rug::Integer::from(a.pow_mod_ref(&e, &self.p).unwrap())
+    }
+
+    fn low_u64(&self, a: &rug::Integer) -> u64 {
+        a.to_u64_wrapping()
+    }
+}
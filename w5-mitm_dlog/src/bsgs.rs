@@ -0,0 +1,153 @@
+use std::io;
+use std::io::prelude::*;
+use std::sync::Arc;
+
+use num_bigint::BigUint;
+
+use crate::group::Group;
+use crate::mitm::{split, Table, TableLookup};
+use crate::progress::Progress;
+use crate::{DiscreteLogSolver, Instance};
+
+const TABLE_MAGIC: &[u8; 8] = b"W5BSGS\x00\x01";
+
+// The baby steps g^j for j in [0, m), keyed by truncated hashes like the
+// meet-in-the-middle table
+#[derive(Debug, Clone)]
+pub struct BabySteps {
+    pub m: u64,
+    table: Table,
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn read_u64<R: Read>(r: &mut R) -> io::Result<u64> {
+    let mut buf = [0u8; 8];
+    r.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+fn read_bytes<R: Read>(r: &mut R) -> io::Result<Vec<u8>> {
+    let mut len = [0u8; 4];
+    r.read_exact(&mut len)?;
+    let mut buf = vec![0; u32::from_le_bytes(len) as usize];
+    r.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+fn write_bytes<W: Write>(w: &mut W, bytes: &[u8]) -> io::Result<()> {
+    w.write_all(&(bytes.len() as u32).to_le_bytes())?;
+    w.write_all(bytes)
+}
+
+impl BabySteps {
+    pub fn build<G: Group>(group: &G, g: &G::Element, m: u64, progress: &Progress) -> BabySteps {
+        let mut table = Table::with_capacity(m as usize);
+        progress.phase("baby steps", m);
+        group.for_each_power_hash(&group.identity(), g, m, |j, key| {
+            progress.tick();
+            progress.insert();
+            // the smallest exponent of a key comes first, which is the one
+            // that solves if g has order below m
+            table.insert(key, j);
+        });
+        BabySteps { m, table }
+    }
+
+    // Giant steps h * g^(-m*i) for i in [0, m) until one lands in the table
+    pub fn giant_steps<G: Group + Clone>(&self, instance: &Instance<G>,
+                                         progress: &Progress) -> Option<BigUint> {
+        let group = &instance.group;
+        let m = self.m;
+
+        let giant = group.inverse(&group.exp(&instance.g, &BigUint::from(m)));
+        let mut y = instance.h.clone();
+        progress.phase("giant steps", m);
+        for i in 0..m {
+            progress.tick();
+            progress.lookup();
+            for j in self.table.exponents(group.hash(&y)) {
+                let x = BigUint::from(i) * BigUint::from(m) + BigUint::from(j);
+                if instance.is_solution(&x) {
+                    return Some(x);
+                }
+            }
+            y = group.op(&y, &giant);
+        }
+        None
+    }
+
+    // Entries are stored as (key, j) pairs in order of j: 12 bytes each,
+    // since j < m <= 2^32. The file is bound to the group and base it was
+    // built for.
+    pub fn write_to<G: Group, W: Write>(&self, group: &G, g: &G::Element, w: &mut W) -> io::Result<()> {
+        let mut entries: Vec<(u64, u64)> = self.table.entries().collect();
+        entries.sort_unstable_by_key(|&(_, j)| j);
+
+        w.write_all(TABLE_MAGIC)?;
+        write_bytes(w, &group.parameters())?;
+        write_bytes(w, &group.serialize(g))?;
+        w.write_all(&self.m.to_le_bytes())?;
+        w.write_all(&(entries.len() as u64).to_le_bytes())?;
+        for (key, j) in entries {
+            w.write_all(&key.to_le_bytes())?;
+            w.write_all(&(j as u32).to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    pub fn read_from<G: Group, R: Read>(group: &G, g: &G::Element, r: &mut R) -> io::Result<BabySteps> {
+        let mut magic = [0u8; 8];
+        r.read_exact(&mut magic)?;
+        if &magic != TABLE_MAGIC {
+            return Err(invalid_data("not a precomputed table"));
+        }
+        if read_bytes(r)? != group.parameters() || read_bytes(r)? != group.serialize(g) {
+            return Err(invalid_data("table was precomputed for a different group or base"));
+        }
+
+        let m = read_u64(r)?;
+        let len = read_u64(r)?;
+        if len > m {
+            return Err(invalid_data("table has more entries than baby steps"));
+        }
+        let mut table = Table::with_capacity(len as usize);
+        let mut j = [0u8; 4];
+        for _ in 0..len {
+            let key = read_u64(r)?;
+            r.read_exact(&mut j)?;
+            table.insert(key, u64::from(u32::from_le_bytes(j)));
+        }
+        Ok(BabySteps { m, table })
+    }
+}
+
+// Shanks' baby-step giant-step: a table of baby steps g^j for j in [0, m),
+// then giant steps h * g^(-m*i) until one lands in the table. Unlike the
+// meet-in-the-middle table, the baby steps don't depend on h, so they can
+// be precomputed once and reused for many targets.
+#[derive(Debug, Default, Clone)]
+pub struct Bsgs {
+    // A precomputed table; built from scratch for each instance if None
+    pub baby_steps: Option<BabySteps>,
+    pub progress: Arc<Progress>,
+}
+
+impl<G: Group + Clone> DiscreteLogSolver<G> for Bsgs {
+    fn name(&self) -> &'static str {
+        "bsgs"
+    }
+
+    fn find(&self, instance: &Instance<G>) -> io::Result<Option<BigUint>> {
+        Ok(match self.baby_steps {
+            Some(ref baby_steps) => baby_steps.giant_steps(instance, &self.progress),
+            None => {
+                let m = split(instance.bound);
+                BabySteps::build(&instance.group, &instance.g, m, &self.progress)
+                    .giant_steps(instance, &self.progress)
+            },
+        })
+    }
+}
@@ -0,0 +1,173 @@
+use crypto_utils::bigint::{parse_big, parse_bigint};
+use crypto_utils::modular::{crt, gcd, mod_inverse, mod_pow};
+use num_bigint::BigUint;
+use rand::Rng;
+
+use crate::params::is_probable_prime;
+use crate::pohlig_hellman::smooth_part;
+
+// Miller-Rabin rounds for isprime and for the cofactor left by trial division
+const PRIME_ROUNDS: u32 = 32;
+
+pub const HELP: &str = "\
+modpow A E M        A^E mod M; a negative E raises the inverse of A
+modinv A M          the inverse of A mod M
+gcd A B...          greatest common divisor
+crt R1 M1 R2 M2...  x with x = Ri (mod Mi), for pairwise coprime Mi
+order G P [N]       the order of G mod a prime P, given a multiple N of it
+                    (default P - 1)
+factor N            prime factors of N, by trial division up to 2^20 and a
+                    cofactor that must be prime
+isprime N           Miller-Rabin with 32 random bases
+help                this list
+quit                leave (or end of input)
+Integers are decimal or 0x-prefixed hex, with underscores between digits;
+_ stands for the last result.";
+
+// A calc session: evaluates one command line at a time, keeping the last
+// result for _
+#[derive(Debug, Default)]
+pub struct Calc {
+    last: Option<BigUint>,
+}
+
+impl Calc {
+    pub fn new() -> Calc {
+        Calc::default()
+    }
+
+    fn parse(&self, arg: &str) -> Result<BigUint, String> {
+        match arg {
+            "_" => self.last.clone().ok_or_else(|| "no result yet for _".to_string()),
+            _ => parse_big(arg).ok_or_else(|| format!("not an integer: {}", arg)),
+        }
+    }
+
+    fn args(&self, args: &[&str], count: usize, usage: &str) -> Result<Vec<BigUint>, String> {
+        if args.len() != count {
+            return Err(format!("usage: {}", usage));
+        }
+        args.iter().map(|arg| self.parse(arg)).collect()
+    }
+
+    // The output of a command line, or None for an empty one. Commands that
+    // compute a single number remember it as _.
+    pub fn eval<R: Rng>(&mut self, line: &str, rng: &mut R) -> Result<Option<String>, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let (command, args) = match words.split_first() {
+            Some((command, args)) => (*command, args),
+            None => return Ok(None),
+        };
+        let zero = BigUint::from(0u32);
+
+        let result = match command {
+            "modpow" => {
+                if args.len() != 3 {
+                    return Err("usage: modpow A E M".to_string());
+                }
+                let (a, m) = (self.parse(args[0])?, self.parse(args[2])?);
+                let e = match args[1] {
+                    "_" => self.parse(args[1])?.into(),
+                    e => parse_bigint(e).ok_or_else(|| format!("not an integer: {}", e))?,
+                };
+                if m == zero {
+                    return Err("M must be positive".to_string());
+                }
+                mod_pow(&a, &e, &m).ok_or_else(|| format!("{} has no inverse mod {}", a, m))?
+            },
+            "modinv" => {
+                let v = self.args(args, 2, "modinv A M")?;
+                if v[1] == zero {
+                    return Err("M must be positive".to_string());
+                }
+                mod_inverse(&v[0], &v[1])
+                    .ok_or_else(|| format!("{} has no inverse mod {}", v[0], v[1]))?
+            },
+            "gcd" => {
+                if args.is_empty() {
+                    return Err("usage: gcd A B...".to_string());
+                }
+                let v = self.args(args, args.len(), "")?;
+                v.iter().fold(zero, |acc, n| gcd(&acc, n))
+            },
+            "crt" => {
+                if args.is_empty() || args.len() % 2 != 0 {
+                    return Err("usage: crt R1 M1 R2 M2...".to_string());
+                }
+                let v = self.args(args, args.len(), "")?;
+                if v.chunks(2).any(|pair| pair[1] == zero) {
+                    return Err("the moduli must be positive".to_string());
+                }
+                let congruences: Vec<_> = v.chunks(2)
+                    .map(|pair| (&pair[0] % &pair[1], pair[1].clone()))
+                    .collect();
+                let (x, modulus) = crt(&congruences)
+                    .ok_or_else(|| "the moduli are not pairwise coprime".to_string())?;
+                self.last = Some(x.clone());
+                return Ok(Some(format!("{} (mod {})", x, modulus)));
+            },
+            "order" => {
+                if args.len() != 2 && args.len() != 3 {
+                    return Err("usage: order G P [N]".to_string());
+                }
+                let v = self.args(args, args.len(), "")?;
+                let one = BigUint::from(1u32);
+                let (g, p) = (&v[0], &v[1]);
+                if !is_probable_prime(p, PRIME_ROUNDS, rng) {
+                    return Err(format!("{} is not prime", p));
+                }
+                if g % p == zero {
+                    return Err(format!("{} is 0 mod {}", g, p));
+                }
+                let n = v.get(2).cloned().unwrap_or_else(|| p - &one);
+                if g.modpow(&n, p) != one {
+                    return Err(format!("{}^{} != 1 mod {}, so {} is not a multiple of its order",
+                                       g, n, p, n));
+                }
+                let mut order = n.clone();
+                for q in self.prime_factors(&n, rng)?.into_iter().map(|(q, _)| q) {
+                    while &order % &q == zero && g.modpow(&(&order / &q), p) == one {
+                        order /= &q;
+                    }
+                }
+                order
+            },
+            "factor" => {
+                let n = &self.args(args, 1, "factor N")?[0];
+                let factors: Vec<String> = self.prime_factors(n, rng)?.iter()
+                    .map(|(q, e)| if *e == 1 { q.to_string() } else { format!("{}^{}", q, e) })
+                    .collect();
+                if factors.is_empty() {
+                    return Ok(Some(format!("{} has no prime factors", n)));
+                }
+                return Ok(Some(format!("{} = {}", n, factors.join(" * "))));
+            },
+            "isprime" => {
+                let n = &self.args(args, 1, "isprime N")?[0];
+                let prime = is_probable_prime(n, PRIME_ROUNDS, rng);
+                return Ok(Some(if prime { "probably prime" } else { "composite" }.to_string()));
+            },
+            "help" => return Ok(Some(HELP.to_string())),
+            other => return Err(format!("unknown command: {} (try help)", other)),
+        };
+        self.last = Some(result.clone());
+        Ok(Some(result.to_string()))
+    }
+
+    // The prime factorization of n by trial division, with a cofactor that
+    // passes Miller-Rabin taken as prime
+    fn prime_factors<R: Rng>(&self, n: &BigUint, rng: &mut R) -> Result<Vec<(BigUint, u32)>, String> {
+        if *n == BigUint::from(0u32) {
+            return Err("0 has no factorization".to_string());
+        }
+        let (factors, rest) = smooth_part(n);
+        let mut factors: Vec<_> = factors.into_iter().map(|(q, e)| (BigUint::from(q), e)).collect();
+        if rest > BigUint::from(1u32) {
+            if !is_probable_prime(&rest, PRIME_ROUNDS, rng) {
+                return Err(format!("could not factor the composite cofactor {}", rest));
+            }
+            factors.push((rest, 1));
+        }
+        Ok(factors)
+    }
+}
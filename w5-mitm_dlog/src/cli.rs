@@ -0,0 +1,1441 @@
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use clap::builder::RangedU64ValueParser;
+use clap::{Parser, Subcommand};
+use crypto_utils::bigint::parse_big;
+use crypto_utils::encoding::to_hex;
+use crypto_utils::interrupt::{self, Partial};
+use crypto_utils::log::{self, LogArgs};
+use crypto_utils::modular::crt;
+use crypto_utils::output::{self, json, OutputArgs, Value};
+use crypto_utils::random::{self, RandomArgs};
+use crypto_utils::size::parse_size;
+use crypto_utils::text;
+use num_bigint::BigUint;
+use rand::Rng;
+use tracing::error;
+
+use crate::{calc, distributed, params, subgroup, validate};
+use crate::error::Error;
+use crate::arith::to_u64;
+#[cfg(all(feature = "gpu", not(feature = "gmp")))]
+use crate::gpu::Gpu;
+use crate::mitm::{self, split};
+use crate::{pohlig_hellman, BabySteps, Bsgs, Counted, Curve, DiscreteLogSolver, DiskOptions,
+            Group, Instance, Kangaroo, Mitm, PhaseStats, PohligHellman, Point, Progress, Zp};
+
+fn parse_u64(val: &str) -> Option<u64> {
+    to_u64(&parse_big(val)?)
+}
+
+// Parses a curve point given as "x,y"
+fn parse_point(val: &str) -> Option<Point> {
+    let mut parts = val.splitn(2, ',');
+    let x = parse_big(parts.next()?)?;
+    let y = parse_big(parts.next()?)?;
+    Some(Point::Affine(x, y))
+}
+
+// Parses a factorization such as "2,3^2,5"
+fn parse_factors(list: &str) -> Option<Vec<(u64, u32)>> {
+    list.split(',')
+        .map(|factor| {
+            let mut parts = factor.splitn(2, '^');
+            let q = parse_u64(parts.next()?)?;
+            let e = match parts.next() {
+                Some(e) => e.trim().parse().ok()?,
+                None => 1,
+            };
+            Some((q, e))
+        })
+        .collect()
+}
+
+fn parse_range(range: &str) -> Option<(u64, u64)> {
+    let mut parts = range.splitn(2, "..");
+    let lo = parse_u64(parts.next()?)?;
+    let hi = parse_u64(parts.next()?)?;
+    if lo <= hi && hi - lo < u64::MAX {
+        Some((lo, hi))
+    } else {
+        None
+    }
+}
+
+fn parse_big_arg(val: &str) -> Result<BigUint, String> {
+    parse_big(val).ok_or_else(|| "not a decimal or 0x hex number".to_string())
+}
+
+fn parse_range_arg(val: &str) -> Result<(u64, u64), String> {
+    parse_range(val).ok_or_else(|| "expected A..B with A <= B".to_string())
+}
+
+fn parse_size_arg(val: &str) -> Result<u64, String> {
+    parse_size(val).ok_or_else(|| "not a size, e.g. 512M or 2G".to_string())
+}
+
+fn title(algorithm: &str) -> &'static str {
+    match algorithm {
+        "mitm" => "Meet-in-the-Middle Attack (MITM)",
+        "bsgs" => "Baby-Step Giant-Step (BSGS)",
+        "rho" => "Pollard's Rho Algorithm",
+        "kangaroo" => "Pollard's Kangaroo (Lambda) Algorithm",
+        "pohlig-hellman" => "Pohlig-Hellman Decomposition",
+        _ => "",
+    }
+}
+
+fn curve(name: &str) -> Option<Curve> {
+    match name {
+        "toy" => Some(Curve::toy()),
+        #[cfg(feature = "secp256k1")]
+        "secp256k1" => Some(Curve::secp256k1()),
+        _ => None,
+    }
+}
+
+fn pohlig_hellman_solver<G>(args: &SolverArgs, instance: &Instance<G>,
+                            progress: &Arc<Progress>) -> Result<PohligHellman<G>, Error>
+    where G: Group + Clone + 'static
+{
+    let factors = factors(args, instance)?;
+    let backend = &args.subgroup_algorithm;
+    let subgroup = crate::solver_with_progress(backend, progress.clone())
+        .ok_or_else(|| Error::UnknownAlgorithm(backend.clone()))?;
+
+    Ok(PohligHellman::new(factors, subgroup))
+}
+
+// The factorization of the group order from --factors, or by trial division
+fn factors<G>(args: &SolverArgs, instance: &Instance<G>) -> Result<Vec<(u64, u32)>, Error>
+    where G: Group + Clone
+{
+    let order = instance.group_order().ok_or(Error::NoOrder)?;
+    match &args.factors {
+        Some(list) => {
+            let factors = parse_factors(list).ok_or_else(|| Error::Factors(list.clone()))?;
+            let product = factors.iter()
+                .flat_map(|&(q, e)| (0..e).map(move |_| q))
+                .fold(BigUint::from(1u32), |acc, q| acc * BigUint::from(q));
+            if product != order {
+                return Err(Error::FactorsProduct { list: list.clone(), order });
+            }
+            Ok(factors)
+        },
+        None => pohlig_hellman::factor(&order).map_err(Error::Unfactored),
+    }
+}
+
+// Seconds per group operation and hash, the step of every algorithm, timed
+// over a few thousand of them
+fn time_per_op<G: Group>(instance: &Instance<G>) -> f64 {
+    let (group, g) = (&instance.group, &instance.g);
+    let samples = 1 << 12;
+    let start = Instant::now();
+    let mut y = g.clone();
+    for _ in 0..samples {
+        y = group.op(&y, g);
+        group.hash(&y);
+    }
+    start.elapsed().as_secs_f64() / f64::from(samples)
+}
+
+// Chooses the MITM table size for a memory budget and prints the split
+// with its predicted cost, timing a few group operations to estimate it
+fn plan<G: Group>(instance: &Instance<G>, max_bytes: u64) -> Result<u64, Error> {
+    let b1 = mitm::plan_split(instance.bound, max_bytes).ok_or(Error::MaxMemory(max_bytes))?;
+    let b2 = mitm::scan_length(instance.bound, b1);
+
+    let per_op = time_per_op(instance);
+    let ops = b1 as f64 + b2 as f64;
+    text!("Split: {} table entries ({:.1} MiB) x {} scan steps",
+          b1, mitm::table_bytes(b1) as f64 / f64::from(1 << 20), b2);
+    text!("Predicted cost: {} group operations, at most about {:.2?}",
+          ops, Duration::from_secs_f64(per_op * ops));
+    output::result("split", json!({
+        "table_entries": b1,
+        "table_bytes": mitm::table_bytes(b1),
+        "scan_steps": b2,
+        "group_operations": ops,
+        "predicted_time": per_op * ops,
+    }));
+    Ok(b1)
+}
+
+// Builds the baby steps for the given range and writes them to --table
+fn precompute<G: Group>(path: &Path, instance: &Instance<G>, progress: &Progress)
+                        -> Result<(), Error> {
+    let m = split(instance.bound);
+    let baby_steps = BabySteps::build(&instance.group, &instance.g, m, progress);
+
+    let write_err = |source| Error::Write { path: path.to_path_buf(), source };
+    let file = File::create(path).map_err(write_err)?;
+    let partial = Partial::new(path);
+    baby_steps.write_to(&instance.group, &instance.g, &mut BufWriter::new(file))
+        .map_err(write_err)?;
+    drop(partial);
+    text!("Wrote {} baby steps to {}", m, path.display());
+    output::result("baby_steps", m);
+    output::result("table", path.display().to_string());
+    Ok(())
+}
+
+fn load_table<G: Group>(path: &Path, instance: &Instance<G>) -> Result<BabySteps, Error> {
+    let baby_steps = File::open(path).and_then(|file| {
+        BabySteps::read_from(&instance.group, &instance.g, &mut BufReader::new(file))
+    }).map_err(|source| Error::Read { path: path.to_path_buf(), source })?;
+
+    if u128::from(baby_steps.m) * u128::from(baby_steps.m) < u128::from(instance.bound) {
+        let covered = u128::from(baby_steps.m).pow(2);
+        return Err(Error::SmallTable { path: path.to_path_buf(), covered });
+    }
+    Ok(baby_steps)
+}
+
+// Reads one target per line, skipping blank lines and # comments
+fn read_targets<E, F>(path: &Path, parse: F) -> Result<Vec<(String, E)>, Error>
+    where F: Fn(&str) -> Option<E>
+{
+    let read_err = |source| Error::Read { path: path.to_path_buf(), source };
+    let file = File::open(path).map_err(read_err)?;
+    let mut targets = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(read_err)?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let h = parse(line)
+            .ok_or_else(|| Error::Target { path: path.to_path_buf(), line: line.to_string() })?;
+        targets.push((line.to_string(), h));
+    }
+    Ok(targets)
+}
+
+// Builds (or loads) the baby steps once and runs only the giant steps for
+// each target
+fn batch<G>(args: &SolveArgs, instance: &Instance<G>, lo: u64,
+            targets: Vec<(String, G::Element)>, progress: &Progress) -> Result<(), Error>
+    where G: Group + Clone
+{
+    let start = Instant::now();
+    let baby_steps = match &args.solver.table {
+        Some(path) => load_table(path, instance)?,
+        None => BabySteps::build(&instance.group, &instance.g, split(instance.bound), progress),
+    };
+
+    text!("{}", title("bsgs"));
+    let mut solved = 0;
+    let mut solutions = Vec::new();
+    for (i, (line, h)) in targets.iter().enumerate() {
+        // The solutions so far have been printed
+        if let Some(path) = args.targets.as_ref().filter(|_| i > 0) {
+            interrupt::resume(Some(format!("run again on the targets of {} after the first {}",
+                                           path.display(), i)));
+        }
+        let target = Instance { h: h.clone(), ..instance.clone() }.shifted(lo, instance.bound);
+        let x = baby_steps.giant_steps(&target, progress).map(|x| x + BigUint::from(lo));
+        match &x {
+            Some(x) => {
+                text!("{}: {}", line, x);
+                solved += 1;
+            },
+            None => text!("{}: not found", line),
+        }
+        solutions.push(json!({ "target": line, "x": x.map(|x| x.to_string()) }));
+    }
+    interrupt::resume(None);
+    text!("Solved {} of {} targets", solved, targets.len());
+    text!("Time: {:.2?}", start.elapsed());
+    output::result("solutions", solutions);
+    output::result("solved", solved);
+    output::timing("solve", start.elapsed());
+    Ok(())
+}
+
+#[cfg(all(feature = "gpu", not(feature = "gmp")))]
+fn with_gpu(zp: Zp) -> Result<Zp, Error> {
+    match Gpu::new() {
+        Some(gpu) => {
+            tracing::info!("Building tables on {}", gpu.name);
+            Ok(zp.with_gpu(Arc::new(gpu)))
+        },
+        None => {
+            tracing::warn!("No GPU adapter found; building tables on the CPU");
+            Ok(zp)
+        },
+    }
+}
+
+#[cfg(not(all(feature = "gpu", not(feature = "gmp"))))]
+fn with_gpu(_: Zp) -> Result<Zp, Error> {
+    Err(Error::NoGpu)
+}
+
+// The interval [lo, hi] given by --range, or [0, bound)
+fn range(args: &GroupArgs, bound: u64) -> (u64, u64) {
+    args.range.unwrap_or((0, bound - 1))
+}
+
+// Splits the MITM search into jobs and hands them out to --worker processes
+fn coordinate(args: &SolveArgs, instance: Instance<Zp>, addr: &str) -> Result<(), Error> {
+    let (lo, hi) = range(&args.group, instance.bound);
+    let instance = instance.shifted(lo, hi - lo + 1);
+    let jobs = distributed::plan_jobs(&instance, args.table_shards, args.scan_shards);
+
+    text!("Distributed Meet-in-the-Middle Attack (MITM)");
+    text!("Waiting for workers on {} with {} jobs", addr, jobs.len());
+    output::parameter("coordinator", addr);
+    output::parameter("jobs", jobs.len());
+    let start = Instant::now();
+    let x = distributed::coordinate(addr, instance, jobs)
+        .map_err(|source| Error::Listen { addr: addr.to_string(), source })?
+        .map(|x| x + BigUint::from(lo));
+    match &x {
+        Some(x) => {
+            text!("x: {}", x);
+            text!("Time: {:.2?}", start.elapsed());
+        },
+        None => text!("x not found"),
+    }
+    output::timing("solve", start.elapsed());
+    output::result("x", x.map(|x| x.to_string()).ok_or(Error::NotFound)?);
+    Ok(())
+}
+
+// Simulates a Diffie-Hellman exchange with secrets drawn from --range, then
+// an active man-in-the-middle who swaps in her own public key and solves
+// the intercepted ones for the secrets behind them. Elements are shown as
+// their serialization in hex.
+fn dh_demo<G>(args: &SolverArgs, instance: &Instance<G>, lo: u64, algorithm: &str,
+              progress: Arc<Progress>) -> Result<(), Error>
+    where G: Group + Clone + 'static
+{
+    let hi = lo + (instance.bound - 1);
+    let (group, g) = (&instance.group, &instance.g);
+    let show = |a: &G::Element| to_hex(&group.serialize(a));
+    let mut rng = random::rng();
+    let mut keypair = || {
+        let secret = rng.gen_range(lo..=hi);
+        (secret, group.exp(g, &BigUint::from(secret)))
+    };
+
+    text!("Diffie-Hellman Key Exchange");
+    let (a, big_a) = keypair();
+    let (b, big_b) = keypair();
+    text!("Alice picks a = {} and sends A = g^a = {}", a, show(&big_a));
+    text!("Bob picks b = {} and sends B = g^b = {}", b, show(&big_b));
+    output::result("alice", json!({ "secret": a.to_string(), "public": show(&big_a) }));
+    output::result("bob", json!({ "secret": b.to_string(), "public": show(&big_b) }));
+    let shared = group.exp(&big_b, &BigUint::from(a));
+    if shared != group.exp(&big_a, &BigUint::from(b)) {
+        return Err(Error::Disagree);
+    }
+    text!("Both compute g^ab = {}", show(&shared));
+    text!();
+    output::result("shared", show(&shared));
+
+    text!("Man-in-the-Middle Attack");
+    let (m, big_m) = keypair();
+    text!("Mallory picks m = {} and replaces both A and B with M = g^m = {}", m, show(&big_m));
+    output::result("mallory", json!({ "secret": m.to_string(), "public": show(&big_m) }));
+    let alice_key = group.exp(&big_m, &BigUint::from(a));
+    let bob_key = group.exp(&big_m, &BigUint::from(b));
+    text!("Alice computes M^a = {}", show(&alice_key));
+    text!("Bob computes M^b = {}", show(&bob_key));
+
+    // Only the targets differ, so one solver does for both
+    let start = Instant::now();
+    let intercepted = |public: &G::Element| {
+        Instance { h: public.clone(), ..instance.clone() }.shifted(lo, instance.bound)
+    };
+    let solver = build_solver(args, &intercepted(&big_a), algorithm, progress)?;
+    text!("Mallory solves A and B with {}", title(solver.name()));
+    let mut recovered = Vec::new();
+    for &(name, public) in &[("Alice", &big_a), ("Bob", &big_b)] {
+        let solution = solver.solve(&intercepted(public)).map_err(Error::Solve)?
+            .ok_or(Error::NoSecret(name))?;
+        recovered.push(solution.x + BigUint::from(lo));
+    }
+    let (a_found, b_found) = (&recovered[0], &recovered[1]);
+    text!("Mallory finds a = {} and b = {}", a_found, b_found);
+
+    let with_alice = group.exp(&big_m, a_found);
+    let with_bob = group.exp(&big_m, b_found);
+    let check = |key: &G::Element, expected: &G::Element| {
+        if key == expected { "matches" } else { "does not match" }
+    };
+    text!("Mallory's key with Alice: M^a = {} ({})",
+          show(&with_alice), check(&with_alice, &alice_key));
+    text!("Mallory's key with Bob: M^b = {} ({})",
+          show(&with_bob), check(&with_bob, &bob_key));
+    let eavesdropped = group.exp(&big_b, a_found);
+    text!("Mallory also learns the secret Alice and Bob meant to share: g^ab = {} ({})",
+          show(&eavesdropped), check(&eavesdropped, &shared));
+    text!("Time: {:.2?}", start.elapsed());
+    output::result("recovered", json!({
+        "a": a_found.to_string(),
+        "b": b_found.to_string(),
+        "key_with_alice": show(&with_alice),
+        "key_with_bob": show(&with_bob),
+        "shared": show(&eavesdropped),
+        "keys_match": with_alice == alice_key && with_bob == bob_key,
+        "shared_matches": eavesdropped == shared,
+    }));
+    output::timing("attack", start.elapsed());
+    Ok(())
+}
+
+// Plays the small-subgroup confinement attack against a peer, Bob, with a
+// full-size static secret, using every prime power of the group order up
+// to the width of --range
+fn subgroup_attack<G>(args: &SolverArgs, instance: &Instance<G>, algorithm: &str,
+                      progress: Arc<Progress>) -> Result<(), Error>
+    where G: Group + Clone + 'static
+{
+    let (group, g) = (&instance.group, &instance.g);
+    let n = instance.group_order().ok_or(Error::NoOrder)?;
+    let factors = factors(args, instance)?;
+    let show = |a: &G::Element| to_hex(&group.serialize(a));
+    let solver = build_solver(args, instance, algorithm, progress)?;
+
+    text!("Small-Subgroup Confinement Attack");
+    let secret = params::random_below(&mut random::rng(), &n);
+    text!("Bob's static secret: b = {}", secret);
+    output::result("secret", secret.to_string());
+    let start = Instant::now();
+    let leaks = subgroup::confine(group, g, &n, &factors, instance.bound, &*solver,
+                                  |sent| group.exp(sent, &secret)).map_err(Error::Solve)?;
+
+    let mut congruences = Vec::with_capacity(leaks.len());
+    let mut sent = Vec::with_capacity(leaks.len());
+    for leak in &leaks {
+        text!("Sent {} of order {}^{}: b = {} mod {}",
+              show(&leak.sent), leak.q, leak.e, leak.residue, leak.modulus());
+        congruences.push((leak.residue.clone(), leak.modulus()));
+        sent.push(json!({
+            "element": show(&leak.sent),
+            "prime": leak.q.to_string(),
+            "exponent": leak.e,
+            "residue": leak.residue.to_string(),
+            "modulus": leak.modulus().to_string(),
+        }));
+    }
+    output::result("leaks", sent);
+    if leaks.len() < factors.len() {
+        text!("Skipped {} prime powers larger than {}", factors.len() - leaks.len(),
+              instance.bound);
+    }
+    output::result("skipped", factors.len() - leaks.len());
+
+    let (residue, modulus) = crt(&congruences).unwrap();
+    text!("CRT: b = {} mod {}", residue, modulus);
+    output::result("residue", residue.to_string());
+    output::result("modulus", modulus.to_string());
+    if modulus >= n {
+        let check = if residue == secret { "matches" } else { "does not match" };
+        text!("Recovered b = {} ({})", residue, check);
+        output::result("recovered", residue == secret);
+    } else {
+        text!("About {} bits of b remain unknown", n.bits() - modulus.bits() + 1);
+        output::result("recovered", false);
+        output::result("unknown_bits", n.bits() - modulus.bits() + 1);
+    }
+    text!("Time: {:.2?}", start.elapsed());
+    output::timing("attack", start.elapsed());
+    Ok(())
+}
+
+// Mints a fresh instance over a random safe prime of --bits bits, printed
+// as the options that solve it, with x on stderr
+fn genparams(args: &GenparamsArgs) -> Result<(), Error> {
+    let (bits, rounds) = (args.bits, args.rounds);
+    output::parameter("bits", bits);
+    output::parameter("rounds", rounds);
+    let (lo, hi) = args.range.unwrap_or((0, (1 << 40) - 1));
+
+    let start = Instant::now();
+    let mut rng = random::rng();
+    let p = params::safe_prime(bits, rounds, &mut rng);
+    let g = params::subgroup_generator(&p);
+    let x = rng.gen_range(lo..=hi);
+    let h = g.modpow(&BigUint::from(x), &p);
+    text!("--prime {} --base {} --target {} --range {}..{}", p, g, h, lo, hi);
+    eprintln!("x: {}", x);
+    eprintln!("Time: {:.2?}", start.elapsed());
+    output::result("prime", p.to_string());
+    output::result("base", g.to_string());
+    output::result("target", h.to_string());
+    output::result("range", format!("{}..{}", lo, hi));
+    output::result("x", x.to_string());
+    output::timing("generate", start.elapsed());
+    Ok(())
+}
+
+// Runs the command, returning x if it solved a single instance
+fn solve_instance<G>(task: &Task, instance: Instance<G>,
+                     algorithm: &str, targets: Option<Vec<(String, G::Element)>>,
+                     progress: Arc<Progress>) -> Result<Option<BigUint>, Error>
+    where G: Group + Clone + 'static
+{
+    let (lo, hi) = range(task.group(), instance.bound);
+    // The baby steps only depend on g and the width of the range, and the
+    // demos draw their secrets from it
+    let width = Instance { bound: hi - lo + 1, ..instance };
+    match (task, targets) {
+        (Task::Precompute(args), _) => precompute(&args.table, &width, &progress)?,
+        (Task::SubgroupAttack(args), _) => {
+            subgroup_attack(&args.solver, &width, algorithm, progress)?
+        },
+        (Task::DhDemo(args), _) => dh_demo(&args.solver, &width, lo, algorithm, progress)?,
+        (Task::Solve(args), Some(targets)) => {
+            if algorithm != "bsgs" {
+                return Err(Error::TargetsNeedBsgs);
+            }
+            batch(args, &width, lo, targets, &progress)?;
+        },
+        (Task::Solve(args), None) => return solve(args, &width, lo, algorithm, progress),
+    }
+    Ok(None)
+}
+
+// Solves for x in [lo, lo + width). With --expand, a miss moves on to the
+// following interval of twice the width, until x is found, the intervals
+// cover every exponent below the order of g, or they run out of u64s.
+fn solve<G>(args: &SolveArgs, instance: &Instance<G>, lo: u64, algorithm: &str,
+            progress: Arc<Progress>) -> Result<Option<BigUint>, Error>
+    where G: Group + Clone + 'static
+{
+    let expand = args.expand;
+    let order = instance.group_order();
+    let start = Instant::now();
+    let (first, mut lo, mut width) = (lo, lo, instance.bound);
+    // Left over from another algorithm
+    interrupt::resume(None);
+    loop {
+        let target = instance.shifted(lo, width);
+        let solver = build_solver(&args.solver, &target, algorithm, progress.clone())?;
+        if lo == first {
+            text!("{}", title(solver.name()));
+        }
+        if let Some(solution) = solver.solve(&target).map_err(Error::Solve)? {
+            let x = solution.x + BigUint::from(lo);
+            text!("x: {}", x);
+            text!("Time: {:.2?}", start.elapsed());
+            output::result("x", x.to_string());
+            output::result("searched", format!("{}..{}", first, lo + (width - 1)));
+            output::timing("solve", start.elapsed());
+            return Ok(Some(x));
+        }
+
+        let hi = lo + (width - 1);
+        let next = hi.checked_add(1);
+        let covered = next.map(|next| BigUint::from(next - first));
+        let exhausted = match (&covered, &order) {
+            (Some(covered), Some(order)) => covered >= order,
+            _ => false,
+        };
+        match next {
+            Some(next) if expand && !exhausted => {
+                width = width.saturating_mul(2).min(u64::MAX - next + 1);
+                text!("x is not in [{}, {}]; trying [{}, {}]", lo, hi, next, next + (width - 1));
+                lo = next;
+                interrupt::resume(Some(format!("pass --range {}..{} --expand (x is not in \
+                                                [{}, {}])", lo, lo + (width - 1), first, hi)));
+            },
+            _ => {
+                text!("x not found in [{}, {}]", first, hi);
+                if exhausted {
+                    text!("Every exponent below the order of g was tried, so h is not a power of g");
+                } else if next.is_none() {
+                    text!("The search reached 2^64 - 1; x may still exist mod the order of g");
+                } else {
+                    text!("x may lie outside the range: pass a wider --range, or --expand to \
+                           keep searching intervals of twice the width");
+                }
+                text!("Time: {:.2?}", start.elapsed());
+                output::result("x", Value::Null);
+                output::result("searched", format!("{}..{}", first, hi));
+                // Whether x can't exist at all, rather than lie outside the search
+                output::result("exhausted", exhausted);
+                output::timing("solve", start.elapsed());
+                return Ok(None);
+            },
+        }
+    }
+}
+
+fn threads(args: &SolverArgs) -> usize {
+    args.threads
+        .unwrap_or_else(|| thread::available_parallelism().map_or(1, |n| n.get()))
+}
+
+// The solver for --algorithm and its options
+fn build_solver<G>(args: &SolverArgs, instance: &Instance<G>, algorithm: &str,
+                   progress: Arc<Progress>) -> Result<Box<dyn DiscreteLogSolver<G>>, Error>
+    where G: Group + Clone + 'static
+{
+    Ok(match algorithm {
+        "bsgs" if args.table.is_some() => {
+            let path = args.table.as_deref().unwrap();
+            Box::new(Bsgs { baby_steps: Some(load_table(path, instance)?), progress })
+        },
+        _ if args.table.is_some() => return Err(Error::TableNeedsBsgs),
+        "kangaroo" => Box::new(Kangaroo {
+            jumps: args.jumps,
+            distinguished_bits: args.distinguished_bits,
+            progress,
+        }),
+        "pohlig-hellman" => Box::new(pohlig_hellman_solver(args, instance, &progress)?),
+        "mitm" => {
+            let disk = args.disk_table.as_ref().map(|dir| DiskOptions {
+                dir: dir.clone().unwrap_or_else(env::temp_dir),
+                run_records: args.run_size,
+            });
+            let table_entries = args.max_memory.map(|max_bytes| plan(instance, max_bytes))
+                .transpose()?;
+            let dump = args.dump_table.clone();
+            let threads = threads(args);
+            Box::new(Mitm { disk, table_entries, dump, threads, progress })
+        },
+        other => crate::solver_with_progress(other, progress)
+            .ok_or_else(|| Error::UnknownAlgorithm(other.to_string()))?,
+    })
+}
+
+// Group operations (expected ones for rho and kangaroo, the worst case
+// otherwise), table memory in bytes if there is a table, and the share of
+// the operations spread over the scan threads
+fn estimate<G>(args: &SolverArgs, instance: &Instance<G>,
+               algorithm: &str) -> Result<(f64, Option<u64>, f64), Error>
+    where G: Group + Clone
+{
+    let width = instance.bound as f64;
+    Ok(match algorithm {
+        "mitm" => {
+            let b1 = match args.max_memory {
+                Some(max) => mitm::plan_split(instance.bound, max).ok_or(Error::MaxMemory(max))?,
+                None => split(instance.bound),
+            };
+            let b2 = mitm::scan_length(instance.bound, b1) as f64;
+            // The disk table is sorted in memory one run at a time
+            let memory = if args.disk_table.is_some() {
+                args.run_size as u64 * 16
+            } else {
+                mitm::table_bytes(b1)
+            };
+            (b1 as f64 + b2, Some(memory), b2)
+        },
+        "bsgs" => {
+            let m = split(instance.bound);
+            (m as f64 + mitm::scan_length(instance.bound, m) as f64, Some(mitm::table_bytes(m)), 0.0)
+        },
+        "rho" => {
+            let n = instance.group_order().ok_or(Error::RhoNoOrder)?;
+            let n = n.to_string().parse::<f64>().unwrap_or(f64::INFINITY);
+            ((std::f64::consts::PI * n / 2.0).sqrt(), None, 0.0)
+        },
+        "kangaroo" => (2.0 * width.sqrt(), None, 0.0),
+        "pohlig-hellman" => {
+            // A search of about 2 sqrt(q) steps per digit, the largest
+            // subgroup having the largest table
+            let factors = factors(args, instance)?;
+            let ops = factors.iter().map(|&(q, e)| f64::from(e) * 2.0 * (q as f64).sqrt()).sum();
+            let largest = factors.iter().map(|&(q, _)| q).max().unwrap_or(1);
+            (ops, Some(mitm::table_bytes(split(largest))), 0.0)
+        },
+        other => return Err(Error::UnknownAlgorithm(other.to_string())),
+    })
+}
+
+// MemAvailable from /proc/meminfo, in bytes (Linux only)
+fn available_memory() -> Option<u64> {
+    let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+    let line = meminfo.lines().find(|line| line.starts_with("MemAvailable:"))?;
+    let kb: u64 = line.trim_start_matches("MemAvailable:").trim().trim_end_matches("kB").trim()
+        .parse().ok()?;
+    Some(kb * 1024)
+}
+
+fn format_bytes(bytes: u64) -> String {
+    let mut size = bytes as f64;
+    let mut units = ["B", "KiB", "MiB", "GiB", "TiB"].iter().peekable();
+    let mut unit = units.next().unwrap();
+    while size >= 1024.0 && units.peek().is_some() {
+        size /= 1024.0;
+        unit = units.next().unwrap();
+    }
+    format!("{:.1} {}", size, unit)
+}
+
+// --plan: the cost of solving with each algorithm, from the sizes of the
+// group and the range and the time of a group operation on this machine
+fn print_plan<G>(args: &SolverArgs, instance: &Instance<G>, algorithms: &[String])
+                 -> Result<(), Error>
+    where G: Group + Clone
+{
+    let per_op = time_per_op(instance);
+    let threads = threads(args) as f64;
+    let available = available_memory();
+    let element_bytes = instance.group.serialize(&instance.g).len();
+    text!("{}-byte group elements, {} values in the range, {:.2?} per group operation",
+          element_bytes, instance.bound, Duration::from_secs_f64(per_op));
+    text!("{:<16}{:>14}{:>14}{:>14}", "Algorithm", "Group ops", "Memory", "Time");
+    output::result("element_bytes", element_bytes);
+    output::result("group_operation_time", per_op);
+    output::result("available_memory", available);
+
+    let mut estimates = Vec::new();
+    let mut warnings = Vec::new();
+    for algorithm in algorithms {
+        let (ops, memory, parallel) = estimate(args, instance, algorithm)?;
+        let seconds = per_op * (ops - parallel + parallel / threads);
+        let feasible = seconds.is_finite() && seconds < 1e15;
+        let time = if feasible {
+            format!("{:.2?}", Duration::from_secs_f64(seconds))
+        } else {
+            "forever".to_string()
+        };
+        text!("{:<16}{:>14.3e}{:>14}{:>14}", algorithm, ops,
+              memory.map_or_else(|| "-".to_string(), format_bytes), time);
+        // Forever is null
+        estimates.push(json!({
+            "algorithm": algorithm,
+            "group_operations": ops,
+            "memory": memory,
+            "time": Some(seconds).filter(|_| feasible),
+        }));
+        if let (Some(memory), Some(available)) = (memory, available) {
+            if memory > available {
+                warnings.push(format!("{} needs {} but only {} is available", algorithm,
+                                      format_bytes(memory), format_bytes(available)));
+            }
+        }
+    }
+    for warning in &warnings {
+        text!("Warning: {}", warning);
+    }
+    if !warnings.is_empty() {
+        text!("mitm can fit a smaller table with --max-memory, or keep it on disk with \
+               --disk-table");
+    }
+    output::result("estimates", estimates);
+    output::result("warnings", warnings);
+    Ok(())
+}
+
+// One run of a solver, for --stats and comparisons
+struct Run {
+    algorithm: String,
+    x: Option<BigUint>,
+    // Cut short by Ctrl-C, so x may still exist
+    interrupted: bool,
+    elapsed: Duration,
+    phases: Vec<PhaseStats>,
+}
+
+impl Run {
+    // Totals over all phases, with the largest peak memory
+    fn total(&self) -> PhaseStats {
+        self.phases.iter().fold(PhaseStats::default(), |total, phase| PhaseStats {
+            ops: total.ops + phase.ops,
+            exps: total.exps + phase.exps,
+            inserts: total.inserts + phase.inserts,
+            lookups: total.lookups + phase.lookups,
+            peak_memory: total.peak_memory.max(phase.peak_memory),
+            ..total
+        })
+    }
+}
+
+// An array with one object per run, each with its phases in order, for
+// --stats and the --json report. x is a string since it may not fit in a
+// double; times are in seconds and memory in bytes. The phase a run was
+// interrupted in ends at the interruption.
+fn stats_json(runs: &[Run]) -> Value {
+    runs.iter().map(|run| json!({
+        "algorithm": run.algorithm,
+        "x": run.x.as_ref().map(ToString::to_string),
+        "interrupted": run.interrupted,
+        "wall_time": run.elapsed.as_secs_f64(),
+        "phases": run.phases.iter().map(|phase| json!({
+            "phase": phase.label,
+            "wall_time": phase.elapsed.as_secs_f64(),
+            "group_operations": phase.ops,
+            "exponentiations": phase.exps,
+            "table_inserts": phase.inserts,
+            "lookups": phase.lookups,
+            "peak_memory": phase.peak_memory,
+        })).collect::<Vec<_>>(),
+    })).collect()
+}
+
+fn print_comparison(runs: &[Run]) {
+    text!();
+    text!("{:<16}{:>16}{:>12}{:>14}{:>8}{:>12}{:>12}{:>12}",
+          "Algorithm", "x", "Time", "Group ops", "Exps", "Inserts", "Lookups", "Peak MiB");
+    for run in runs {
+        let total = run.total();
+        let x = match run.x {
+            Some(ref x) => x.to_string(),
+            None if run.interrupted => "interrupted".to_string(),
+            None => "-".to_string(),
+        };
+        let peak = total.peak_memory.map_or_else(|| "-".to_string(), |bytes| {
+            format!("{:.1}", bytes as f64 / f64::from(1 << 20))
+        });
+        text!("{:<16}{:>16}{:>12}{:>14}{:>8}{:>12}{:>12}{:>12}",
+              run.algorithm, x, format!("{:.2?}", run.elapsed), total.ops, total.exps,
+              total.inserts, total.lookups, peak);
+    }
+}
+
+// The runs of a dispatch so far and the one in progress, for the Ctrl-C
+// handler to report
+#[derive(Default)]
+struct Runs {
+    done: Vec<Run>,
+    current: Option<(String, Arc<Progress>, Instant)>,
+}
+
+fn print_counts(progress: &Progress, elapsed: Duration) {
+    let counters = progress.counters();
+    eprintln!("{} group operations and {} exponentiations in {:.2?}",
+              counters.ops.load(Ordering::Relaxed), counters.exps.load(Ordering::Relaxed),
+              elapsed);
+}
+
+// The comparison table for several runs, and the --stats output, which
+// goes in the report instead of on stdout with --json
+fn report_runs(args: &ReportArgs, runs: &[Run], compare: bool) -> Result<(), Error> {
+    if compare {
+        print_comparison(runs);
+    }
+    let stats = stats_json(runs);
+    output::result("runs", stats.clone());
+    match &args.stats {
+        Some(Some(path)) => std::fs::write(path, format!("{:#}\n", stats))
+            .map_err(|source| Error::Write { path: path.clone(), source })?,
+        Some(None) if !output::is_json() => println!("{:#}", stats),
+        _ => {},
+    }
+    Ok(())
+}
+
+// With --progress or --stats, or when comparing algorithms, counts the
+// operations of each run for a summary at the end. Ctrl-C ends the runs
+// early with the summary so far, the run it interrupted included.
+fn dispatch<G>(task: &Task, instance: Instance<G>,
+               algorithms: &[String], mut targets: Option<Vec<(String, G::Element)>>)
+               -> Result<(), Error>
+    where G: Group + Clone + 'static
+{
+    let args = task.report();
+    let report = args.progress;
+    let stats = args.stats.is_some() || algorithms.len() > 1;
+    // A miss is a failure, but only after every algorithm had its turn
+    let solving = matches!(task, Task::Solve(_)) && targets.is_none();
+    let compare = algorithms.len() > 1;
+    if compare && targets.is_some() {
+        return Err(Error::Compare);
+    }
+
+    if let Task::Solve(solve) = task {
+        if solve.plan {
+            output::parameter("plan", true);
+            let (lo, hi) = range(&solve.group, instance.bound);
+            let width = Instance { bound: hi - lo + 1, ..instance };
+            return print_plan(&solve.solver, &width, algorithms);
+        }
+    }
+
+    let runs = Arc::new(Mutex::new(Runs::default()));
+    {
+        let (args, runs) = (args.clone(), runs.clone());
+        interrupt::init()?;
+        interrupt::on_interrupt(move || {
+            let mut runs = runs.lock().unwrap();
+            if let Some((algorithm, progress, start)) = runs.current.take() {
+                let elapsed = start.elapsed();
+                if report {
+                    print_counts(&progress, elapsed);
+                }
+                let phases = progress.finish();
+                runs.done.push(Run { algorithm, x: None, interrupted: true, elapsed, phases });
+            }
+            if let Err(e) = report_runs(&args, &runs.done, compare) {
+                error!("{}", e);
+            }
+        });
+    }
+    if !report && !stats {
+        // Only for the debug log of the phases
+        let progress = Arc::new(Progress::default());
+        let x = solve_instance(task, instance, &algorithms[0], targets, progress.clone())?;
+        progress.finish();
+        if solving && x.is_none() {
+            return Err(Error::NotFound);
+        }
+        return Ok(());
+    }
+
+    for algorithm in algorithms {
+        let progress = Arc::new(Progress::new(report).with_stats(stats));
+        let group = Counted::new(instance.group.clone(), progress.counters());
+        let counted = Instance {
+            group,
+            g: instance.g.clone(),
+            h: instance.h.clone(),
+            bound: instance.bound,
+            order: instance.order.clone(),
+        };
+        let start = Instant::now();
+        runs.lock().unwrap().current = Some((algorithm.clone(), progress.clone(), start));
+        let x = solve_instance(task, counted, algorithm, targets.take(),
+                               progress.clone())?;
+        let elapsed = start.elapsed();
+
+        let mut runs = runs.lock().unwrap();
+        runs.current = None;
+        if report {
+            print_counts(&progress, elapsed);
+        }
+        let phases = progress.finish();
+        runs.done.push(Run { algorithm: algorithm.clone(), x, interrupted: false, elapsed, phases });
+    }
+    let runs = runs.lock().unwrap();
+    report_runs(args, &runs.done, compare)?;
+    if solving && runs.done.iter().any(|run| run.x.is_none()) {
+        return Err(Error::NotFound);
+    }
+    Ok(())
+}
+
+// The calc REPL: one command per line from stdin, with a prompt when stdin
+// is a terminal. With --json, the outputs go in the report.
+fn repl() {
+    let stdin = io::stdin();
+    let interactive = stdin.is_terminal() && !output::is_json();
+    let mut session = calc::Calc::new();
+    let mut rng = random::rng();
+    let mut outputs = Vec::new();
+    if interactive {
+        println!("Modular arithmetic on big integers; type help for the commands");
+    }
+    loop {
+        if interactive {
+            print!("> ");
+            let _ = io::stdout().flush();
+        }
+        let mut line = String::new();
+        match stdin.lock().read_line(&mut line) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {},
+        }
+        if matches!(line.trim(), "quit" | "exit") {
+            break;
+        }
+        match session.eval(&line, &mut rng) {
+            Ok(Some(output)) => {
+                text!("{}", output);
+                outputs.push(output);
+            },
+            Ok(None) => {},
+            Err(e) => eprintln!("Error: {}", e),
+        }
+    }
+    output::result("outputs", outputs);
+}
+
+// The group of an instance and the interval of x, for the commands on one
+#[derive(clap::Args, Clone)]
+struct GroupArgs {
+    #[arg(short, long, value_name = "P", value_parser = parse_big_arg,
+          help = "prime modulus (default: assignment instance)")]
+    prime: Option<BigUint>,
+    #[arg(short, long, value_name = "NAME",
+          help = "work on an elliptic curve instead of Zp*: toy, or secp256k1 if built with \
+                  that feature")]
+    curve: Option<String>,
+    #[arg(short = 'g', long, value_name = "G",
+          help = "base of the discrete log (a point X,Y on curves, default: the curve generator)")]
+    base: Option<String>,
+    #[arg(short, long, value_name = "A..B", value_parser = parse_range_arg,
+          help = "interval [A, B] known to contain x (default 0..2^40-1)")]
+    range: Option<(u64, u64)>,
+    #[arg(long, value_name = "N", value_parser = parse_big_arg,
+          help = "order of the group generated by g, or a multiple of it (rho and \
+                  pohlig-hellman, default p-1)")]
+    order: Option<BigUint>,
+    #[arg(long, help = "experimental: build tables on the GPU, falling back to the CPU if there \
+                        is no adapter (Zp* only, needs the gpu feature)")]
+    gpu: bool,
+}
+
+// The options of the algorithms, for the commands that run one
+#[derive(clap::Args, Clone)]
+struct SolverArgs {
+    #[arg(long, value_name = "FILE", help = "file of baby steps written by precompute (bsgs only)")]
+    table: Option<PathBuf>,
+    #[arg(long, value_name = "DIR", num_args = 0..=1, require_equals = true,
+          help = "build the table as a sorted file in DIR (default: the system temp dir) \
+                  instead of in memory (mitm only)")]
+    disk_table: Option<Option<PathBuf>>,
+    #[arg(long, value_name = "SIZE", value_parser = parse_size_arg, conflicts_with = "disk_table",
+          help = "memory budget for the table, e.g. 512M or 2G: shrinks the table and \
+                  lengthens the scan to fit (mitm only)")]
+    max_memory: Option<u64>,
+    #[arg(long, value_name = "FILE", conflicts_with = "disk_table",
+          help = "write every MITM table entry to FILE as CSV, or NDJSON for a .ndjson or \
+                  .jsonl FILE")]
+    dump_table: Option<PathBuf>,
+    #[arg(long, value_name = "N", value_parser = RangedU64ValueParser::<usize>::new().range(1..),
+          help = "threads sharing the MITM scan (default: one per CPU)")]
+    threads: Option<usize>,
+    #[arg(long, value_name = "N", default_value_t = 1 << 22,
+          value_parser = RangedU64ValueParser::<usize>::new().range(1..),
+          help = "records sorted in memory per run when building the table on disk")]
+    run_size: usize,
+    #[arg(short, long, value_name = "N",
+          help = "points whose hash has N zero bits are distinguished (kangaroo only)")]
+    distinguished_bits: Option<u32>,
+    #[arg(short, long, value_name = "N", default_value_t = 32,
+          value_parser = RangedU64ValueParser::<usize>::new().range(1..),
+          help = "number of entries in the jump table (kangaroo only)")]
+    jumps: usize,
+    #[arg(long, value_name = "LIST",
+          help = "factorization of the order, e.g. 2,3^2,5 (pohlig-hellman only, default: trial \
+                  division)")]
+    factors: Option<String>,
+    #[arg(long, value_name = "NAME", default_value = "mitm",
+          value_parser = ["mitm", "bsgs", "rho", "kangaroo"],
+          help = "backend for the prime order subgroups (pohlig-hellman only)")]
+    subgroup_algorithm: String,
+}
+
+// What to report of the runs of a command
+#[derive(clap::Args, Clone)]
+struct ReportArgs {
+    #[arg(long, value_name = "FILE", num_args = 0..=1, require_equals = true,
+          help = "write the group operations, table inserts, lookups, peak memory and wall time \
+                  of every phase as JSON to FILE (default: stdout)")]
+    stats: Option<Option<PathBuf>>,
+    #[arg(long, help = "report progress and an ETA on stderr, and count the group operations \
+                        performed")]
+    progress: bool,
+}
+
+#[derive(clap::Args, Clone)]
+struct SolveArgs {
+    #[command(flatten)]
+    group: GroupArgs,
+    #[arg(short, long, value_name = "H",
+          help = "element h = g^x to solve for (a point X,Y on curves)")]
+    target: Option<String>,
+    #[arg(short, long = "algorithm", value_name = "NAME", value_delimiter = ',',
+          help = "dlog algorithm: mitm (default, or bsgs with --table or --targets), bsgs, rho, \
+                  kangaroo or pohlig-hellman; several, repeated or separated by commas, are \
+                  compared on the same instance")]
+    algorithm: Vec<String>,
+    #[arg(long, help = "if x is not in the range, keep searching the following intervals, \
+                        doubling their width each time")]
+    expand: bool,
+    #[arg(long, value_name = "FILE",
+          help = "solve every target in FILE, one per line, reusing one table of baby steps \
+                  (bsgs only)")]
+    targets: Option<PathBuf>,
+    #[command(flatten)]
+    solver: SolverArgs,
+    #[arg(long, value_name = "ADDR",
+          help = "hand out the MITM search to workers connecting to ADDR, e.g. 0.0.0.0:7878 \
+                  (Zp* only)")]
+    coordinator: Option<String>,
+    #[arg(long, value_name = "N", default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..),
+          requires = "coordinator", help = "split the table into N parts, one per job (coordinator only)")]
+    table_shards: u64,
+    #[arg(long, value_name = "N", default_value_t = 16, value_parser = clap::value_parser!(u64).range(1..),
+          requires = "coordinator", help = "split the scan into N parts, one per job (coordinator only)")]
+    scan_shards: u64,
+    #[arg(long, conflicts_with_all = ["targets", "coordinator"],
+          help = "estimate the memory, group operations and time of each --algorithm on \
+                  this machine, without solving; with --bits N, for an N-bit p")]
+    plan: bool,
+    #[arg(long, value_name = "N", value_parser = RangedU64ValueParser::<usize>::new().range(3..),
+          requires = "plan", help = "size of p in bits, for --plan")]
+    bits: Option<usize>,
+    #[command(flatten)]
+    report: ReportArgs,
+}
+
+#[derive(clap::Args, Clone)]
+struct PrecomputeArgs {
+    #[command(flatten)]
+    group: GroupArgs,
+    #[arg(long, value_name = "FILE", help = "file to write the baby steps to, for solve --table")]
+    table: PathBuf,
+    #[command(flatten)]
+    report: ReportArgs,
+}
+
+// dh-demo and subgroup-attack, which solve for secrets of their own
+#[derive(clap::Args, Clone)]
+struct AttackArgs {
+    #[command(flatten)]
+    group: GroupArgs,
+    #[arg(short, long, value_name = "NAME",
+          help = "dlog algorithm: mitm (default, or bsgs with --table), bsgs, rho, kangaroo or \
+                  pohlig-hellman")]
+    algorithm: Option<String>,
+    #[command(flatten)]
+    solver: SolverArgs,
+    #[command(flatten)]
+    report: ReportArgs,
+}
+
+#[derive(clap::Args, Clone)]
+struct GenparamsArgs {
+    #[arg(long, value_name = "N", value_parser = RangedU64ValueParser::<usize>::new().range(3..),
+          help = "size of p in bits")]
+    bits: usize,
+    #[arg(long, value_name = "N", default_value_t = 32,
+          help = "Miller-Rabin rounds for p and (p-1)/2")]
+    rounds: u32,
+    #[arg(short, long, value_name = "A..B", value_parser = parse_range_arg,
+          help = "interval [A, B] to draw x from (default 0..2^40-1)")]
+    range: Option<(u64, u64)>,
+}
+
+#[derive(Subcommand, Clone)]
+enum Command {
+    #[command(flatten)]
+    Task(Box<Task>),
+    #[command(about = "Generates an instance over a random safe prime of --bits bits, printed \
+                       as the options that solve it")]
+    Genparams(GenparamsArgs),
+    #[command(about = "Starts a calculator for modular arithmetic on big integers")]
+    Calc,
+}
+
+// The commands on an instance of the discrete log problem
+#[derive(Subcommand, Clone)]
+enum Task {
+    #[command(about = "Solves h = g^x for x in a known range, as without a command")]
+    Solve(SolveArgs),
+    #[command(about = "Writes the baby steps of a range to a file, for solve --table")]
+    Precompute(PrecomputeArgs),
+    #[command(about = "Demos a Diffie-Hellman exchange and a man in the middle who solves for \
+                       its secrets")]
+    DhDemo(AttackArgs),
+    #[command(about = "Recovers a static secret by sending elements of small order")]
+    SubgroupAttack(AttackArgs),
+}
+
+impl Task {
+    fn name(&self) -> &'static str {
+        match self {
+            Task::Solve(_) => "solve",
+            Task::Precompute(_) => "precompute",
+            Task::DhDemo(_) => "dh-demo",
+            Task::SubgroupAttack(_) => "subgroup-attack",
+        }
+    }
+
+    fn group(&self) -> &GroupArgs {
+        match self {
+            Task::Solve(args) => &args.group,
+            Task::Precompute(args) => &args.group,
+            Task::DhDemo(args) | Task::SubgroupAttack(args) => &args.group,
+        }
+    }
+
+    fn report(&self) -> &ReportArgs {
+        match self {
+            Task::Solve(args) => &args.report,
+            Task::Precompute(args) => &args.report,
+            Task::DhDemo(args) | Task::SubgroupAttack(args) => &args.report,
+        }
+    }
+
+    // The algorithms to run, by default mitm, or bsgs where there is a
+    // table, since precomputed and shared tables are only usable by
+    // baby-step giant-step
+    fn algorithms(&self) -> Vec<String> {
+        let (given, table) = match self {
+            Task::Solve(args) => {
+                (args.algorithm.clone(), args.solver.table.is_some() || args.targets.is_some())
+            },
+            Task::Precompute(_) => (Vec::new(), true),
+            Task::DhDemo(args) | Task::SubgroupAttack(args) => {
+                (args.algorithm.iter().cloned().collect(), args.solver.table.is_some())
+            },
+        };
+        let mut algorithms: Vec<String> = given.iter()
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+            .collect();
+        if algorithms.is_empty() {
+            algorithms.push(if table { "bsgs" } else { "mitm" }.to_string());
+        }
+        algorithms
+    }
+}
+
+#[derive(Parser, Clone)]
+#[command(name = "w5-mitm_dlog",
+          about = "Solves discrete logarithms in Zp* and on elliptic curves",
+          long_about = "Solves h = g^x for x in a known range, by default the assignment instance \
+                        with x < 2^40, as the solve command does with the same options. The \
+                        other commands precompute a table of baby steps, demo a Diffie-Hellman \
+                        exchange, attack a weak subgroup, generate parameters or start a \
+                        calculator for group arithmetic. Their options go after them.",
+          args_conflicts_with_subcommands = true)]
+pub struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+    // Without a command, the options of solve
+    #[command(flatten)]
+    solve: SolveArgs,
+    #[arg(long, value_name = "ADDR", help = "run MITM jobs for the coordinator at ADDR until it \
+                                             is done")]
+    worker: Option<String>,
+    #[command(flatten)]
+    log: LogArgs,
+    #[command(flatten)]
+    output: OutputArgs,
+    #[command(flatten)]
+    random: RandomArgs,
+}
+
+pub fn run(args: Args) -> Result<(), Error> {
+    log::init(&args.log);
+    let command = match (&args.worker, &args.command) {
+        (Some(_), _) => "worker",
+        (None, Some(Command::Task(task))) => task.name(),
+        (None, Some(Command::Genparams(_))) => "genparams",
+        (None, Some(Command::Calc)) => "calc",
+        (None, None) => "solve",
+    };
+    output::init(&format!("dlog {}", command), &args.output);
+    random::init(&args.random);
+    output::finish(execute(args))
+}
+
+fn execute(args: Args) -> Result<(), Error> {
+    if let Some(addr) = &args.worker {
+        output::parameter("worker", addr.as_str());
+        let progress = Progress::new(args.solve.report.progress);
+        return distributed::work(addr, &progress)
+            .map_err(|source| Error::Worker { addr: addr.clone(), source });
+    }
+
+    let solve = args.solve;
+    match args.command.unwrap_or_else(|| Command::Task(Box::new(Task::Solve(solve)))) {
+        Command::Task(task) => run_task(&task),
+        Command::Genparams(args) => genparams(&args),
+        Command::Calc => {
+            repl();
+            Ok(())
+        },
+    }
+}
+
+fn run_task(task: &Task) -> Result<(), Error> {
+    let args = task.group();
+    let algorithms = task.algorithms();
+    output::parameter("algorithms", algorithms.clone());
+    // Only solve has a target, and --bits for --plan
+    let (target, bits, coordinator, targets) = match task {
+        Task::Solve(solve) => {
+            (&solve.target, solve.bits, &solve.coordinator, solve.targets.as_ref())
+        },
+        _ => (&None, None, &None, None),
+    };
+
+    let h = BigUint::parse_bytes(b"3239475104050450443565264378728065788649\
+                                   0975209524495278347924529719819761432925\
+                                   5807385693795855318053287892800149470609\
+                                   7394108577585732452307673444020333", 10).unwrap();
+    let g = BigUint::parse_bytes(b"1171782988036620700951611759633536708855\
+                                   8084999998952205599979459063929499736583\
+                                   7466705721764714603129285948296754282794\
+                                   66566527115212748467589894601965568", 10).unwrap();
+    let p = BigUint::parse_bytes(b"1340780792994259709957402499820584612747\
+                                   9365820592393377723561443721764030073546\
+                                   9768018742981669034276900318581864860508\
+                                   53753882811946569946433649006084171", 10).unwrap();
+    // dh-demo defaults to a 64-bit safe prime, and subgroup-attack to a
+    // 128-bit prime p with p - 1 a product of primes below 2^16. Both are
+    // generated by 2.
+    let (p, g) = match task {
+        Task::DhDemo(_) => {
+            (BigUint::parse_bytes(b"18446744073709550147", 10).unwrap(), BigUint::from(2u32))
+        },
+        Task::SubgroupAttack(_) => {
+            (BigUint::parse_bytes(b"321357171713570770250973175486410895067", 10).unwrap(),
+             BigUint::from(2u32))
+        },
+        _ => (p, g),
+    };
+    let b = 2u64.pow(20);
+
+    let bound = b * b;
+    let order = args.order.clone();
+    let parse_big_opt = |name, val: &Option<String>| val.as_ref().map(|val| {
+        parse_big(val).ok_or_else(|| Error::Value { name, value: val.clone() })
+    }).transpose();
+
+    match &args.curve {
+        None => {
+            let p = args.prime.clone().unwrap_or(p);
+            let g = parse_big_opt("base", &args.base)?.unwrap_or(g);
+            let h = parse_big_opt("target", target)?.unwrap_or(h);
+            // --plan --bits N times the arithmetic mod any N-bit number
+            let (p, g, h) = match bits {
+                Some(bits) => {
+                    let top = BigUint::from(1u32) << (bits - 1);
+                    let p = params::random_below(&mut random::rng(), &top) + &top;
+                    (p | BigUint::from(1u32), BigUint::from(2u32), BigUint::from(3u32))
+                },
+                None => (p, g, h),
+            };
+            // h is only the target of a plain solve
+            let solving = matches!(task, Task::Solve(_)) && targets.is_none();
+            let (lo, hi) = range(args, bound);
+            output::parameter("prime", p.to_string());
+            output::parameter("base", g.to_string());
+            if solving {
+                output::parameter("target", h.to_string());
+            }
+            output::parameter("range", format!("{}..{}", lo, hi));
+            if bits.is_none() {
+                validate::check_zp(&p, &g, Some(&h).filter(|_| solving), order.as_ref(),
+                                   hi - lo + 1, &mut random::rng())?;
+            }
+
+            let mut zp = Zp::new(p.clone());
+            if args.gpu {
+                zp = with_gpu(zp)?;
+            }
+            let (g, h) = (zp.element(&g), zp.element(&h));
+            let targets = targets.map(|path| {
+                read_targets(path, |line| {
+                    parse_big(line)
+                        .filter(|n| *n >= BigUint::from(2u32) && *n < p)
+                        .map(|n| zp.element(&n))
+                })
+            }).transpose()?;
+            let mut instance = Instance::new(zp, g, h, bound);
+            instance.order = order;
+            if let (Task::Solve(solve), Some(addr)) = (task, coordinator) {
+                return coordinate(solve, instance, addr);
+            }
+            dispatch(task, instance, &algorithms, targets)
+        },
+        Some(name) => {
+            let curve = curve(name).ok_or_else(|| Error::UnknownCurve(name.clone()))?;
+            let parse_point_opt = |name, val: &Option<String>| val.as_ref().map(|val| {
+                parse_point(val)
+                    .filter(|point| curve.contains(point))
+                    .ok_or_else(|| Error::Point { name, value: val.clone() })
+            }).transpose();
+
+            if coordinator.is_some() {
+                return Err(Error::DistributedCurve);
+            }
+            if args.gpu {
+                return Err(Error::GpuCurve);
+            }
+            let g = parse_point_opt("base", &args.base)?
+                .unwrap_or_else(|| curve.generator.clone());
+            // The baby steps don't depend on the target
+            let targets = targets.map(|path| {
+                read_targets(path, |line| parse_point(line).filter(|point| curve.contains(point)))
+            }).transpose()?;
+            let h = match parse_point_opt("target", target)? {
+                Some(h) => h,
+                None if !matches!(task, Task::Solve(_)) || targets.is_some() => curve.identity(),
+                None => return Err(Error::NoCurveTarget),
+            };
+            let (lo, hi) = range(args, bound);
+            output::parameter("curve", name.as_str());
+            output::parameter("base", g.to_string());
+            if matches!(task, Task::Solve(_)) && targets.is_none() {
+                output::parameter("target", h.to_string());
+            }
+            output::parameter("range", format!("{}..{}", lo, hi));
+            let mut instance = Instance::new(curve, g, h, bound);
+            instance.order = order;
+            dispatch(task, instance, &algorithms, targets)
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(argv: &[&str]) -> Result<Args, clap::Error> {
+        Args::try_parse_from(argv)
+    }
+
+    // Without a command, the options are those of solve
+    #[test]
+    fn solves_without_a_command() {
+        let argv = ["w5-mitm_dlog", "--range", "0..99", "-a", "mitm,rho", "--expand"];
+        let args = parse(&argv).unwrap();
+        assert!(args.command.is_none());
+        assert_eq!(args.solve.group.range, Some((0, 99)));
+        assert!(args.solve.expand);
+        assert_eq!(Task::Solve(args.solve).algorithms(), ["mitm", "rho"]);
+
+        let args = parse(&["w5-mitm_dlog", "solve", "--targets", "t.txt"]).unwrap();
+        match args.command {
+            Some(Command::Task(task)) => assert_eq!(task.algorithms(), ["bsgs"]),
+            _ => panic!("not solving"),
+        }
+    }
+
+    #[test]
+    fn scopes_options_to_their_command() {
+        for argv in [
+            &["w5-mitm_dlog", "dh-demo", "--expand"][..],
+            &["w5-mitm_dlog", "subgroup-attack", "--targets", "t.txt"],
+            &["w5-mitm_dlog", "precompute", "--table", "t.tbl", "--algorithm", "mitm"],
+            &["w5-mitm_dlog", "genparams", "--bits", "64", "--prime", "23"],
+            &["w5-mitm_dlog", "calc", "--range", "0..9"],
+            &["w5-mitm_dlog", "--range", "0..9", "dh-demo"],
+        ] {
+            assert!(parse(argv).is_err(), "accepted {:?}", argv);
+        }
+        let argv = ["w5-mitm_dlog", "dh-demo", "--range", "0..9", "--json", "-a", "rho"];
+        let args = parse(&argv).unwrap();
+        match args.command {
+            Some(Command::Task(task)) if matches!(*task, Task::DhDemo(_)) => {
+                assert_eq!(task.group().range, Some((0, 9)));
+                assert_eq!(task.algorithms(), ["rho"]);
+            },
+            _ => panic!("not dh-demo"),
+        }
+    }
+
+    #[test]
+    fn requires_the_options_of_a_command() {
+        for argv in [
+            &["w5-mitm_dlog", "precompute"][..],
+            &["w5-mitm_dlog", "genparams"],
+            &["w5-mitm_dlog", "--bits", "64"],
+            &["w5-mitm_dlog", "--scan-shards", "4"],
+            &["w5-mitm_dlog", "--plan", "--targets", "t.txt"],
+        ] {
+            assert!(parse(argv).is_err(), "accepted {:?}", argv);
+        }
+        assert!(parse(&["w5-mitm_dlog", "--plan", "--bits", "64"]).is_ok());
+    }
+}
@@ -0,0 +1,179 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fs::File;
+use std::io;
+use std::io::prelude::*;
+use std::io::{BufReader, BufWriter, SeekFrom};
+
+use memmap2::Mmap;
+
+use crate::group::Group;
+use crate::mitm::{for_each_left, DiskOptions, TableLookup};
+use crate::progress::Progress;
+
+// Each record is a (key, exponent) pair of little-endian u64s
+const RECORD_SIZE: usize = 16;
+
+fn write_record<W: Write>(w: &mut W, (key, x): (u64, u64)) -> io::Result<()> {
+    w.write_all(&key.to_le_bytes())?;
+    w.write_all(&x.to_le_bytes())
+}
+
+fn read_record<R: Read>(r: &mut R) -> io::Result<Option<(u64, u64)>> {
+    let mut buf = [0u8; RECORD_SIZE];
+    match r.read_exact(&mut buf) {
+        Ok(()) => {},
+        Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    let mut key = [0u8; 8];
+    let mut x = [0u8; 8];
+    key.copy_from_slice(&buf[..8]);
+    x.copy_from_slice(&buf[8..]);
+    Ok(Some((u64::from_le_bytes(key), u64::from_le_bytes(x))))
+}
+
+// External sort: records are sorted in memory in runs of a bounded size,
+// each run is written to its own temporary file, and the runs are then
+// merged into a single sorted file
+struct ExternalSorter<'a> {
+    options: &'a DiskOptions,
+    buf: Vec<(u64, u64)>,
+    runs: Vec<File>,
+    // First I/O error, reported by finish(), so that push() can be used
+    // from an infallible callback
+    error: Option<io::Error>,
+}
+
+impl<'a> ExternalSorter<'a> {
+    fn new(options: &'a DiskOptions) -> Self {
+        let run_records = options.run_records.max(1);
+        ExternalSorter { options, buf: Vec::with_capacity(run_records), runs: Vec::new(), error: None }
+    }
+
+    fn push(&mut self, record: (u64, u64)) {
+        if self.error.is_some() {
+            return;
+        }
+        self.buf.push(record);
+        if self.buf.len() >= self.options.run_records.max(1) {
+            if let Err(e) = self.flush_run() {
+                self.error = Some(e);
+            }
+        }
+    }
+
+    fn flush_run(&mut self) -> io::Result<()> {
+        if self.buf.is_empty() {
+            return Ok(());
+        }
+        self.buf.sort_unstable();
+
+        let mut file = tempfile::tempfile_in(&self.options.dir)?;
+        {
+            let mut w = BufWriter::new(&mut file);
+            for &record in &self.buf {
+                write_record(&mut w, record)?;
+            }
+            w.flush()?;
+        }
+        file.seek(SeekFrom::Start(0))?;
+
+        self.runs.push(file);
+        self.buf.clear();
+        Ok(())
+    }
+
+    fn finish(mut self) -> io::Result<DiskTable> {
+        if let Some(e) = self.error.take() {
+            return Err(e);
+        }
+        self.flush_run()?;
+
+        let file = if self.runs.len() == 1 {
+            self.runs.pop().unwrap()
+        } else {
+            self.merge()?
+        };
+        let len = file.metadata()?.len() as usize / RECORD_SIZE;
+        // The file is private to this process and never written again
+        let mmap = unsafe { Mmap::map(&file)? };
+
+        Ok(DiskTable { mmap, len })
+    }
+
+    // k-way merge of the sorted runs
+    fn merge(&mut self) -> io::Result<File> {
+        let mut readers: Vec<_> = self.runs.drain(..).map(BufReader::new).collect();
+        let mut heap = BinaryHeap::with_capacity(readers.len());
+        for (i, r) in readers.iter_mut().enumerate() {
+            if let Some(record) = read_record(r)? {
+                heap.push(Reverse((record, i)));
+            }
+        }
+
+        let mut file = tempfile::tempfile_in(&self.options.dir)?;
+        {
+            let mut w = BufWriter::new(&mut file);
+            while let Some(Reverse((record, i))) = heap.pop() {
+                write_record(&mut w, record)?;
+                if let Some(next) = read_record(&mut readers[i])? {
+                    heap.push(Reverse((next, i)));
+                }
+            }
+            w.flush()?;
+        }
+        Ok(file)
+    }
+}
+
+// A table of (key, exponent) records sorted by key in a memory-mapped
+// temporary file, searched by binary search
+pub struct DiskTable {
+    mmap: Mmap,
+    len: usize,
+}
+
+impl DiskTable {
+    fn record(&self, i: usize) -> (u64, u64) {
+        let mut key = [0u8; 8];
+        let mut x = [0u8; 8];
+        let offset = i * RECORD_SIZE;
+        key.copy_from_slice(&self.mmap[offset..offset + 8]);
+        x.copy_from_slice(&self.mmap[offset + 8..offset + RECORD_SIZE]);
+        (u64::from_le_bytes(key), u64::from_le_bytes(x))
+    }
+}
+
+impl TableLookup for DiskTable {
+    fn exponents(&self, key: u64) -> Vec<u64> {
+        // First record with a key >= the one looked up
+        let (mut lo, mut hi) = (0, self.len);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if self.record(mid).0 < key {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+
+        (lo..self.len)
+            .map(|i| self.record(i))
+            .take_while(|&(k, _)| k == key)
+            .map(|(_, x1)| x1)
+            .collect()
+    }
+}
+
+pub fn build_table<G: Group>(group: &G, h: &G::Element, g_inverse: &G::Element, b: u64,
+                             options: &DiskOptions, progress: &Progress) -> io::Result<DiskTable> {
+    let mut sorter = ExternalSorter::new(options);
+    progress.phase("table entries", b);
+    for_each_left(group, h, g_inverse, b, |key, x1| {
+        sorter.push((key, x1));
+        progress.insert();
+        progress.tick();
+    });
+    sorter.finish()
+}
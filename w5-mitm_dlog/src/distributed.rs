@@ -0,0 +1,340 @@
+use std::collections::VecDeque;
+use std::io;
+use std::io::prelude::*;
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::ops::Range;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+
+use num_bigint::BigUint;
+use tracing::{debug, info, warn};
+
+use crate::group::{Group, Zp};
+use crate::mitm::{build_table, find_x, lookup_x0_x1, scan_length, split, Table};
+use crate::progress::Progress;
+use crate::Instance;
+
+// Frames larger than this are rejected rather than allocated
+const MAX_FRAME: u32 = 1 << 20;
+
+// One rectangle of the MITM search: a table of h * g^(-x1) for x1 in x1,
+// scanned against (g^b1)^x0 for x0 in x0
+#[derive(Debug, Clone, PartialEq)]
+pub struct Job {
+    pub id: u64,
+    pub p: BigUint,
+    pub g: BigUint,
+    pub h: BigUint,
+    pub b1: u64,
+    pub x1: Range<u64>,
+    pub x0: Range<u64>,
+}
+
+// Every message is a frame of a u32 little-endian length followed by a
+// tag byte and the fields: u64s little-endian, integers as length-prefixed
+// big-endian bytes
+#[derive(Debug, Clone, PartialEq)]
+pub enum Message {
+    // Worker -> coordinator: ready for the first job
+    Ready,
+    // Coordinator -> worker
+    Job(Job),
+    // Worker -> coordinator: result of a job, which also asks for the next
+    Found { id: u64, x: BigUint },
+    NotFound { id: u64 },
+    // Coordinator -> worker: no more jobs
+    Done,
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn put_u64(buf: &mut Vec<u8>, n: u64) {
+    buf.extend(&n.to_le_bytes());
+}
+
+fn put_big(buf: &mut Vec<u8>, n: &BigUint) {
+    let bytes = n.to_bytes_be();
+    buf.extend(&(bytes.len() as u32).to_le_bytes());
+    buf.extend(bytes);
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> io::Result<&'a [u8]> {
+        if self.0.len() < n {
+            return Err(invalid_data("truncated message"));
+        }
+        let (head, tail) = self.0.split_at(n);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn u64(&mut self) -> io::Result<u64> {
+        let mut buf = [0u8; 8];
+        buf.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(buf))
+    }
+
+    fn big(&mut self) -> io::Result<BigUint> {
+        let mut len = [0u8; 4];
+        len.copy_from_slice(self.take(4)?);
+        let bytes = self.take(u32::from_le_bytes(len) as usize)?;
+        Ok(BigUint::from_bytes_be(bytes))
+    }
+}
+
+impl Message {
+    pub fn write_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        let mut buf = Vec::new();
+        match self {
+            Message::Ready => buf.push(0),
+            Message::Job(job) => {
+                buf.push(1);
+                put_u64(&mut buf, job.id);
+                put_big(&mut buf, &job.p);
+                put_big(&mut buf, &job.g);
+                put_big(&mut buf, &job.h);
+                for &n in &[job.b1, job.x1.start, job.x1.end, job.x0.start, job.x0.end] {
+                    put_u64(&mut buf, n);
+                }
+            },
+            Message::Found { id, x } => {
+                buf.push(2);
+                put_u64(&mut buf, *id);
+                put_big(&mut buf, x);
+            },
+            Message::NotFound { id } => {
+                buf.push(3);
+                put_u64(&mut buf, *id);
+            },
+            Message::Done => buf.push(4),
+        }
+        w.write_all(&(buf.len() as u32).to_le_bytes())?;
+        w.write_all(&buf)?;
+        w.flush()
+    }
+
+    pub fn read_from<R: Read>(r: &mut R) -> io::Result<Message> {
+        let mut len = [0u8; 4];
+        r.read_exact(&mut len)?;
+        let len = u32::from_le_bytes(len);
+        if len == 0 || len > MAX_FRAME {
+            return Err(invalid_data("bad frame length"));
+        }
+        let mut buf = vec![0u8; len as usize];
+        r.read_exact(&mut buf)?;
+
+        let mut reader = Reader(&buf[1..]);
+        let message = match buf[0] {
+            0 => Message::Ready,
+            1 => {
+                let id = reader.u64()?;
+                let (p, g, h) = (reader.big()?, reader.big()?, reader.big()?);
+                let b1 = reader.u64()?;
+                let x1 = reader.u64()?..reader.u64()?;
+                let x0 = reader.u64()?..reader.u64()?;
+                Message::Job(Job { id, p, g, h, b1, x1, x0 })
+            },
+            2 => Message::Found { id: reader.u64()?, x: reader.big()? },
+            3 => Message::NotFound { id: reader.u64()? },
+            4 => Message::Done,
+            _ => return Err(invalid_data("unknown message")),
+        };
+        Ok(message)
+    }
+}
+
+// Splits [0, n) into at most `parts` nearly equal ranges
+fn shards(n: u64, parts: u64) -> Vec<Range<u64>> {
+    let parts = parts.clamp(1, n.max(1));
+    (0..parts).map(|i| (n * i / parts)..(n * (i + 1) / parts)).collect()
+}
+
+// Partitions the instance's x1 and x0 ranges into a grid of jobs. Table
+// shards split the table across workers' memory; scan shards split the
+// scan, with every worker reusing its table shard across jobs.
+pub fn plan_jobs(instance: &Instance<Zp>, table_shards: u64, scan_shards: u64) -> Vec<Job> {
+    let group = &instance.group;
+    let b1 = split(instance.bound);
+    let b2 = scan_length(instance.bound, b1);
+    let (p, g, h) = (group.p.clone(), group.value(&instance.g), group.value(&instance.h));
+
+    let mut jobs = Vec::new();
+    for x1 in shards(b1, table_shards) {
+        for x0 in shards(b2, scan_shards) {
+            let id = jobs.len() as u64;
+            jobs.push(Job { id, p: p.clone(), g: g.clone(), h: h.clone(), b1, x1: x1.clone(), x0 });
+        }
+    }
+    jobs
+}
+
+struct State {
+    pending: VecDeque<Job>,
+    remaining: usize,
+    found: Option<BigUint>,
+}
+
+type Shared = Arc<(Mutex<State>, Condvar)>;
+
+// Serves one worker until it disconnects or the search is over, keeping
+// track of the job it holds
+fn serve_jobs(stream: TcpStream, instance: &Instance<Zp>, shared: &Shared,
+              current: &mut Option<Job>) -> io::Result<()> {
+    let (state, done) = &**shared;
+    let mut reader = io::BufReader::new(stream.try_clone()?);
+    let mut writer = io::BufWriter::new(stream);
+
+    loop {
+        let message = Message::read_from(&mut reader)?;
+        let held = current.as_ref().map(|job| job.id);
+        let mut state = state.lock().unwrap();
+        match message {
+            Message::Ready if held.is_none() => {},
+            Message::Found { id, ref x } if held == Some(id) => {
+                // The worker is trusted with the search, not with the answer
+                if instance.is_solution(x) {
+                    state.found = Some(x.clone());
+                } else {
+                    warn!(job = id, "Worker claimed a wrong x: {}", x);
+                }
+                state.remaining -= 1;
+            },
+            Message::NotFound { id } if held == Some(id) => {
+                state.remaining -= 1;
+                debug!(job = id, remaining = state.remaining, "Job done without x");
+            },
+            _ => return Err(invalid_data("unexpected message from worker")),
+        }
+        *current = None;
+        if state.found.is_some() || state.remaining == 0 {
+            done.notify_all();
+        }
+
+        // With no jobs left to hand out, wait in case another worker drops one
+        let next = loop {
+            if state.found.is_some() || state.remaining == 0 {
+                break None;
+            }
+            if let Some(job) = state.pending.pop_front() {
+                break Some(job);
+            }
+            state = done.wait(state).unwrap();
+        };
+        drop(state);
+        match next {
+            Some(job) => {
+                debug!(job = job.id, "Handing out job");
+                Message::Job(job.clone()).write_to(&mut writer)?;
+                *current = Some(job);
+            },
+            None => return Message::Done.write_to(&mut writer),
+        }
+    }
+}
+
+// A job held by a worker that disconnects is handed out again
+fn serve(stream: TcpStream, instance: &Instance<Zp>, shared: &Shared) -> io::Result<()> {
+    let mut current = None;
+    let result = serve_jobs(stream, instance, shared, &mut current);
+    if let Some(job) = current {
+        shared.0.lock().unwrap().pending.push_front(job);
+        shared.1.notify_all();
+    }
+    result
+}
+
+// Listens for workers and hands out the jobs until one of them finds x or
+// all of them come back empty. Returns x relative to the instance.
+pub fn coordinate<A: ToSocketAddrs>(addr: A, instance: Instance<Zp>,
+                                    jobs: Vec<Job>) -> io::Result<Option<BigUint>> {
+    let listener = TcpListener::bind(addr)?;
+    let shared: Shared = Arc::new((Mutex::new(State {
+        remaining: jobs.len(),
+        pending: jobs.into_iter().collect(),
+        found: None,
+    }), Condvar::new()));
+    let instance = Arc::new(instance);
+
+    {
+        let shared = shared.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let stream = match stream {
+                    Ok(stream) => stream,
+                    Err(_) => continue,
+                };
+                let (shared, instance) = (shared.clone(), instance.clone());
+                thread::spawn(move || {
+                    let peer = stream.peer_addr().ok();
+                    debug!("Worker {:?} connected", peer);
+                    if let Err(e) = serve(stream, &instance, &shared) {
+                        if e.kind() != io::ErrorKind::UnexpectedEof {
+                            warn!("Worker {:?}: {}", peer, e);
+                        }
+                    }
+                });
+            }
+        });
+    }
+
+    let (state, done) = &*shared;
+    let mut state = state.lock().unwrap();
+    while state.found.is_none() && state.remaining > 0 {
+        state = done.wait(state).unwrap();
+    }
+    Ok(state.found.take())
+}
+
+// The last table shard built, reused while jobs share its instance and x1 range
+struct CachedTable {
+    key: (BigUint, BigUint, BigUint, u64, Range<u64>),
+    h_shifted: <Zp as Group>::Element,
+    table: Table,
+}
+
+// Runs jobs from the coordinator at addr until it has no more
+pub fn work<A: ToSocketAddrs>(addr: A, progress: &Progress) -> io::Result<()> {
+    let stream = TcpStream::connect(addr)?;
+    let mut reader = io::BufReader::new(stream.try_clone()?);
+    let mut writer = io::BufWriter::new(stream);
+    let mut cache: Option<CachedTable> = None;
+
+    Message::Ready.write_to(&mut writer)?;
+    loop {
+        let job = match Message::read_from(&mut reader)? {
+            Message::Job(job) => job,
+            Message::Done => return Ok(()),
+            _ => return Err(invalid_data("unexpected message from coordinator")),
+        };
+        info!("Job {}: x1 in [{}, {}), x0 in [{}, {})",
+              job.id, job.x1.start, job.x1.end, job.x0.start, job.x0.end);
+
+        let group = Zp::new(job.p.clone());
+        let g = group.element(&job.g);
+        let key = (job.p.clone(), job.g.clone(), job.h.clone(), job.b1, job.x1.clone());
+        if cache.as_ref().map(|cached| &cached.key) != Some(&key) {
+            // The shard for x1 in [lo, hi) is the table for h * g^(-lo)
+            let g_inverse = group.inverse(&g);
+            let h = group.element(&job.h);
+            let h_shifted = group.op(&h, &group.exp(&g_inverse, &BigUint::from(job.x1.start)));
+            let table = build_table(&group, &h_shifted, &g_inverse,
+                                    job.x1.end - job.x1.start, progress);
+            cache = Some(CachedTable { key, h_shifted, table });
+        }
+        let cached = cache.as_ref().unwrap();
+
+        let reply = match lookup_x0_x1(&group, &cached.table, &g, &cached.h_shifted, job.b1,
+                                       job.x0.clone(), progress) {
+            Some((x0, x1)) => Message::Found {
+                id: job.id,
+                x: BigUint::from(find_x(x0, x1 + job.x1.start, job.b1)),
+            },
+            None => Message::NotFound { id: job.id },
+        };
+        reply.write_to(&mut writer)?;
+    }
+}
@@ -0,0 +1,149 @@
+use std::fmt;
+
+use num_bigint::BigUint;
+
+use crate::group::Group;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Point {
+    Infinity,
+    Affine(BigUint, BigUint),
+}
+
+impl fmt::Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Point::Infinity => write!(f, "infinity"),
+            Point::Affine(x, y) => write!(f, "({}, {})", x, y),
+        }
+    }
+}
+
+// The group of points on y^2 = x^3 + ax + b over Fp, in affine coordinates
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Curve {
+    pub p: BigUint,
+    pub a: BigUint,
+    pub b: BigUint,
+    pub generator: Point,
+    // Number of points on the curve, if known
+    pub order: Option<BigUint>,
+}
+
+impl Curve {
+    // A toy curve of prime order 268430219 over a 28-bit field, small
+    // enough that any discrete log on it is solved in seconds
+    pub fn toy() -> Curve {
+        Curve {
+            p: BigUint::from(268_435_399u32),
+            a: BigUint::from(10_065_165u32),
+            b: BigUint::from(23_071_285u32),
+            generator: Point::Affine(BigUint::from(1u32), BigUint::from(45_520_182u32)),
+            order: Some(BigUint::from(268_430_219u32)),
+        }
+    }
+
+    // The Bitcoin curve y^2 = x^3 + 7 over a 256-bit field
+    #[cfg(feature = "secp256k1")]
+    pub fn secp256k1() -> Curve {
+        let parse = |s: &[u8]| BigUint::parse_bytes(s, 16).unwrap();
+        Curve {
+            p: parse(b"fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f"),
+            a: BigUint::from(0u32),
+            b: BigUint::from(7u32),
+            generator: Point::Affine(
+                parse(b"79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798"),
+                parse(b"483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8"),
+            ),
+            order: Some(parse(b"fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141")),
+        }
+    }
+
+    pub fn contains(&self, point: &Point) -> bool {
+        match point {
+            Point::Infinity => true,
+            Point::Affine(x, y) => {
+                let p = &self.p;
+                x < p && y < p && y * y % p == (x * x * x + &self.a * x + &self.b) % p
+            },
+        }
+    }
+
+    fn sub(&self, a: &BigUint, b: &BigUint) -> BigUint {
+        (a + &self.p - b % &self.p) % &self.p
+    }
+
+    fn div(&self, a: &BigUint, b: &BigUint) -> BigUint {
+        let two = BigUint::from(2u32);
+        a * b.modpow(&(&self.p - &two), &self.p) % &self.p
+    }
+}
+
+impl Group for Curve {
+    type Element = Point;
+
+    fn identity(&self) -> Point {
+        Point::Infinity
+    }
+
+    fn op(&self, a: &Point, b: &Point) -> Point {
+        let (x1, y1, x2, y2) = match (a, b) {
+            (Point::Infinity, _) => return b.clone(),
+            (_, Point::Infinity) => return a.clone(),
+            (Point::Affine(x1, y1), Point::Affine(x2, y2)) => (x1, y1, x2, y2),
+        };
+        let p = &self.p;
+
+        let slope = if x1 == x2 {
+            if (y1 + y2) % p == BigUint::from(0u32) {
+                return Point::Infinity;
+            }
+            // Tangent line when doubling
+            let three = BigUint::from(3u32);
+            self.div(&(three * x1 * x1 + &self.a), &(y1 + y1))
+        } else {
+            self.div(&self.sub(y2, y1), &self.sub(x2, x1))
+        };
+
+        let x3 = self.sub(&self.sub(&(&slope * &slope), x1), x2);
+        let y3 = self.sub(&(&slope * self.sub(x1, &x3)), y1);
+        Point::Affine(x3, y3)
+    }
+
+    fn inverse(&self, a: &Point) -> Point {
+        match a {
+            Point::Infinity => Point::Infinity,
+            Point::Affine(x, y) => Point::Affine(x.clone(), self.sub(&self.p, y)),
+        }
+    }
+
+    // SEC1 compressed encoding: parity of y followed by x
+    fn serialize(&self, a: &Point) -> Vec<u8> {
+        let len = self.p.bits().div_ceil(8);
+        match a {
+            Point::Infinity => vec![0; len + 1],
+            Point::Affine(x, y) => {
+                let odd = y.to_bytes_le()[0] & 1 == 1;
+                let bytes = x.to_bytes_be();
+                let mut buf = vec![if odd { 3 } else { 2 }];
+                buf.resize(len + 1 - bytes.len(), 0);
+                buf.extend(bytes);
+                buf
+            },
+        }
+    }
+
+    fn order(&self) -> Option<BigUint> {
+        self.order.clone()
+    }
+
+    fn parameters(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        for n in &[&self.p, &self.a, &self.b] {
+            let bytes = n.to_bytes_be();
+            buf.extend(&(bytes.len() as u32).to_le_bytes());
+            buf.extend(bytes);
+        }
+        buf
+    }
+}
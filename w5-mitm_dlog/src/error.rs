@@ -0,0 +1,84 @@
+use std::io;
+use std::path::PathBuf;
+
+use crypto_utils::exit::{self, Status};
+use crypto_utils::interrupt;
+use num_bigint::BigUint;
+use thiserror::Error;
+
+use crate::validate::Invalid;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Invalid instance: {0}")]
+    Instance(#[from] Invalid),
+    #[error("Invalid value for --{name}: {value}")]
+    Value { name: &'static str, value: String },
+    #[error("Invalid point for --{name}: {value}")]
+    Point { name: &'static str, value: String },
+    #[error("Unknown curve: {0}")]
+    UnknownCurve(String),
+    #[error("Unknown algorithm: {0}")]
+    UnknownAlgorithm(String),
+    #[error("A target point is required on curves: pass --target X,Y")]
+    NoCurveTarget,
+    #[error("The group order is unknown; pass --order")]
+    NoOrder,
+    #[error("rho needs the order of g: pass --order")]
+    RhoNoOrder,
+    #[error("Invalid factorization: {0}")]
+    Factors(String),
+    #[error("Factorization {list} does not multiply to {order}")]
+    FactorsProduct { list: String, order: BigUint },
+    #[error("Could not factor {0} by trial division; pass --factors")]
+    Unfactored(BigUint),
+    #[error("--max-memory {0} is too small for any table")]
+    MaxMemory(u64),
+    #[error("{} only covers ranges of up to {covered} values; precompute it with a larger \
+             --range", path.display())]
+    SmallTable { path: PathBuf, covered: u128 },
+    #[error("Invalid target in {}: {line}", path.display())]
+    Target { path: PathBuf, line: String },
+    #[error("--table only works with bsgs")]
+    TableNeedsBsgs,
+    #[error("--targets only works with bsgs")]
+    TargetsNeedBsgs,
+    #[error("--targets solves with a single algorithm")]
+    Compare,
+    #[error("Distributed mode only supports Zp*")]
+    DistributedCurve,
+    #[error("--gpu only supports Zp*")]
+    GpuCurve,
+    #[error("--gpu needs a build with --features gpu (and without gmp)")]
+    NoGpu,
+    #[error("Alice and Bob disagree on the shared secret")]
+    Disagree,
+    #[error("Mallory could not find {0}'s secret")]
+    NoSecret(&'static str),
+    #[error("No x with g^x = h in the range searched")]
+    NotFound,
+    #[error("Could not read {}: {source}", path.display())]
+    Read { path: PathBuf, source: io::Error },
+    #[error("Could not write {}: {source}", path.display())]
+    Write { path: PathBuf, source: io::Error },
+    #[error("Solving failed: {0}")]
+    Solve(io::Error),
+    #[error("Could not listen on {addr}: {source}")]
+    Listen { addr: String, source: io::Error },
+    #[error("Worker for {addr}: {source}")]
+    Worker { addr: String, source: io::Error },
+    #[error("Could not handle Ctrl-C: {0}")]
+    CtrlC(#[from] interrupt::Error),
+}
+
+impl Status for Error {
+    fn exit_code(&self) -> u8 {
+        match self {
+            Error::Disagree | Error::NoSecret(_) | Error::NotFound | Error::CtrlC(_) => exit::FAILED,
+            Error::Read { .. } | Error::Write { .. } | Error::Solve(_) | Error::Listen { .. }
+                => exit::IO,
+            Error::Worker { .. } => exit::ORACLE,
+            _ => exit::USAGE,
+        }
+    }
+}
@@ -0,0 +1,168 @@
+use std::fmt;
+use std::sync::mpsc;
+
+use num_bigint::BigUint;
+use wgpu::util::DeviceExt;
+
+use crate::montgomery::{Montgomery, Residue};
+
+// Lanes per dispatch and at most this many consecutive powers per lane
+const LANES: u64 = 1 << 14;
+const MAX_STEPS: u64 = 64;
+// Inner loop iterations per lane. Some drivers (llvmpipe) silently stop a
+// shader after 65535 iterations, and a multiplication takes about 2n^2.
+const LOOP_BUDGET: u64 = 1 << 15;
+// Must match MAX_LIMBS in the shader
+const MAX_LIMBS: usize = 64;
+
+// A compute pipeline that multiplies Montgomery residues by a fixed step
+// in many lanes at once (see gpu.wgsl)
+pub struct Gpu {
+    pub name: String,
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipeline: wgpu::ComputePipeline,
+}
+
+impl fmt::Debug for Gpu {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Gpu({})", self.name)
+    }
+}
+
+fn to_u32_limbs(limbs: &[u64]) -> Vec<u32> {
+    limbs.iter().flat_map(|&limb| vec![limb as u32, (limb >> 32) as u32]).collect()
+}
+
+fn as_bytes(words: &[u32]) -> Vec<u8> {
+    words.iter().flat_map(|word| word.to_le_bytes().to_vec()).collect()
+}
+
+impl Gpu {
+    // None if there is no adapter to run on
+    pub fn new() -> Option<Gpu> {
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
+            backends: wgpu::Backends::all(),
+            ..Default::default()
+        });
+        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            ..Default::default()
+        }))?;
+        let (device, queue) = pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor {
+            label: None,
+            required_features: wgpu::Features::empty(),
+            required_limits: adapter.limits(),
+            memory_hints: wgpu::MemoryHints::Performance,
+        }, None)).ok()?;
+
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("powers"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("gpu.wgsl").into()),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("powers"),
+            layout: None,
+            module: &module,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        Some(Gpu { name: adapter.get_info().name, device, queue, pipeline })
+    }
+
+    // Calls f(i, low) with the low 64 bits of the residue a * step^i for
+    // every i in [0, count), in order. Returns false without calling f if
+    // the modulus is too large for the shader.
+    pub fn for_each_power_low<F>(&self, mont: &Montgomery, a: &Residue, step: &Residue,
+                                 count: u64, mut f: F) -> bool
+        where F: FnMut(u64, u64)
+    {
+        let n = 2 * mont.limbs().len();
+        if n > MAX_LIMBS {
+            return false;
+        }
+        let steps = (LOOP_BUDGET / (2 * n * n) as u64).clamp(1, MAX_STEPS);
+
+        let storage = |contents: &[u32], usage| {
+            self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: None,
+                contents: &as_bytes(contents),
+                usage: wgpu::BufferUsages::STORAGE | usage,
+            })
+        };
+        let params = storage(&[0; 4], wgpu::BufferUsages::COPY_DST);
+        let modulus = storage(&to_u32_limbs(mont.limbs()), wgpu::BufferUsages::empty());
+        let step_buffer = storage(&to_u32_limbs(&step.0), wgpu::BufferUsages::empty());
+        let starts = storage(&vec![0; LANES as usize * n], wgpu::BufferUsages::COPY_DST);
+        let keys_size = LANES * MAX_STEPS * 8;
+        let keys = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: keys_size,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let readback = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: keys_size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &self.pipeline.get_bind_group_layout(0),
+            entries: &[&params, &modulus, &step_buffer, &starts, &keys].iter()
+                .enumerate()
+                .map(|(i, buffer)| wgpu::BindGroupEntry {
+                    binding: i as u32,
+                    resource: buffer.as_entire_binding(),
+                })
+                .collect::<Vec<_>>(),
+        });
+
+        // Each lane starts `steps` powers after the previous one
+        let lane_step = mont.pow(step, &BigUint::from(steps));
+        let mut start = a.clone();
+        let mut done = 0;
+        while done < count {
+            let lanes = LANES.min((count - done).div_ceil(steps));
+            let mut lane_starts = Vec::with_capacity(lanes as usize * n);
+            for _ in 0..lanes {
+                lane_starts.extend(to_u32_limbs(&start.0));
+                start = mont.mul(&start, &lane_step);
+            }
+            let p_inv = mont.p_inv() as u32;
+            self.queue.write_buffer(&params, 0, &as_bytes(&[n as u32, p_inv, steps as u32, lanes as u32]));
+            self.queue.write_buffer(&starts, 0, &as_bytes(&lane_starts));
+
+            let mut encoder = self.device.create_command_encoder(&Default::default());
+            {
+                let mut pass = encoder.begin_compute_pass(&Default::default());
+                pass.set_pipeline(&self.pipeline);
+                pass.set_bind_group(0, &bind_group, &[]);
+                pass.dispatch_workgroups(lanes.div_ceil(64) as u32, 1, 1);
+            }
+            let used = lanes * steps * 8;
+            encoder.copy_buffer_to_buffer(&keys, 0, &readback, 0, used);
+            self.queue.submit(Some(encoder.finish()));
+
+            let slice = readback.slice(..used);
+            let (tx, rx) = mpsc::channel();
+            slice.map_async(wgpu::MapMode::Read, move |result| tx.send(result).unwrap());
+            self.device.poll(wgpu::Maintain::Wait);
+            rx.recv().unwrap().expect("failed to read back from the GPU");
+            {
+                let data = slice.get_mapped_range();
+                for chunk in data.chunks_exact(8).take((count - done) as usize) {
+                    let mut low = [0u8; 8];
+                    low.copy_from_slice(chunk);
+                    f(done, u64::from_le_bytes(low));
+                    done += 1;
+                }
+            }
+            readback.unmap();
+        }
+        true
+    }
+}
@@ -0,0 +1,122 @@
+// Each lane starts at a Montgomery residue x and writes the low 64 bits of
+// x, x * step, x * step^2, ... for `steps` steps. Residues are n 32-bit
+// limbs, little-endian, with the same R as the CPU's 64-bit limbs.
+
+struct Params {
+    n: u32,
+    p_inv: u32,
+    steps: u32,
+    lanes: u32,
+}
+
+@group(0) @binding(0) var<storage, read> params: Params;
+@group(0) @binding(1) var<storage, read> modulus: array<u32>;
+@group(0) @binding(2) var<storage, read> step: array<u32>;
+@group(0) @binding(3) var<storage, read> starts: array<u32>;
+@group(0) @binding(4) var<storage, read_write> keys: array<u32>;
+
+// Up to 2048-bit moduli
+const MAX_LIMBS: u32 = 64u;
+
+var<private> x: array<u32, MAX_LIMBS>;
+var<private> t: array<u32, 66>;
+
+// 32 x 32 -> 64-bit product as (low, high), from 16-bit halves
+fn mul_wide(a: u32, b: u32) -> vec2<u32> {
+    let a0 = a & 0xffffu;
+    let a1 = a >> 16u;
+    let b0 = b & 0xffffu;
+    let b1 = b >> 16u;
+    let p00 = a0 * b0;
+    let p01 = a0 * b1;
+    let p10 = a1 * b0;
+    let p11 = a1 * b1;
+    let mid = (p00 >> 16u) + (p01 & 0xffffu) + (p10 & 0xffffu);
+    let lo = (p00 & 0xffffu) | (mid << 16u);
+    let hi = p11 + (p01 >> 16u) + (p10 >> 16u) + (mid >> 16u);
+    return vec2<u32>(lo, hi);
+}
+
+// a + b * c + carry as (low, high)
+fn mac(a: u32, b: u32, c: u32, carry: u32) -> vec2<u32> {
+    let p = mul_wide(b, c);
+    let lo = p.x + a;
+    var hi = p.y + select(0u, 1u, lo < a);
+    let lo2 = lo + carry;
+    hi = hi + select(0u, 1u, lo2 < lo);
+    return vec2<u32>(lo2, hi);
+}
+
+// x = x * step * R^(-1) mod p, by the same CIOS as the CPU
+fn mont_mul_step() {
+    let n = params.n;
+    for (var i = 0u; i < n + 2u; i++) {
+        t[i] = 0u;
+    }
+
+    for (var i = 0u; i < n; i++) {
+        let b_i = step[i];
+        var carry = 0u;
+        for (var j = 0u; j < n; j++) {
+            let r = mac(t[j], x[j], b_i, carry);
+            t[j] = r.x;
+            carry = r.y;
+        }
+        let sum = t[n] + carry;
+        t[n + 1u] = select(0u, 1u, sum < carry);
+        t[n] = sum;
+
+        let m = t[0] * params.p_inv;
+        var r = mac(t[0], m, modulus[0], 0u);
+        carry = r.y;
+        for (var j = 1u; j < n; j++) {
+            r = mac(t[j], m, modulus[j], carry);
+            t[j - 1u] = r.x;
+            carry = r.y;
+        }
+        let sum2 = t[n] + carry;
+        t[n - 1u] = sum2;
+        t[n] = t[n + 1u] + select(0u, 1u, sum2 < carry);
+    }
+
+    // t < 2p, so at most one subtraction brings it below p
+    var subtract = t[n] != 0u;
+    if (!subtract) {
+        subtract = true;
+        for (var j = n; j > 0u; j--) {
+            if (t[j - 1u] != modulus[j - 1u]) {
+                subtract = t[j - 1u] > modulus[j - 1u];
+                break;
+            }
+        }
+    }
+    var borrow = 0u;
+    for (var j = 0u; j < n; j++) {
+        if (subtract) {
+            let d = t[j] - modulus[j];
+            let b1 = select(0u, 1u, t[j] < modulus[j]);
+            x[j] = d - borrow;
+            borrow = b1 | select(0u, 1u, d < borrow);
+        } else {
+            x[j] = t[j];
+        }
+    }
+}
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let lane = id.x;
+    if (lane >= params.lanes) {
+        return;
+    }
+    let n = params.n;
+    for (var j = 0u; j < n; j++) {
+        x[j] = starts[lane * n + j];
+    }
+    for (var k = 0u; k < params.steps; k++) {
+        let out = (lane * params.steps + k) * 2u;
+        keys[out] = x[0];
+        keys[out + 1u] = x[1];
+        mont_mul_step();
+    }
+}
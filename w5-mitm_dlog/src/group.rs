@@ -0,0 +1,170 @@
+use std::fmt::Debug;
+use std::hash::Hash;
+#[cfg(all(feature = "gpu", not(feature = "gmp")))]
+use std::sync::Arc;
+
+use num_bigint::BigUint;
+
+use crate::arith::{bytes_hash, mix};
+use crate::bigint::{Backend, Int, Modulus};
+#[cfg(all(feature = "gpu", not(feature = "gmp")))]
+use crate::gpu::Gpu;
+
+// A finite cyclic group in which discrete logs are taken. Groups and their
+// elements are shared by the threads of a parallel scan.
+pub trait Group: Sync {
+    type Element: Clone + Eq + Hash + Debug + Send + Sync;
+
+    fn identity(&self) -> Self::Element;
+
+    fn op(&self, a: &Self::Element, b: &Self::Element) -> Self::Element;
+
+    fn inverse(&self, a: &Self::Element) -> Self::Element;
+
+    // Square-and-multiply (double-and-add for curves) over the bits of n
+    fn exp(&self, a: &Self::Element, n: &BigUint) -> Self::Element {
+        let mut result = self.identity();
+        for byte in n.to_bytes_be() {
+            for i in (0..8).rev() {
+                result = self.op(&result, &result);
+                if (byte >> i) & 1 == 1 {
+                    result = self.op(&result, a);
+                }
+            }
+        }
+        result
+    }
+
+    // Canonical fixed-length encoding of an element
+    fn serialize(&self, a: &Self::Element) -> Vec<u8>;
+
+    // Order of the whole group, if known
+    fn order(&self) -> Option<BigUint>;
+
+    // Encoding of the group's defining parameters, used to bind
+    // precomputed tables to the group they were built for
+    fn parameters(&self) -> Vec<u8>;
+
+    // Hash used to drive pseudo-random walks (rho, kangaroo)
+    fn hash(&self, a: &Self::Element) -> u64 {
+        bytes_hash(&self.serialize(a))
+    }
+
+    // Calls f(i, hash) with the hash of a * step^i for every i in [0, count),
+    // the bulk of building a table. Groups that can batch the products
+    // elsewhere (on a GPU) override it.
+    fn for_each_power_hash<F>(&self, a: &Self::Element, step: &Self::Element, count: u64, f: F)
+        where Self: Sized, F: FnMut(u64, u64)
+    {
+        powers_hash(self, a, step, count, f)
+    }
+}
+
+fn powers_hash<G, F>(group: &G, a: &G::Element, step: &G::Element, count: u64, mut f: F)
+    where G: Group, F: FnMut(u64, u64)
+{
+    // start with a instead of exponentiating a * step^i on every iteration
+    let mut x = a.clone();
+    f(0, group.hash(&x));
+    for i in 1..count {
+        // reuse the previous power: simply multiply by step
+        x = group.op(&x, step);
+        f(i, group.hash(&x));
+    }
+}
+
+// The multiplicative group Zp* of integers modulo an odd prime p. Elements
+// use the arithmetic backend selected at build time; convert with element().
+#[derive(Debug, Clone)]
+pub struct Zp {
+    pub p: BigUint,
+    modulus: Backend,
+    // Builds tables on the GPU when set
+    #[cfg(all(feature = "gpu", not(feature = "gmp")))]
+    gpu: Option<Arc<Gpu>>,
+}
+
+impl Zp {
+    pub fn new(p: BigUint) -> Zp {
+        let modulus = Backend::new(&p);
+        Zp {
+            p,
+            modulus,
+            #[cfg(all(feature = "gpu", not(feature = "gmp")))]
+            gpu: None,
+        }
+    }
+
+    #[cfg(all(feature = "gpu", not(feature = "gmp")))]
+    pub fn with_gpu(self, gpu: Arc<Gpu>) -> Zp {
+        Zp { gpu: Some(gpu), ..self }
+    }
+
+    pub fn element(&self, n: &BigUint) -> Int {
+        self.modulus.element(n)
+    }
+
+    pub fn value(&self, a: &Int) -> BigUint {
+        BigUint::from_bytes_be(&self.modulus.to_bytes_be(a))
+    }
+}
+
+impl Group for Zp {
+    type Element = Int;
+
+    fn identity(&self) -> Int {
+        self.modulus.element(&BigUint::from(1u32))
+    }
+
+    fn op(&self, a: &Int, b: &Int) -> Int {
+        self.modulus.mul(a, b)
+    }
+
+    // Fermat's little theorem: a^(p-2) = a^(-1)
+    fn inverse(&self, a: &Int) -> Int {
+        let two = BigUint::from(2u32);
+        self.modulus.pow(a, &(&self.p - &two))
+    }
+
+    fn exp(&self, a: &Int, n: &BigUint) -> Int {
+        self.modulus.pow(a, n)
+    }
+
+    fn serialize(&self, a: &Int) -> Vec<u8> {
+        let len = self.p.bits().div_ceil(8);
+        let bytes = self.modulus.to_bytes_be(a);
+        let mut buf = vec![0; len.saturating_sub(bytes.len())];
+        buf.extend(bytes);
+        buf
+    }
+
+    fn order(&self) -> Option<BigUint> {
+        Some(&self.p - BigUint::from(1u32))
+    }
+
+    // Hashes depend on the backend's representation, so it is part of the
+    // parameters that precomputed tables are checked against
+    fn parameters(&self) -> Vec<u8> {
+        let mut buf = self.p.to_bytes_be();
+        buf.extend(Backend::NAME.as_bytes());
+        buf
+    }
+
+    // Mixes the low 64 bits of the representation so that walks driven by
+    // the hash (jump index, partition, distinguished points) look random
+    fn hash(&self, a: &Int) -> u64 {
+        mix(self.modulus.low_u64(a))
+    }
+
+    #[cfg(all(feature = "gpu", not(feature = "gmp")))]
+    fn for_each_power_hash<F>(&self, a: &Int, step: &Int, count: u64, mut f: F)
+        where F: FnMut(u64, u64)
+    {
+        if let Some(ref gpu) = self.gpu {
+            if gpu.for_each_power_low(&self.modulus, a, step, count, |i, low| f(i, mix(low))) {
+                return;
+            }
+        }
+        powers_hash(self, a, step, count, f)
+    }
+}
@@ -0,0 +1,190 @@
+use std::cmp;
+use std::collections::HashMap;
+use std::io;
+use std::sync::Arc;
+
+use num_bigint::BigUint;
+
+use crate::arith::{mix, to_u64};
+use crate::group::Group;
+use crate::progress::Progress;
+use crate::{DiscreteLogSolver, Instance};
+
+// Number of times the wild kangaroo is restarted before giving up
+const MAX_ATTEMPTS: u64 = 32;
+
+// Pollard's kangaroo (lambda): a tame kangaroo starting inside the interval
+// and a wild one starting at h hop by pseudo-random powers of g, leaving
+// traps at distinguished points until the wild one lands on the tame trail
+#[derive(Debug, Clone)]
+pub struct Kangaroo {
+    pub jumps: usize,
+    // Defaults to roughly 2^10 distinguished points over an expected run
+    pub distinguished_bits: Option<u32>,
+    pub progress: Arc<Progress>,
+}
+
+impl Default for Kangaroo {
+    fn default() -> Kangaroo {
+        Kangaroo { jumps: 32, distinguished_bits: None, progress: Arc::default() }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Herd {
+    Tame,
+    Wild,
+}
+
+#[derive(Debug)]
+struct Hopper<E> {
+    herd: Herd,
+    pos: E,
+    dist: u128,
+}
+
+enum Hop {
+    Continue,
+    // Collision between the tame and wild herds: (tame dist, wild dist)
+    Found(u128, u128),
+    // A kangaroo landed on its own herd's trail and will only retrace it
+    Retrace,
+}
+
+struct Jumps<'a, G: Group> {
+    sizes: Vec<u64>,
+    powers: Vec<G::Element>,
+    dp_mask: u64,
+    group: &'a G,
+    progress: &'a Progress,
+}
+
+impl<'a, G: Group> Jumps<'a, G> {
+    fn new(group: &'a G, g: &G::Element, width: u64, params: &'a Kangaroo) -> Self {
+        // Mean jump size of sqrt(width)/2 is optimal for one tame and one
+        // wild kangaroo. Sizes are drawn pseudo-randomly from [1, 2 * mean]:
+        // evenly spaced sizes share a near-common divisor, which keeps the
+        // two kangaroos on disjoint residue classes and they never meet
+        let mean = cmp::max(1, (width as f64).sqrt() as u64 / 2);
+        let k = cmp::max(1, params.jumps) as u64;
+        let sizes: Vec<u64> = (0..k)
+            .map(|i| 1 + mix(i) % (2 * mean))
+            .collect();
+        let powers = sizes.iter()
+            .map(|&s| group.exp(g, &BigUint::from(s)))
+            .collect();
+        let sqrt_bits = (64 - width.leading_zeros()) / 2;
+        let dp_bits = params.distinguished_bits
+            .unwrap_or_else(|| sqrt_bits.saturating_sub(10));
+        let dp_mask = (1u64 << cmp::min(dp_bits, 63)) - 1;
+
+        Jumps { sizes, powers, dp_mask, group, progress: &params.progress }
+    }
+
+    fn hop(&self, hopper: &mut Hopper<G::Element>,
+           traps: &mut HashMap<G::Element, (Herd, u128)>) -> Hop {
+        let j = (self.group.hash(&hopper.pos) % self.sizes.len() as u64) as usize;
+        hopper.pos = self.group.op(&hopper.pos, &self.powers[j]);
+        hopper.dist += u128::from(self.sizes[j]);
+
+        // Use the high half of the hash so distinguished points don't
+        // all share the same jump index
+        if self.group.hash(&hopper.pos).rotate_right(32) & self.dp_mask != 0 {
+            return Hop::Continue;
+        }
+
+        self.progress.lookup();
+        match traps.get(&hopper.pos) {
+            Some(&(herd, dist)) if herd != hopper.herd => {
+                match hopper.herd {
+                    Herd::Tame => Hop::Found(hopper.dist, dist),
+                    Herd::Wild => Hop::Found(dist, hopper.dist),
+                }
+            },
+            Some(_) => Hop::Retrace,
+            None => {
+                traps.insert(hopper.pos.clone(), (hopper.herd, hopper.dist));
+                self.progress.insert();
+                Hop::Continue
+            },
+        }
+    }
+}
+
+impl Kangaroo {
+    fn search<G: Group + Clone>(&self, instance: &Instance<G>) -> Option<BigUint> {
+        let (group, g, target) = (&instance.group, &instance.g, &instance.h);
+        let width = instance.bound.saturating_sub(1);
+        // In small groups the kangaroos wrap around, so collision distances
+        // are only correct modulo the order of g
+        let order = instance.order.as_ref().and_then(to_u64);
+
+        let jumps = Jumps::new(group, g, width, self);
+        let max_steps = 8 * (width as f64).sqrt() as u64 + 8 * (jumps.dp_mask + 1) + 64;
+
+        // Traps are kept across restarts: every distinguished point stays valid
+        let mut traps = HashMap::new();
+
+        let start = width / 2;
+        let mut tame = Hopper {
+            herd: Herd::Tame,
+            pos: group.exp(g, &BigUint::from(start)),
+            dist: u128::from(start),
+        };
+
+        // Each step moves both kangaroos; together they expect about
+        // 2 * sqrt(width) hops
+        self.progress.phase("steps", (width as f64).sqrt() as u64);
+        for attempt in 0..MAX_ATTEMPTS {
+            // A restarted wild kangaroo starts slightly further along so that
+            // it doesn't follow its previous path
+            let mut wild = Hopper {
+                herd: Herd::Wild,
+                pos: group.op(target, &group.exp(g, &BigUint::from(attempt))),
+                dist: u128::from(attempt),
+            };
+
+            for _ in 0..max_steps {
+                self.progress.tick();
+                // The tame kangaroo only retraces its trail if it is caught in
+                // a cycle of a small group, where the wild one can still find it
+                let hop = match jumps.hop(&mut tame, &mut traps) {
+                    Hop::Continue | Hop::Retrace => jumps.hop(&mut wild, &mut traps),
+                    found => found,
+                };
+
+                match hop {
+                    Hop::Continue => continue,
+                    Hop::Retrace => break,
+                    Hop::Found(tame_dist, wild_dist) => {
+                        // g^tame_dist = h * g^wild_dist; the difference is
+                        // only meaningful if it falls inside the interval
+                        let mut diff = tame_dist as i128 - wild_dist as i128;
+                        if let Some(order) = order {
+                            diff = diff.rem_euclid(i128::from(order));
+                        }
+                        if diff >= 0 && diff <= i128::from(width) {
+                            let x = BigUint::from(diff as u64);
+                            if instance.is_solution(&x) {
+                                return Some(x);
+                            }
+                        }
+                        break;
+                    },
+                }
+            }
+        }
+
+        None
+    }
+}
+
+impl<G: Group + Clone> DiscreteLogSolver<G> for Kangaroo {
+    fn name(&self) -> &'static str {
+        "kangaroo"
+    }
+
+    fn find(&self, instance: &Instance<G>) -> io::Result<Option<BigUint>> {
+        Ok(self.search(instance))
+    }
+}
@@ -0,0 +1,117 @@
+extern crate clap;
+extern crate crypto_utils;
+extern crate num_bigint;
+extern crate rand;
+extern crate thiserror;
+extern crate tracing;
+
+pub mod arith;
+mod bigint;
+pub mod bsgs;
+pub mod calc;
+pub mod cli;
+pub mod disk;
+pub mod distributed;
+pub mod ec;
+pub mod error;
+#[cfg(all(feature = "gpu", not(feature = "gmp")))]
+pub mod gpu;
+pub mod group;
+pub mod kangaroo;
+pub mod mitm;
+#[cfg(not(feature = "gmp"))]
+mod montgomery;
+pub mod params;
+pub mod pohlig_hellman;
+pub mod progress;
+pub mod rho;
+pub mod subgroup;
+pub mod validate;
+
+use std::io;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use num_bigint::BigUint;
+
+pub use crate::bsgs::{BabySteps, Bsgs};
+pub use crate::ec::{Curve, Point};
+pub use crate::group::{Group, Zp};
+pub use crate::kangaroo::Kangaroo;
+pub use crate::mitm::{DiskOptions, Mitm};
+pub use crate::pohlig_hellman::PohligHellman;
+pub use crate::progress::{Counted, PhaseStats, Progress};
+pub use crate::rho::Rho;
+
+// A discrete log problem g^x = h in a group, with x known to lie in [0, bound)
+#[derive(Debug, Clone)]
+pub struct Instance<G: Group> {
+    pub group: G,
+    pub g: G::Element,
+    pub h: G::Element,
+    pub bound: u64,
+    // Order of g, or a multiple of it, if known
+    pub order: Option<BigUint>,
+}
+
+impl<G: Group + Clone> Instance<G> {
+    pub fn new(group: G, g: G::Element, h: G::Element, bound: u64) -> Instance<G> {
+        Instance { group, g, h, bound, order: None }
+    }
+
+    // The order of g if known, otherwise the order of the whole group
+    pub fn group_order(&self) -> Option<BigUint> {
+        self.order.clone().or_else(|| self.group.order())
+    }
+
+    // For x known to lie in [lo, lo + bound), the instance for x - lo,
+    // i.e. g^(x - lo) = h * g^(-lo)
+    pub fn shifted(&self, lo: u64, bound: u64) -> Instance<G> {
+        let g_lo = self.group.exp(&self.g, &BigUint::from(lo));
+        let h = self.group.op(&self.h, &self.group.inverse(&g_lo));
+        Instance { h, bound, ..self.clone() }
+    }
+
+    pub fn is_solution(&self, x: &BigUint) -> bool {
+        self.group.exp(&self.g, x) == self.h
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Solution {
+    pub algorithm: &'static str,
+    pub x: BigUint,
+    pub elapsed: Duration,
+}
+
+pub trait DiscreteLogSolver<G: Group> {
+    fn name(&self) -> &'static str;
+
+    // Returns some x with g^x = h, or None if the search space was
+    // exhausted without finding one. Only the solvers with files, such as
+    // the tables of mitm, can fail.
+    fn find(&self, instance: &Instance<G>) -> io::Result<Option<BigUint>>;
+
+    fn solve(&self, instance: &Instance<G>) -> io::Result<Option<Solution>> {
+        let start = Instant::now();
+        let x = self.find(instance)?;
+        Ok(x.map(|x| Solution { algorithm: self.name(), x, elapsed: start.elapsed() }))
+    }
+}
+
+// Solvers that need no configuration beyond the instance itself
+pub fn solver<G: Group + Clone + 'static>(name: &str) -> Option<Box<dyn DiscreteLogSolver<G>>> {
+    solver_with_progress(name, Arc::default())
+}
+
+pub fn solver_with_progress<G>(name: &str, progress: Arc<Progress>) -> Option<Box<dyn DiscreteLogSolver<G>>>
+    where G: Group + Clone + 'static
+{
+    match name {
+        "mitm" => Some(Box::new(Mitm { progress, ..Mitm::default() })),
+        "bsgs" => Some(Box::new(Bsgs { progress, ..Bsgs::default() })),
+        "rho" => Some(Box::new(Rho { progress })),
+        "kangaroo" => Some(Box::new(Kangaroo { progress, ..Kangaroo::default() })),
+        _ => None,
+    }
+}
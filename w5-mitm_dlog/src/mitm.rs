@@ -0,0 +1,347 @@
+use std::cmp;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::io::prelude::*;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+use crypto_utils::encoding::to_hex;
+use crypto_utils::interrupt::Partial;
+use num_bigint::BigUint;
+use tracing::debug;
+
+use crate::disk;
+use crate::group::Group;
+use crate::progress::Progress;
+use crate::{DiscreteLogSolver, Instance};
+
+// Table keys are a 64-bit hash of the group element rather than the element
+// itself (a 512-bit BigUint for the assignment): this cuts table memory by
+// an order of magnitude, at the cost of re-checking every match. Two table
+// entries sharing a key among 2^20 is a ~2^-25 event, so the first exponent
+// of a key is stored inline and any further ones in a side map that is
+// almost always empty, where the scan still finds them.
+#[derive(Debug, Default, Clone)]
+pub struct Table {
+    first: HashMap<u64, u64>,
+    more: HashMap<u64, Vec<u64>>,
+}
+
+impl Table {
+    pub fn with_capacity(entries: usize) -> Table {
+        Table { first: HashMap::with_capacity(entries), more: HashMap::new() }
+    }
+
+    pub fn insert(&mut self, key: u64, x1: u64) {
+        match self.first.entry(key) {
+            Entry::Vacant(entry) => {
+                entry.insert(x1);
+            },
+            Entry::Occupied(_) => self.more.entry(key).or_default().push(x1),
+        }
+    }
+
+    // Every (key, x1) pair, in no particular order
+    pub fn entries(&self) -> impl Iterator<Item = (u64, u64)> + '_ {
+        let more = self.more.iter().flat_map(|(&key, x1s)| x1s.iter().map(move |&x1| (key, x1)));
+        self.first.iter().map(|(&key, &x1)| (key, x1)).chain(more)
+    }
+}
+
+// Exponents stored under a key, in the order they were inserted, each to be
+// re-checked since keys are truncated hashes
+pub trait TableLookup {
+    fn exponents(&self, key: u64) -> Vec<u64>;
+}
+
+impl TableLookup for Table {
+    fn exponents(&self, key: u64) -> Vec<u64> {
+        let mut exponents: Vec<u64> = self.first.get(&key).copied().into_iter().collect();
+        if let Some(more) = self.more.get(&key) {
+            exponents.extend(more);
+        }
+        exponents
+    }
+}
+
+// Smallest b with b^2 >= bound, so that x0 * b + x1 covers [0, bound)
+pub fn split(bound: u64) -> u64 {
+    let mut b = (bound as f64).sqrt() as u64;
+    while u128::from(b) * u128::from(b) < u128::from(bound) {
+        b += 1;
+    }
+    b
+}
+
+// Heap memory of a Table with the given number of entries: the hash map
+// rounds its buckets up to a power of two at a 7/8 load factor, and each
+// bucket holds a 16-byte entry plus one control byte
+pub fn table_bytes(entries: u64) -> u64 {
+    let buckets = cmp::max(8, (entries * 8 / 7).next_power_of_two());
+    buckets * 17
+}
+
+// The number of table entries b1 for a split b1 * b2 >= bound that fits
+// in max_bytes. Building the table and scanning cost about the same per
+// step, so b1 + b2 is smallest for the balanced split; a smaller budget
+// shifts the work to a longer scan. None if not even one entry fits.
+pub fn plan_split(bound: u64, max_bytes: u64) -> Option<u64> {
+    let balanced = split(bound);
+    if table_bytes(balanced) <= max_bytes {
+        return Some(balanced);
+    }
+    // Buckets come in powers of two, so fill the largest bucket count that fits
+    let buckets = (max_bytes / 17).checked_next_power_of_two()?;
+    let buckets = if buckets * 17 > max_bytes { buckets / 2 } else { buckets };
+    let entries = buckets * 7 / 8;
+    if buckets < 8 || entries == 0 {
+        return None;
+    }
+    Some(entries)
+}
+
+// Number of scan steps b2 needed to cover bound with a table of b1 entries
+pub fn scan_length(bound: u64, b1: u64) -> u64 {
+    bound.div_ceil(b1)
+}
+
+// Calls insert(key, x1) with the key of h * g^(-x1) for every x1 in [0, b)
+pub fn for_each_left<G, F>(group: &G, h: &G::Element, g_inverse: &G::Element, b: u64, mut insert: F)
+    where G: Group, F: FnMut(u64, u64)
+{
+    group.for_each_power_hash(h, g_inverse, b, |x1, key| insert(key, x1));
+}
+
+pub fn build_table<G: Group>(group: &G, h: &G::Element, g_inverse: &G::Element,
+                             b: u64, progress: &Progress) -> Table {
+    let mut table = Table::with_capacity(b as usize);
+    progress.phase("table entries", b);
+    for_each_left(group, h, g_inverse, b, |key, x1| {
+        table.insert(key, x1);
+        progress.insert();
+        progress.tick();
+    });
+    table
+}
+
+// build_table, also writing every entry as it is inserted: x1, the element
+// h * g^(-x1) as its serialization in hex, and the key it is stored under.
+// NDJSON for a path ending in .ndjson or .jsonl, CSV otherwise.
+pub fn build_table_dump<G: Group>(group: &G, h: &G::Element, g_inverse: &G::Element, b: u64,
+                                  path: &Path, progress: &Progress) -> io::Result<Table> {
+    let ndjson = matches!(path.extension().and_then(|ext| ext.to_str()),
+                          Some("ndjson") | Some("jsonl"));
+    let mut out = io::BufWriter::new(File::create(path)?);
+    let partial = Partial::new(path);
+    if !ndjson {
+        writeln!(out, "x1,element,key")?;
+    }
+
+    let mut table = Table::with_capacity(b as usize);
+    progress.phase("table entries", b);
+    let mut left = h.clone();
+    for x1 in 0..b {
+        let key = group.hash(&left);
+        table.insert(key, x1);
+        progress.insert();
+        progress.tick();
+
+        let element = to_hex(&group.serialize(&left));
+        if ndjson {
+            writeln!(out, "{{\"x1\": {}, \"element\": \"0x{}\", \"key\": \"0x{:016x}\"}}",
+                     x1, element, key)?;
+        } else {
+            writeln!(out, "{},0x{},0x{:016x}", x1, element, key)?;
+        }
+        if x1 + 1 < b {
+            left = group.op(&left, g_inverse);
+        }
+    }
+    out.flush()?;
+    drop(partial);
+    Ok(table)
+}
+
+// Scans (g^b1)^x0 for x0 in the given range against a table of b1 entries
+pub fn lookup_x0_x1<G, T>(group: &G, table: &T, g: &G::Element, h: &G::Element,
+                          b1: u64, x0_range: Range<u64>, progress: &Progress) -> Option<(u64, u64)>
+    where G: Group, T: TableLookup + ?Sized
+{
+    progress.phase("scanned", x0_range.end - x0_range.start);
+    scan(group, table, g, h, b1, x0_range, &AtomicBool::new(false), progress)
+}
+
+// The scan split into contiguous shards, one per thread. The first thread
+// to find a match sets stop, and the others give up at their next step.
+#[allow(clippy::too_many_arguments)]
+pub fn par_lookup_x0_x1<G, T>(group: &G, table: &T, g: &G::Element, h: &G::Element, b1: u64,
+                              x0_range: Range<u64>, threads: usize,
+                              progress: &Progress) -> Option<(u64, u64)>
+    where G: Group, T: TableLookup + Sync + ?Sized
+{
+    let (start, n) = (x0_range.start, x0_range.end - x0_range.start);
+    let threads = (threads as u64).clamp(1, n.max(1));
+    let stop = AtomicBool::new(false);
+    progress.phase("scanned", n);
+    thread::scope(|scope| {
+        let shards: Vec<_> = (0..threads).map(|i| {
+            let shard = (start + n * i / threads)..(start + n * (i + 1) / threads);
+            let stop = &stop;
+            scope.spawn(move || scan(group, table, g, h, b1, shard, stop, progress))
+        }).collect();
+        shards.into_iter().filter_map(|shard| shard.join().unwrap()).next()
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+fn scan<G, T>(group: &G, table: &T, g: &G::Element, h: &G::Element, b1: u64,
+              x0_range: Range<u64>, stop: &AtomicBool, progress: &Progress) -> Option<(u64, u64)>
+    where G: Group, T: TableLookup + ?Sized
+{
+    let g_b = group.exp(g, &BigUint::from(b1));
+    let mut right = group.exp(&g_b, &BigUint::from(x0_range.start));
+
+    for x0 in x0_range {
+        if stop.load(Ordering::Relaxed) {
+            return None;
+        }
+        progress.tick();
+        progress.lookup();
+        for x1 in table.exponents(group.hash(&right)) {
+            // a matching key may be a hash collision: re-check with the
+            // full computation
+            let x = BigUint::from(find_x(x0, x1, b1));
+            if group.exp(g, &x) == *h {
+                stop.store(true, Ordering::Relaxed);
+                return Some((x0, x1));
+            }
+        }
+
+        // reuse exponentiation: simply multiply
+        // by g^b1 to increase exponent by 1
+        right = group.op(&right, &g_b);
+    }
+    None
+}
+
+pub fn find_x(x0: u64, x1: u64, b: u64) -> u64 {
+    x0 * b + x1
+}
+
+#[derive(Debug, Clone)]
+pub struct DiskOptions {
+    // Where the temporary run and table files are created
+    pub dir: PathBuf,
+    // Records sorted in memory before being written out as a run
+    pub run_records: usize,
+}
+
+// Meet-in-the-middle: a table of h * g^(-x1) for all x1 in [0, b1), then
+// a scan over (g^b1)^x0 for x0 in [0, b2) until both sides meet
+#[derive(Debug, Default, Clone)]
+pub struct Mitm {
+    // Build the table as a sorted file on disk instead of in memory, for
+    // bounds whose table doesn't fit in RAM
+    pub disk: Option<DiskOptions>,
+    // Number of table entries b1; defaults to the balanced split
+    pub table_entries: Option<u64>,
+    // Also write the in-memory table to this file, see build_table_dump
+    pub dump: Option<PathBuf>,
+    // Threads sharing the scan; 0 and 1 both scan on the calling thread
+    pub threads: usize,
+    pub progress: Arc<Progress>,
+}
+
+impl Mitm {
+    fn lookup<G, T>(&self, group: &G, table: &T, instance: &Instance<G>,
+                    b1: u64, b2: u64) -> Option<(u64, u64)>
+        where G: Group, T: TableLookup + Sync + ?Sized
+    {
+        let (g, h) = (&instance.g, &instance.h);
+        if self.threads > 1 {
+            par_lookup_x0_x1(group, table, g, h, b1, 0..b2, self.threads, &self.progress)
+        } else {
+            lookup_x0_x1(group, table, g, h, b1, 0..b2, &self.progress)
+        }
+    }
+}
+
+impl<G: Group + Clone> DiscreteLogSolver<G> for Mitm {
+    fn name(&self) -> &'static str {
+        "mitm"
+    }
+
+    fn find(&self, instance: &Instance<G>) -> io::Result<Option<BigUint>> {
+        let group = &instance.group;
+        let b1 = cmp::max(1, self.table_entries.unwrap_or_else(|| split(instance.bound)));
+        let b2 = scan_length(instance.bound, b1);
+        debug!(table = b1, scan = b2, on_disk = self.disk.is_some(), "Split the search");
+        let g_inverse = group.inverse(&instance.g);
+        let found = match self.disk {
+            None => {
+                let table = match self.dump {
+                    None => build_table(group, &instance.h, &g_inverse, b1, &self.progress),
+                    Some(ref path) => build_table_dump(group, &instance.h, &g_inverse, b1, path,
+                                                       &self.progress)
+                        .map_err(|e| io::Error::new(e.kind(), format!(
+                            "could not dump the table to {}: {}", path.display(), e)))?,
+                };
+                self.lookup(group, &table, instance, b1, b2)
+            },
+            Some(ref options) => {
+                let table = disk::build_table(group, &instance.h, &g_inverse, b1, options,
+                                              &self.progress)
+                    .map_err(|e| io::Error::new(e.kind(), format!(
+                        "could not build the table on disk in {}: {}", options.dir.display(), e)))?;
+                self.lookup(group, &table, instance, b1, b2)
+            },
+        };
+        Ok(found.map(|(x0, x1)| BigUint::from(find_x(x0, x1, b1))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Zp;
+
+    #[test]
+    fn keeps_every_exponent_of_a_key() {
+        let mut table = Table::default();
+        table.insert(7, 1);
+        table.insert(7, 5);
+        table.insert(9, 2);
+        table.insert(7, 3);
+        assert_eq!(table.exponents(7), [1, 5, 3]);
+        assert_eq!(table.exponents(9), [2]);
+        assert!(table.exponents(8).is_empty());
+        let mut entries: Vec<_> = table.entries().collect();
+        entries.sort_unstable();
+        assert_eq!(entries, [(7, 1), (7, 3), (7, 5), (9, 2)]);
+    }
+
+    #[test]
+    fn scans_past_a_colliding_entry() {
+        let zp = Zp::new(BigUint::from(1019u32));
+        let g = zp.element(&BigUint::from(2u32));
+        let b = split(1018);
+        for x in [0, 1, 500, 1017] {
+            let h = zp.exp(&g, &BigUint::from(x));
+            // Wrong exponents before and after the right one under every
+            // key, as if other elements had the same hash
+            let mut table = Table::default();
+            for_each_left(&zp, &h, &zp.inverse(&g), b, |key, x1| {
+                table.insert(key, (x1 + 1) % b);
+                table.insert(key, x1);
+                table.insert(key, (x1 + 2) % b);
+            });
+            let found = lookup_x0_x1(&zp, &table, &g, &h, b, 0..b, &Progress::default());
+            assert_eq!(found.map(|(x0, x1)| find_x(x0, x1, b)), Some(x));
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use num_bigint::BigUint;
+
+// An element aR mod p in Montgomery form, as little-endian 64-bit limbs
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Residue(pub(crate) Box<[u64]>);
+
+// Montgomery arithmetic modulo a fixed odd p of n 64-bit limbs. Elements
+// are kept as aR mod p with R = 2^(64n), so that a product is reduced with
+// word multiplies and shifts instead of a long division.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Montgomery {
+    modulus: BigUint,
+    p: Box<[u64]>,
+    // -p^(-1) mod 2^64
+    p_inv: u64,
+    // R^2 mod p, to convert into Montgomery form
+    r2: Residue,
+    // R mod p, i.e. 1 in Montgomery form
+    one: Residue,
+}
+
+fn to_limbs(n: &BigUint, len: usize) -> Box<[u64]> {
+    let bytes = n.to_bytes_le();
+    let mut limbs = vec![0u64; len];
+    for (i, chunk) in bytes.chunks(8).enumerate() {
+        let mut buf = [0u8; 8];
+        buf[..chunk.len()].copy_from_slice(chunk);
+        limbs[i] = u64::from_le_bytes(buf);
+    }
+    limbs.into_boxed_slice()
+}
+
+// a + b * c + carry, as (low, high) words
+fn mac(a: u64, b: u64, c: u64, carry: u64) -> (u64, u64) {
+    let t = u128::from(a) + u128::from(b) * u128::from(c) + u128::from(carry);
+    (t as u64, (t >> 64) as u64)
+}
+
+impl Montgomery {
+    pub fn new(p: &BigUint) -> Montgomery {
+        assert!(p.to_bytes_le()[0] & 1 == 1, "Montgomery arithmetic needs an odd modulus");
+        let n = p.bits().div_ceil(64);
+        let limbs = to_limbs(p, n);
+
+        // Newton's iteration doubles the correct low bits of p^(-1) each step
+        let mut inv = 1u64;
+        for _ in 0..6 {
+            inv = inv.wrapping_mul(2u64.wrapping_sub(limbs[0].wrapping_mul(inv)));
+        }
+
+        let r = BigUint::from(1u32) << (64 * n);
+        let r2 = Residue(to_limbs(&(&r * &r % p), n));
+        let one = Residue(to_limbs(&(r % p), n));
+        Montgomery { modulus: p.clone(), p: limbs, p_inv: inv.wrapping_neg(), r2, one }
+    }
+
+    pub fn limbs(&self) -> &[u64] {
+        &self.p
+    }
+
+    pub fn p_inv(&self) -> u64 {
+        self.p_inv
+    }
+
+    pub fn one(&self) -> Residue {
+        self.one.clone()
+    }
+
+    pub fn residue(&self, a: &BigUint) -> Residue {
+        let reduced = to_limbs(&(a % &self.modulus), self.p.len());
+        self.mul(&Residue(reduced), &self.r2)
+    }
+
+    pub fn value(&self, a: &Residue) -> BigUint {
+        let mut one = vec![0u64; self.p.len()];
+        one[0] = 1;
+        let plain = self.mul(a, &Residue(one.into_boxed_slice()));
+        let bytes: Vec<u8> = plain.0.iter().flat_map(|limb| limb.to_le_bytes()).collect();
+        BigUint::from_bytes_le(&bytes)
+    }
+
+    // Low word of the Montgomery form, for hashing
+    pub fn low_u64(&self, a: &Residue) -> u64 {
+        a.0[0]
+    }
+
+    // abR^(-1) mod p by coarsely integrated operand scanning (CIOS)
+    pub fn mul(&self, a: &Residue, b: &Residue) -> Residue {
+        let (a, b, p) = (&a.0, &b.0, &self.p);
+        let n = p.len();
+        let mut t = vec![0u64; n + 2];
+
+        for &b_i in b.iter() {
+            // t += a * b_i
+            let mut carry = 0;
+            for j in 0..n {
+                let (lo, hi) = mac(t[j], a[j], b_i, carry);
+                t[j] = lo;
+                carry = hi;
+            }
+            let (sum, overflow) = t[n].overflowing_add(carry);
+            t[n] = sum;
+            t[n + 1] = u64::from(overflow);
+
+            // t = (t + m * p) / 2^64, with m chosen to clear the low word
+            let m = t[0].wrapping_mul(self.p_inv);
+            let (_, mut carry) = mac(t[0], m, p[0], 0);
+            for j in 1..n {
+                let (lo, hi) = mac(t[j], m, p[j], carry);
+                t[j - 1] = lo;
+                carry = hi;
+            }
+            let (sum, overflow) = t[n].overflowing_add(carry);
+            t[n - 1] = sum;
+            t[n] = t[n + 1] + u64::from(overflow);
+        }
+
+        // t < 2p, so at most one subtraction brings it below p
+        if t[n] != 0 || !less_than(&t[..n], p) {
+            let mut borrow = false;
+            for j in 0..n {
+                let (diff, b1) = t[j].overflowing_sub(p[j]);
+                let (diff, b2) = diff.overflowing_sub(u64::from(borrow));
+                t[j] = diff;
+                borrow = b1 || b2;
+            }
+        }
+        t.truncate(n);
+        Residue(t.into_boxed_slice())
+    }
+
+    // Left-to-right square-and-multiply
+    pub fn pow(&self, a: &Residue, e: &BigUint) -> Residue {
+        let mut result = self.one();
+        for byte in e.to_bytes_be() {
+            for i in (0..8).rev() {
+                result = self.mul(&result, &result);
+                if (byte >> i) & 1 == 1 {
+                    result = self.mul(&result, a);
+                }
+            }
+        }
+        result
+    }
+}
+
+fn less_than(a: &[u64], b: &[u64]) -> bool {
+    for (x, y) in a.iter().rev().zip(b.iter().rev()) {
+        if x != y {
+            return x < y;
+        }
+    }
+    false
+}
@@ -0,0 +1,111 @@
+use num_bigint::BigUint;
+use rand::Rng;
+
+// Candidates divisible by one of these are rejected before Miller-Rabin
+const SMALL_PRIMES: [u32; 24] = [3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61,
+                                 67, 71, 73, 79, 83, 89, 97];
+
+// A uniformly random integer in [0, n)
+pub fn random_below<R: Rng>(rng: &mut R, n: &BigUint) -> BigUint {
+    let mut bytes = vec![0u8; n.bits().div_ceil(8)];
+    loop {
+        rng.fill(&mut bytes[..]);
+        // Mask the top byte so that each draw succeeds with probability >= 1/2
+        bytes[0] &= 0xff >> (bytes.len() * 8 - n.bits());
+        let x = BigUint::from_bytes_be(&bytes);
+        if &x < n {
+            return x;
+        }
+    }
+}
+
+// A random integer of exactly the given number of bits
+fn random_bits<R: Rng>(rng: &mut R, bits: usize) -> BigUint {
+    let top = BigUint::from(1u32) << (bits - 1);
+    random_below(rng, &top) + top
+}
+
+// Divisible by a small prime other than itself
+fn has_small_factor(n: &BigUint) -> bool {
+    let zero = BigUint::from(0u32);
+    SMALL_PRIMES.iter().any(|&q| n % q == zero && *n != BigUint::from(q))
+}
+
+// Miller-Rabin with the given number of random bases; a composite n passes
+// with probability at most 4^(-rounds)
+pub fn is_probable_prime<R: Rng>(n: &BigUint, rounds: u32, rng: &mut R) -> bool {
+    let one = BigUint::from(1u32);
+    let two = BigUint::from(2u32);
+    if *n < two {
+        return false;
+    }
+    if *n == two || *n == BigUint::from(3u32) {
+        return true;
+    }
+    if (n % &two) == BigUint::from(0u32) || has_small_factor(n) {
+        return false;
+    }
+
+    // n - 1 = d * 2^s with d odd
+    let n_minus_one = n - &one;
+    let mut d = n_minus_one.clone();
+    let mut s = 0;
+    while (&d % &two) == BigUint::from(0u32) {
+        d >>= 1;
+        s += 1;
+    }
+
+    'witness: for _ in 0..rounds {
+        // a in [2, n - 2]
+        let a = random_below(rng, &(n - 3u32)) + &two;
+        let mut x = a.modpow(&d, n);
+        if x == one || x == n_minus_one {
+            continue;
+        }
+        for _ in 1..s {
+            x = x.modpow(&two, n);
+            if x == n_minus_one {
+                continue 'witness;
+            }
+        }
+        return false;
+    }
+    true
+}
+
+// A random safe prime p = 2q + 1 of the given number of bits (at least 3),
+// with both p and q passing the given number of Miller-Rabin rounds
+pub fn safe_prime<R: Rng>(bits: usize, rounds: u32, rng: &mut R) -> BigUint {
+    assert!(bits >= 3, "safe primes have at least 3 bits");
+    let one = BigUint::from(1u32);
+    loop {
+        let q = random_bits(rng, bits - 1) | &one;
+        let p = (&q << 1) + &one;
+        if has_small_factor(&q) || has_small_factor(&p) {
+            continue;
+        }
+        // A base-2 Fermat test on p rejects most composites cheaply
+        if p.bits() > 2 && BigUint::from(2u32).modpow(&(&p - &one), &p) != one {
+            continue;
+        }
+        if is_probable_prime(&q, rounds, rng) && is_probable_prime(&p, rounds, rng) {
+            return p;
+        }
+    }
+}
+
+// The smallest g >= 2 generating the subgroup of prime order q = (p - 1)/2
+// of a safe prime p, i.e. the quadratic residues. It always exists, since
+// 4 is one.
+pub fn subgroup_generator(p: &BigUint) -> BigUint {
+    let one = BigUint::from(1u32);
+    let q = (p - &one) >> 1;
+    let mut g = BigUint::from(2u32);
+    loop {
+        // g != 1 and g^q = 1 means g has order exactly q
+        if g.modpow(&q, p) == one {
+            return g;
+        }
+        g += &one;
+    }
+}
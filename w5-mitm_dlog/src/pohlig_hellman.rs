@@ -0,0 +1,141 @@
+use std::io;
+
+use crypto_utils::modular::crt;
+use num_bigint::BigUint;
+
+use crate::group::Group;
+use crate::{DiscreteLogSolver, Instance};
+
+// Trial division only looks for prime factors below this limit; a leftover
+// cofactor below its square must itself be prime
+const TRIAL_DIVISION_LIMIT: u64 = 1 << 20;
+
+// Splits n into its prime factors below the trial division limit and the
+// remaining cofactor, all of whose prime factors are above it
+pub fn smooth_part(n: &BigUint) -> (Vec<(u64, u32)>, BigUint) {
+    let zero = BigUint::from(0u32);
+    let one = BigUint::from(1u32);
+    let mut factors = Vec::new();
+    let mut rest = n.clone();
+
+    let mut q = 2u64;
+    while q < TRIAL_DIVISION_LIMIT && rest > one {
+        let big_q = BigUint::from(q);
+        let mut e = 0;
+        while &rest % &big_q == zero {
+            rest /= &big_q;
+            e += 1;
+        }
+        if e > 0 {
+            factors.push((q, e));
+        }
+        q += if q == 2 { 1 } else { 2 };
+    }
+    (factors, rest)
+}
+
+// Factors n by trial division. On failure returns the part of n that
+// could not be factored.
+pub fn factor(n: &BigUint) -> Result<Vec<(u64, u32)>, BigUint> {
+    let one = BigUint::from(1u32);
+    let (mut factors, rest) = smooth_part(n);
+
+    if rest == one {
+        return Ok(factors);
+    }
+    let limit = BigUint::from(TRIAL_DIVISION_LIMIT);
+    if rest < &limit * &limit {
+        // rest < 2^40, so it fits in a u64
+        let bytes = rest.to_bytes_le();
+        let mut buf = [0u8; 8];
+        buf[..bytes.len()].copy_from_slice(&bytes);
+        factors.push((u64::from_le_bytes(buf), 1));
+        return Ok(factors);
+    }
+    Err(rest)
+}
+
+// Pohlig-Hellman: given the factorization of an order n of g, solves for
+// x mod q^e for each prime power q^e dividing n and recombines with the
+// CRT. The base-q digits of x mod q^e are found one at a time as dlogs in
+// the subgroup of order q, using any other solver as the backend.
+pub struct PohligHellman<G: Group> {
+    pub factors: Vec<(u64, u32)>,
+    pub subgroup: Box<dyn DiscreteLogSolver<G>>,
+}
+
+impl<G: Group> PohligHellman<G> {
+    pub fn new(factors: Vec<(u64, u32)>, subgroup: Box<dyn DiscreteLogSolver<G>>) -> PohligHellman<G> {
+        PohligHellman { factors, subgroup }
+    }
+}
+
+impl<G: Group + Clone> DiscreteLogSolver<G> for PohligHellman<G> {
+    fn name(&self) -> &'static str {
+        "pohlig-hellman"
+    }
+
+    fn find(&self, instance: &Instance<G>) -> io::Result<Option<BigUint>> {
+        let (group, g, h) = (&instance.group, &instance.g, &instance.h);
+        let one = BigUint::from(1u32);
+        let identity = group.identity();
+        let g_inverse = group.inverse(g);
+
+        let mut n = self.factors.iter()
+            .flat_map(|&(q, e)| (0..e).map(move |_| q))
+            .fold(one.clone(), |acc, q| acc * BigUint::from(q));
+        if group.exp(g, &n) != identity {
+            return Ok(None);
+        }
+
+        // Reduce n to the exact order of g so that every subgroup is nontrivial
+        let mut exact_factors = Vec::with_capacity(self.factors.len());
+        for &(q, mut e) in &self.factors {
+            let big_q = BigUint::from(q);
+            while e > 0 && group.exp(g, &(&n / &big_q)) == identity {
+                n /= &big_q;
+                e -= 1;
+            }
+            if e > 0 {
+                exact_factors.push((q, e));
+            }
+        }
+
+        let mut congruences = Vec::with_capacity(exact_factors.len());
+        for (q, e) in exact_factors {
+            let big_q = BigUint::from(q);
+            let gamma = group.exp(g, &(&n / &big_q));
+
+            let mut x_q = BigUint::from(0u32);
+            let mut q_k = one.clone();
+            for _ in 0..e {
+                // Strip the digits found so far and project into the subgroup
+                let stripped = group.op(h, &group.exp(&g_inverse, &x_q));
+                let h_k = group.exp(&stripped, &(&n / (&q_k * &big_q)));
+                let subinstance = Instance {
+                    group: group.clone(),
+                    g: gamma.clone(),
+                    h: h_k,
+                    bound: q,
+                    order: Some(big_q.clone()),
+                };
+                let d = match self.subgroup.find(&subinstance)? {
+                    Some(d) => d,
+                    None => return Ok(None),
+                };
+
+                x_q += &q_k * d;
+                q_k *= &big_q;
+            }
+
+            congruences.push((x_q, q_k));
+        }
+
+        // Every congruence can be satisfied even if h is not a power of g
+        let x = match crt(&congruences) {
+            Some((x, _)) if instance.is_solution(&x) => x,
+            _ => return Ok(None),
+        };
+        Ok(Some(x))
+    }
+}
@@ -0,0 +1,310 @@
+#[cfg(target_os = "linux")]
+use std::fs;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use num_bigint::BigUint;
+use tracing::{debug, info, Level};
+
+use crate::group::Group;
+
+// Steps between checks of the clock (a power of two), to keep tick() cheap
+// in hot loops
+const CHECK_EVERY: u64 = 1 << 12;
+const REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug)]
+struct Phase {
+    label: &'static str,
+    // Expected number of steps, for the percentage and ETA
+    total: u64,
+    start: Instant,
+    last_report: Instant,
+    // Counters when the phase started
+    counts: [u64; 4],
+}
+
+// Operation counts shared by a Progress and the Counted group of its run
+#[derive(Debug, Default)]
+pub struct Counters {
+    pub ops: AtomicU64,
+    pub exps: AtomicU64,
+    pub inserts: AtomicU64,
+    pub lookups: AtomicU64,
+}
+
+impl Counters {
+    fn snapshot(&self) -> [u64; 4] {
+        [&self.ops, &self.exps, &self.inserts, &self.lookups]
+            .map(|counter| counter.load(Ordering::Relaxed))
+    }
+}
+
+// What happened during one phase of a run, over all the times it was
+// entered
+#[derive(Debug, Clone, Default)]
+pub struct PhaseStats {
+    pub label: &'static str,
+    pub ops: u64,
+    pub exps: u64,
+    pub inserts: u64,
+    pub lookups: u64,
+    // Peak resident memory of the process during the phase, where known
+    pub peak_memory: Option<u64>,
+    pub elapsed: Duration,
+}
+
+// Periodic progress lines on stderr for long runs. A solver announces each
+// phase with its expected number of steps and ticks once per step; the
+// default reporter is disabled and does nothing. With stats enabled it
+// also records the counters, wall time and peak memory of every phase, and
+// with debug logging it logs the steps and rate of each phase as it ends.
+#[derive(Debug)]
+pub struct Progress {
+    enabled: bool,
+    stats: bool,
+    debug: bool,
+    start: Instant,
+    done: AtomicU64,
+    phase: Mutex<Phase>,
+    counters: Arc<Counters>,
+    records: Mutex<Vec<PhaseStats>>,
+}
+
+// The process's peak resident set size since the last reset
+#[cfg(target_os = "linux")]
+fn peak_memory() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+    let kb: u64 = line.trim_start_matches("VmHWM:").trim().trim_end_matches("kB").trim()
+        .parse().ok()?;
+    Some(kb * 1024)
+}
+
+#[cfg(target_os = "linux")]
+fn reset_peak_memory() {
+    // Writing 5 resets the peak to the current resident set size
+    let _ = fs::write("/proc/self/clear_refs", "5");
+}
+
+#[cfg(not(target_os = "linux"))]
+fn peak_memory() -> Option<u64> {
+    None
+}
+
+#[cfg(not(target_os = "linux"))]
+fn reset_peak_memory() {}
+
+impl Default for Progress {
+    fn default() -> Progress {
+        Progress::new(false)
+    }
+}
+
+impl Progress {
+    pub fn new(enabled: bool) -> Progress {
+        let now = Instant::now();
+        Progress {
+            enabled,
+            stats: false,
+            debug: tracing::enabled!(Level::DEBUG),
+            start: now,
+            done: AtomicU64::new(0),
+            // Anything before the solver's first phase
+            phase: Mutex::new(Phase {
+                label: "setup",
+                total: 0,
+                start: now,
+                last_report: now,
+                counts: [0; 4],
+            }),
+            counters: Arc::default(),
+            records: Mutex::default(),
+        }
+    }
+
+    pub fn with_stats(self, stats: bool) -> Progress {
+        if stats {
+            reset_peak_memory();
+        }
+        Progress { stats, ..self }
+    }
+
+    // For a Counted group to report its operations to
+    pub fn counters(&self) -> Arc<Counters> {
+        self.counters.clone()
+    }
+
+    pub fn phase(&self, label: &'static str, total: u64) {
+        if !self.enabled && !self.stats && !self.debug {
+            return;
+        }
+        let now = Instant::now();
+        let mut phase = self.phase.lock().unwrap();
+        if self.stats {
+            self.record(&phase, now);
+            reset_peak_memory();
+        }
+        self.log_end(&phase, now);
+        debug!(phase = label, steps = total, "Phase started");
+        let counts = self.counters.snapshot();
+        *phase = Phase { label, total, start: now, last_report: now, counts };
+        self.done.store(0, Ordering::Relaxed);
+    }
+
+    // Adds the phase that is ending to the stats of its label
+    fn record(&self, phase: &Phase, now: Instant) {
+        let counts = self.counters.snapshot();
+        let mut records = self.records.lock().unwrap();
+        let i = match records.iter().position(|record| record.label == phase.label) {
+            Some(i) => i,
+            None => {
+                records.push(PhaseStats { label: phase.label, ..PhaseStats::default() });
+                records.len() - 1
+            },
+        };
+        let record = &mut records[i];
+        record.ops += counts[0] - phase.counts[0];
+        record.exps += counts[1] - phase.counts[1];
+        record.inserts += counts[2] - phase.counts[2];
+        record.lookups += counts[3] - phase.counts[3];
+        record.peak_memory = record.peak_memory.max(peak_memory());
+        record.elapsed += now - phase.start;
+    }
+
+    fn log_end(&self, phase: &Phase, now: Instant) {
+        if !self.debug || phase.label == "setup" {
+            return;
+        }
+        let elapsed = now - phase.start;
+        let steps = self.done.load(Ordering::Relaxed);
+        debug!(phase = phase.label, steps, elapsed = ?elapsed, "Phase ended at {:.0} steps/s",
+               steps as f64 / elapsed.as_secs_f64());
+    }
+
+    // Ends the current phase and returns the stats of every phase so far
+    pub fn finish(&self) -> Vec<PhaseStats> {
+        if self.stats || self.debug {
+            let now = Instant::now();
+            let mut phase = self.phase.lock().unwrap();
+            if self.stats {
+                self.record(&phase, now);
+            }
+            self.log_end(&phase, now);
+            *phase = Phase { label: "setup", total: 0, start: now, last_report: now,
+                             counts: self.counters.snapshot() };
+        }
+        self.records.lock().unwrap().drain(..).collect()
+    }
+
+    // A table entry was written
+    pub fn insert(&self) {
+        if self.stats {
+            self.counters.inserts.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    // A key was looked up in a table
+    pub fn lookup(&self) {
+        if self.stats {
+            self.counters.lookups.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn tick(&self) {
+        if !self.enabled && !self.debug {
+            return;
+        }
+        let done = self.done.fetch_add(1, Ordering::Relaxed) + 1;
+        if !self.enabled || done & (CHECK_EVERY - 1) != 0 {
+            return;
+        }
+
+        let mut phase = self.phase.lock().unwrap();
+        let now = Instant::now();
+        if now - phase.last_report < REPORT_INTERVAL {
+            return;
+        }
+        phase.last_report = now;
+
+        let elapsed = now - phase.start;
+        let mut line = format!("[{:.1?}] {}: {}", self.start.elapsed(), phase.label, done);
+        if phase.total > 0 {
+            let fraction = done as f64 / phase.total as f64;
+            line += &format!("/{} ({:.1}%)", phase.total, 100.0 * fraction);
+            if fraction < 1.0 {
+                let eta = elapsed.as_secs_f64() * (1.0 - fraction) / fraction;
+                line += &format!(", ETA {:.1?}", Duration::from_secs_f64(eta));
+            }
+        }
+        info!("{}", line);
+    }
+}
+
+// A group that counts the operations performed in it, for the summary at
+// the end of a run. Clones share the counters.
+#[derive(Debug, Clone)]
+pub struct Counted<G> {
+    pub inner: G,
+    counters: Arc<Counters>,
+}
+
+impl<G: Group> Counted<G> {
+    pub fn new(inner: G, counters: Arc<Counters>) -> Counted<G> {
+        Counted { inner, counters }
+    }
+
+    // (group operations and inversions, exponentiations) so far
+    pub fn counts(&self) -> (u64, u64) {
+        let counts = self.counters.snapshot();
+        (counts[0], counts[1])
+    }
+}
+
+impl<G: Group> Group for Counted<G> {
+    type Element = G::Element;
+
+    fn identity(&self) -> G::Element {
+        self.inner.identity()
+    }
+
+    fn op(&self, a: &G::Element, b: &G::Element) -> G::Element {
+        self.counters.ops.fetch_add(1, Ordering::Relaxed);
+        self.inner.op(a, b)
+    }
+
+    fn inverse(&self, a: &G::Element) -> G::Element {
+        self.counters.ops.fetch_add(1, Ordering::Relaxed);
+        self.inner.inverse(a)
+    }
+
+    // Counted as one exponentiation, not as the operations inside it
+    fn exp(&self, a: &G::Element, e: &BigUint) -> G::Element {
+        self.counters.exps.fetch_add(1, Ordering::Relaxed);
+        self.inner.exp(a, e)
+    }
+
+    fn serialize(&self, a: &G::Element) -> Vec<u8> {
+        self.inner.serialize(a)
+    }
+
+    fn order(&self) -> Option<BigUint> {
+        self.inner.order()
+    }
+
+    fn parameters(&self) -> Vec<u8> {
+        self.inner.parameters()
+    }
+
+    fn hash(&self, a: &G::Element) -> u64 {
+        self.inner.hash(a)
+    }
+
+    fn for_each_power_hash<F>(&self, a: &G::Element, step: &G::Element, count: u64, f: F)
+        where F: FnMut(u64, u64)
+    {
+        self.counters.ops.fetch_add(count.saturating_sub(1), Ordering::Relaxed);
+        self.inner.for_each_power_hash(a, step, count, f)
+    }
+}
@@ -0,0 +1,148 @@
+use std::io;
+use std::sync::Arc;
+
+use crypto_utils::modular::{gcd, mod_inverse};
+use num_bigint::BigUint;
+
+use crate::arith::{mix, to_u64};
+use crate::group::Group;
+use crate::progress::Progress;
+use crate::{DiscreteLogSolver, Instance};
+
+// Number of fresh starting points tried before giving up
+const MAX_ATTEMPTS: u64 = 16;
+// Largest gcd(b1 - b2, n) whose candidate solutions are checked one by one
+const MAX_CANDIDATES: u64 = 1 << 16;
+// Walks in tiny groups degenerate (squaring quickly gets stuck at 1),
+// so groups below this order are searched exhaustively instead
+const SMALL_ORDER: u64 = 64;
+
+// A point g^a * h^b of the walk
+#[derive(Debug, Clone)]
+struct Point<E> {
+    x: E,
+    a: BigUint,
+    b: BigUint,
+}
+
+// Pollard's rho: a pseudo-random walk over points g^a * h^b until Floyd's
+// cycle detection finds g^a1 * h^b1 = g^a2 * h^b2, which gives a linear
+// congruence for x modulo the group order. Needs almost no memory but
+// ignores the bound: it takes about sqrt(n) steps for a group of order n.
+#[derive(Debug, Default, Clone)]
+pub struct Rho {
+    pub progress: Arc<Progress>,
+}
+
+struct Walk<'a, G: Group> {
+    instance: &'a Instance<G>,
+    n: BigUint,
+}
+
+impl<'a, G: Group + Clone> Walk<'a, G> {
+    // The classic three-way partition: multiply by h, square, or multiply by g
+    fn step(&self, point: &mut Point<G::Element>) {
+        let group = &self.instance.group;
+        let one = BigUint::from(1u32);
+
+        match group.hash(&point.x) % 3 {
+            0 => {
+                point.x = group.op(&point.x, &self.instance.h);
+                point.b = (&point.b + &one) % &self.n;
+            },
+            1 => {
+                point.x = group.op(&point.x, &point.x);
+                point.a = (&point.a + &point.a) % &self.n;
+                point.b = (&point.b + &point.b) % &self.n;
+            },
+            _ => {
+                point.x = group.op(&point.x, &self.instance.g);
+                point.a = (&point.a + &one) % &self.n;
+            },
+        }
+    }
+
+    fn start(&self, seed: u64) -> Point<G::Element> {
+        let group = &self.instance.group;
+        let a = BigUint::from(mix(2 * seed)) % &self.n;
+        let b = BigUint::from(mix(2 * seed + 1)) % &self.n;
+        let x = group.op(&group.exp(&self.instance.g, &a), &group.exp(&self.instance.h, &b));
+        Point { x, a, b }
+    }
+
+    // Solves (b1 - b2) x = a2 - a1 (mod n) and checks every candidate
+    fn collide(&self, t: &Point<G::Element>, u: &Point<G::Element>) -> Option<BigUint> {
+        let n = &self.n;
+        let r = (&t.b + n - &u.b) % n;
+        let s = (&u.a + n - &t.a) % n;
+
+        let zero = BigUint::from(0u32);
+        if r == zero {
+            return None;
+        }
+        let d = gcd(&r, n);
+        if &s % &d != zero || d > BigUint::from(MAX_CANDIDATES) {
+            return None;
+        }
+
+        let n_d = n / &d;
+        let x0 = (&s / &d) * mod_inverse(&(&r / &d), &n_d)? % &n_d;
+        let mut x = x0;
+        while &x < n {
+            if self.instance.is_solution(&x) {
+                return Some(x);
+            }
+            x += &n_d;
+        }
+        None
+    }
+}
+
+impl Rho {
+    fn search<G: Group + Clone>(&self, instance: &Instance<G>) -> Option<BigUint> {
+        let walk = Walk { instance, n: instance.group_order()? };
+
+        if walk.n < BigUint::from(SMALL_ORDER) {
+            let mut x = BigUint::from(0u32);
+            while x < walk.n {
+                if instance.is_solution(&x) {
+                    return Some(x);
+                }
+                x += 1u32;
+            }
+            return None;
+        }
+
+        // The expected number of steps before the walk cycles is about sqrt(n)
+        let expected = to_u64(&walk.n.sqrt()).unwrap_or(0);
+        for attempt in 0..MAX_ATTEMPTS {
+            let mut tortoise = walk.start(attempt);
+            let mut hare = tortoise.clone();
+            self.progress.phase("walk steps", expected);
+            loop {
+                self.progress.tick();
+                walk.step(&mut tortoise);
+                walk.step(&mut hare);
+                walk.step(&mut hare);
+                if tortoise.x == hare.x {
+                    break;
+                }
+            }
+
+            if let Some(x) = walk.collide(&tortoise, &hare) {
+                return Some(x);
+            }
+        }
+        None
+    }
+}
+
+impl<G: Group + Clone> DiscreteLogSolver<G> for Rho {
+    fn name(&self) -> &'static str {
+        "rho"
+    }
+
+    fn find(&self, instance: &Instance<G>) -> io::Result<Option<BigUint>> {
+        Ok(self.search(instance))
+    }
+}
@@ -0,0 +1,67 @@
+use std::io;
+
+use num_bigint::BigUint;
+
+use crate::arith::to_u64;
+use crate::group::Group;
+use crate::{DiscreteLogSolver, Instance};
+
+// What one element of small order reveals: the peer's secret is congruent
+// to residue modulo q^e
+#[derive(Debug, Clone)]
+pub struct Leak<E> {
+    pub q: u64,
+    pub e: u32,
+    pub sent: E,
+    pub residue: BigUint,
+}
+
+impl<E> Leak<E> {
+    pub fn modulus(&self) -> BigUint {
+        (0..self.e).fold(BigUint::from(1u32), |acc, _| acc * BigUint::from(self.q))
+    }
+}
+
+// The small-subgroup confinement attack (Lim and Lee). A peer that raises
+// every element it receives to a static secret b, without checking that
+// the element lies in the intended subgroup, reveals b mod the order of
+// that element. For each prime power q^e of the order n of g, up to
+// max_order, the attacker sends the element g^(n/q^e) of order q^e and
+// solves the peer's reply for b mod q^e. The leaks combine with the CRT.
+pub fn confine<G, F>(group: &G, g: &G::Element, n: &BigUint, factors: &[(u64, u32)],
+                     max_order: u64, solver: &dyn DiscreteLogSolver<G>,
+                     mut peer: F) -> io::Result<Vec<Leak<G::Element>>>
+    where G: Group + Clone,
+          F: FnMut(&G::Element) -> G::Element
+{
+    let identity = group.identity();
+    let mut leaks = Vec::new();
+
+    for &(q, e) in factors {
+        let big_q = BigUint::from(q);
+        let mut leak = Leak { q, e, sent: identity.clone(), residue: BigUint::from(0u32) };
+        leak.sent = group.exp(g, &(n / leak.modulus()));
+
+        // If g doesn't generate the whole group, its order has a smaller
+        // q-part than n
+        while leak.e > 0 && group.exp(&leak.sent, &(leak.modulus() / &big_q)) == identity {
+            leak.e -= 1;
+        }
+        let order = match to_u64(&leak.modulus()) {
+            Some(order) if leak.e > 0 && order <= max_order => order,
+            _ => continue,
+        };
+
+        let instance = Instance {
+            group: group.clone(),
+            g: leak.sent.clone(),
+            h: peer(&leak.sent),
+            bound: order,
+            order: Some(leak.modulus()),
+        };
+        if let Some(residue) = solver.find(&instance)? {
+            leaks.push(Leak { residue, ..leak });
+        }
+    }
+    Ok(leaks)
+}
@@ -0,0 +1,88 @@
+use num_bigint::BigUint;
+use rand::Rng;
+use thiserror::Error;
+
+use crate::params::is_probable_prime;
+use crate::pohlig_hellman::smooth_part;
+
+// Miller-Rabin rounds for the primality check of p
+const PRIME_ROUNDS: u32 = 16;
+
+// Why a dlog instance over Zp* can't be solved as given
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum Invalid {
+    #[error("p = {0} is not prime")]
+    NotPrime(BigUint),
+    // An element outside [2, p - 1]
+    #[error("{0} = {1} is not in [2, p - 1]")]
+    OutOfRange(&'static str, BigUint),
+    // The claimed order n of g, with g^n != 1
+    #[error("g^{0} != 1, so {0} is not a multiple of the order of g")]
+    WrongOrder(BigUint),
+    // The exact order of g, below the width of the range
+    #[error("g only generates a subgroup of order {0}, smaller than the {1} values in the range, \
+             so x would only be found mod {0}; narrow --range")]
+    SmallOrder(BigUint, u64),
+    // The exact order of g, with h^order != 1
+    #[error("h is not a power of g: g has order {0} but h^{0} != 1")]
+    NotInSubgroup(BigUint),
+}
+
+// The exact order of g mod p if it only has prime factors below the trial
+// division limit, given a multiple n of it
+fn smooth_order(p: &BigUint, g: &BigUint, n: &BigUint) -> Option<BigUint> {
+    let one = BigUint::from(1u32);
+    let (factors, rest) = smooth_part(n);
+    let mut order = n / &rest;
+    if g.modpow(&order, p) != one {
+        return None;
+    }
+    for (q, e) in factors {
+        let q = BigUint::from(q);
+        for _ in 0..e {
+            if g.modpow(&(&order / &q), p) != one {
+                break;
+            }
+            order /= &q;
+        }
+    }
+    Some(order)
+}
+
+// Checks that p is prime, that g and h (if given) lie in [2, p - 1], and
+// that the order of g, or the multiple n of it given as order (default
+// p - 1), is consistent with them. The exact order of g is only known when
+// it is smooth enough to find by trial division, and then it must cover
+// the width of the range and h must lie in the subgroup it generates.
+pub fn check_zp<R: Rng>(p: &BigUint, g: &BigUint, h: Option<&BigUint>, order: Option<&BigUint>,
+                        width: u64, rng: &mut R) -> Result<(), Invalid> {
+    let one = BigUint::from(1u32);
+    let two = BigUint::from(2u32);
+    if !is_probable_prime(p, PRIME_ROUNDS, rng) {
+        return Err(Invalid::NotPrime(p.clone()));
+    }
+    let p_minus_one = p - &one;
+    for (name, value) in [("g", Some(g)), ("h", h)] {
+        if let Some(value) = value {
+            if *value < two || *value > p_minus_one {
+                return Err(Invalid::OutOfRange(name, value.clone()));
+            }
+        }
+    }
+
+    let n = order.unwrap_or(&p_minus_one);
+    if g.modpow(n, p) != one {
+        return Err(Invalid::WrongOrder(n.clone()));
+    }
+    if let Some(order) = smooth_order(p, g, n) {
+        if order < BigUint::from(width) {
+            return Err(Invalid::SmallOrder(order, width));
+        }
+        if let Some(h) = h {
+            if h.modpow(&order, p) != one {
+                return Err(Invalid::NotInSubgroup(order));
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,384 @@
+use std::time::Instant;
+
+use clap::{Parser, Subcommand};
+use crypto_utils::log::{self, LogArgs};
+use crypto_utils::oracle::{CommandOracle, Oracle, Parity};
+use crypto_utils::output::{self, json, OutputArgs};
+use crypto_utils::text;
+use num_bigint::BigUint;
+use tracing::{debug, info};
+
+use crate::error::Error;
+use crate::fermat::{fermat, Factors};
+use crate::parity::{self, LocalParityOracle};
+use crate::{hastad, rsa};
+
+// Factoring challenge #1: |p - q| < 2 N^(1/4)
+const N1: &[u8] = b"1797693134862315907729305190789024733617\
+                    9769789423065727343008115773267580550562\
+                    0686985379449212982959585501387537164015\
+                    7101398586478337786069255834975410851965\
+                    9161512805757594075263500747593528871082\
+                    3649949940771895617054361149474865046711\
+                    0151015639406805275400715845608785776637\
+                    43040086340742855278549092581";
+
+// Factoring challenge #2: |p - q| < 2^11 N^(1/4)
+const N2: &[u8] = b"6484558428080716696628242653467722787263\
+                    4372070697626306043907037879730861808111\
+                    6462714015276061417569195587321840254520\
+                    6554249067198924288448418393532819729885\
+                    3131051173864896596258282150250499026445\
+                    2100885281673303711142296421027840289307\
+                    6574586452336833570778346897158386460882\
+                    39640236866252211790085787877";
+
+// Factoring challenge #3: |3p - 2q| < N^(1/4)
+const N3: &[u8] = b"7200622637473504252795644355255837383380\
+                    8445147399984182665305798191635569018833\
+                    7790423408664187663938485175264994017897\
+                    0835240791356868774411551320151882793318\
+                    1230909199624636189683657364311917409496\
+                    1348524639707885238799396839230364676670\
+                    2216270183532994432411921738127292761475\
+                    30748597302192751375739387929";
+
+// Challenge #4: encrypted with PKCS#1 v1.5 under e = 65537 and N1
+const C4: &[u8] = b"2209645186741038177630656113488341801741\
+                    0069787892831071731839143676135600120538\
+                    0042823296504735094243439462197515122564\
+                    6583996794288946076454204058156474898801\
+                    3734864120452325229320176487916666402997\
+                    5091887299716905260832220677716000193292\
+                    6087000957999372407745896777369781757126\
+                    7229951148662959627934791540";
+
+// Three 1024-bit RSA moduli for e = 3, for the broadcast attack demo
+const BROADCAST_MODULI: [&[u8]; 3] = [
+    b"1089205376804747118825085151367786225645\
+     5539104329323305366384841514975549274470\
+     1780082922597487943623759317376579352659\
+     3950771282379177126204322685660060115578\
+     3818261343014856161329086828456088140604\
+     6166506101563602807941803332852967516891\
+     5923017439293904888322771347412680620744\
+     48911659102247053627909208379",
+    b"1332177127128348152755308813608525800000\
+     0177066176707543194208360988816962140828\
+     3766808119757753066154828123498419264338\
+     4951091784815087900011002377021800128035\
+     6719134768568776258216007546942466620083\
+     5153157174980390868751204837858378351264\
+     2551640226457583563538106383044463024018\
+     95936041620128499696035613527",
+    b"1377527653087383433529958092359626881330\
+     4314159808427573160200172469476377733899\
+     7978819687343842817926764529619177579962\
+     5820891619907729284173945409889317289421\
+     9847978747025170233988587480394049559718\
+     0458058190909185422262146269185424872854\
+     7944113608899801724478719145613842618691\
+     32093179384886351000831184227",
+];
+
+const DEFAULT_STEPS: u64 = 1 << 21;
+
+const DEFAULT_EXPONENT: u32 = 65_537;
+
+const DEFAULT_MESSAGE: &str = "One bit at a time is all it takes";
+
+const DEFAULT_BROADCAST: &str = "Same message, three recipients, no padding";
+
+// Queries between progress reports of the parity attack
+const REPORT_EVERY: usize = 128;
+
+fn parse_decimal(val: &str) -> Result<BigUint, String> {
+    BigUint::parse_bytes(val.trim().as_bytes(), 10).ok_or_else(|| "not a decimal number".to_string())
+}
+
+// Decrypts c with the private key recovered from the factors of n
+fn decrypt(n: &BigUint, factors: &Factors, e: &BigUint, c: &BigUint) -> Result<Vec<u8>, Error> {
+    let d = rsa::private_exponent(e, &factors.p, &factors.q)
+        .ok_or_else(|| Error::NotInvertible(e.clone()))?;
+    rsa::pkcs1_v15_unpad(&c.modpow(&d, n), rsa::modulus_len(n))
+}
+
+fn run_assignment() {
+    let challenges = [(N1, (1, 1)), (N2, (1, 1)), (N3, (3, 2))];
+    let mut first = None;
+    let mut primes = Vec::new();
+    for (i, &(n, ratio)) in challenges.iter().enumerate() {
+        let n = BigUint::parse_bytes(n, 10).unwrap();
+        match fermat(&n, ratio, DEFAULT_STEPS) {
+            Some(factors) => {
+                text!("{}. Prime p: {} (step {})", i + 1, factors.p, factors.steps);
+                primes.push(json!({ "p": factors.p.to_string(), "step": factors.steps }));
+                first.get_or_insert(factors);
+            },
+            None => {
+                text!("{}. Not factored in {} steps", i + 1, DEFAULT_STEPS);
+                primes.push(json!(null));
+            },
+        }
+    }
+    output::result("factored", primes);
+
+    if let Some(factors) = first {
+        let (n, c) = (BigUint::parse_bytes(N1, 10).unwrap(), BigUint::parse_bytes(C4, 10).unwrap());
+        match decrypt(&n, &factors, &BigUint::from(DEFAULT_EXPONENT), &c) {
+            Ok(msg) => {
+                text!("4. Plaintext: {:?}", String::from_utf8_lossy(&msg));
+                output::result("plaintext", String::from_utf8_lossy(&msg));
+            },
+            Err(e) => {
+                text!("4. {}", e);
+                output::result("decryption_error", e.to_string());
+            },
+        }
+    }
+}
+
+fn parse_ratio(val: &str) -> Result<(u32, u32), String> {
+    let mut parts = val.split(':').map(|x| x.trim().parse().ok());
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(Some(a)), Some(Some(b)), None) if a > 0 && b > 0 => Ok((a, b)),
+        _ => Err("must be A:B with positive A and B".to_string()),
+    }
+}
+
+// The bytes of m within the bounds [lo, hi] on it, without the leading
+// zeros of hi, with _ for those that still differ and . for non-printable
+// ones
+fn show_bounds(lo: &BigUint, hi: &BigUint, k: usize) -> String {
+    let (lo, hi) = (rsa::i2osp(lo, k), rsa::i2osp(hi, k));
+    let start = hi.iter().position(|&b| b != 0).unwrap_or(k);
+    let known = lo.iter().zip(&hi).take_while(|(a, b)| a == b).count();
+    (start..k)
+        .map(|i| match hi[i] {
+            _ if i >= known => '_',
+            b if b.is_ascii_graphic() || b == b' ' => b as char,
+            _ => '.',
+        })
+        .collect()
+}
+
+// Attacks a parity oracle: a program run with --command, for --modulus and
+// --ciphertext, or else one simulated with the key of challenge 1, for a
+// ciphertext of --message
+fn parity(args: ParityArgs) -> Result<(), Error> {
+    let e = args.exponent;
+    output::parameter("exponent", e.to_string());
+    let (oracle, n, c): (Box<dyn Oracle<Response = Parity>>, BigUint, BigUint) =
+        match (args.command, args.modulus, args.ciphertext) {
+            (Some(command), Some(n), Some(c)) => {
+                output::parameter("command", command.as_str());
+                let oracle = CommandOracle::new(&command).ok_or(Error::EmptyCommand)?;
+                (Box::new(oracle), n, c)
+            },
+            _ => {
+                output::parameter("message", args.message.as_str());
+                let n = BigUint::parse_bytes(N1, 10).unwrap();
+                let factors = fermat(&n, (1, 1), DEFAULT_STEPS).unwrap();
+                let d = rsa::private_exponent(&e, &factors.p, &factors.q)
+                    .ok_or_else(|| Error::NotInvertible(e.clone()))?;
+                let m = BigUint::from_bytes_be(args.message.as_bytes());
+                if m >= n {
+                    return Err(Error::MessageTooLong("N"));
+                }
+                let c = m.modpow(&e, &n);
+                (Box::new(LocalParityOracle::new(&n, &d)), n, c)
+            },
+        };
+
+    output::parameter("modulus", n.to_string());
+    output::parameter("ciphertext", c.to_string());
+    let (bits, k) = (n.bits(), rsa::modulus_len(&n));
+    info!("Attacking a parity oracle for a {}-bit N, one query per bit", bits);
+    let start = Instant::now();
+    let m = parity::parity_attack(&oracle, &n, &e, &c, |i, lo, hi| {
+        if i % REPORT_EVERY == 0 || i == bits {
+            text!("{}/{} [{}]", i, bits, show_bounds(lo, hi, k));
+        }
+    });
+    let elapsed = start.elapsed();
+    debug!(elapsed = ?elapsed, "{:.0} queries/s", bits as f64 / elapsed.as_secs_f64());
+    output::timing("attack", elapsed);
+    let m = m?.to_bytes_be();
+    text!("Plaintext: {:?}", String::from_utf8_lossy(&m));
+    output::result("queries", bits);
+    output::result("plaintext", String::from_utf8_lossy(&m));
+    Ok(())
+}
+
+// Combines the ciphertexts of one message under several moduli, given
+// by --modulus and --ciphertext in pairs, or else encrypts --message under
+// the built-in moduli first
+fn broadcast(args: HastadArgs) -> Result<(), Error> {
+    let e = args.exponent;
+    output::parameter("exponent", e);
+    let (moduli, ciphertexts) = (args.modulus, args.ciphertext);
+    let pairs: Vec<(BigUint, BigUint)> = if moduli.is_empty() && ciphertexts.is_empty() {
+        let message = args.message;
+        output::parameter("message", message.as_str());
+        let m = BigUint::from_bytes_be(message.as_bytes());
+        let e = BigUint::from(e);
+        let pairs: Vec<(BigUint, BigUint)> = BROADCAST_MODULI.iter()
+            .map(|n| BigUint::parse_bytes(n, 10).unwrap())
+            .map(|n| {
+                if m >= n {
+                    return Err(Error::MessageTooLong("the moduli"));
+                }
+                let c = m.modpow(&e, &n);
+                Ok((n, c))
+            })
+            .collect::<Result<_, _>>()?;
+        info!("Encrypted {:?} under {} moduli with e = {}", message, pairs.len(), e);
+        pairs
+    } else if moduli.len() == ciphertexts.len() {
+        moduli.into_iter().zip(ciphertexts).collect()
+    } else {
+        return Err(Error::Unpaired);
+    };
+    let pairs_json: Vec<_> = pairs.iter()
+        .map(|(n, c)| json!({ "modulus": n.to_string(), "ciphertext": c.to_string() }))
+        .collect();
+    output::parameter("pairs", pairs_json);
+
+    let m = hastad::hastad(e, &pairs)?;
+    text!("Plaintext: {:?}", String::from_utf8_lossy(&m.to_bytes_be()));
+    output::result("plaintext", String::from_utf8_lossy(&m.to_bytes_be()));
+    Ok(())
+}
+
+// Without --modulus, solves the assignment
+#[derive(Parser)]
+#[command(name = "w6-rsa_problem", about = "Breaks RSA with close primes, a parity oracle or a \
+          broadcast",
+          long_about = "Factors an RSA modulus whose primes are close, with Fermat's method, and \
+          decrypts a PKCS#1 v1.5 ciphertext with the factors. Without --modulus, solves the four \
+          challenges of the assignment. parity decrypts a ciphertext with an oracle for the \
+          parity of decryptions, and hastad a message sent to e recipients under a small \
+          exponent e.",
+          args_conflicts_with_subcommands = true)]
+pub struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[arg(short = 'n', long, value_name = "N", value_parser = parse_decimal,
+          help = "factor the RSA modulus N, in decimal")]
+    modulus: Option<BigUint>,
+    #[arg(short, long, value_name = "A:B", default_value = "1:1", value_parser = parse_ratio,
+          help = "A:B with A*p close to B*q")]
+    ratio: (u32, u32),
+    #[arg(short, long, value_name = "N", default_value_t = DEFAULT_STEPS,
+          help = "candidates to try above sqrt(4ABN)")]
+    steps: u64,
+    #[arg(short, long, value_name = "C", value_parser = parse_decimal, requires = "modulus",
+          help = "then decrypt the PKCS#1 v1.5 ciphertext C, in decimal")]
+    ciphertext: Option<BigUint>,
+    #[arg(short, long, value_name = "E", value_parser = parse_decimal,
+          default_value_t = BigUint::from(DEFAULT_EXPONENT), help = "public exponent")]
+    exponent: BigUint,
+    #[command(flatten)]
+    log: LogArgs,
+    #[command(flatten)]
+    output: OutputArgs,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    #[command(about = "Decrypts a ciphertext with an oracle for the parity of decryptions",
+              long_about = "Decrypts a ciphertext with an oracle for the parity of decryptions: \
+              a program run with --command, for --modulus and --ciphertext, or else one \
+              simulated with the key of challenge 1, for a ciphertext of --message.")]
+    Parity(ParityArgs),
+    #[command(about = "Decrypts a message sent to e recipients under a small exponent e",
+              long_about = "Decrypts a message sent to e recipients under a small exponent e, \
+              from its ciphertexts under each --modulus, or else from its encryption under three \
+              built-in moduli.")]
+    Hastad(HastadArgs),
+}
+
+#[derive(clap::Args)]
+struct ParityArgs {
+    #[arg(long, value_name = "CMD", requires_all = ["modulus", "ciphertext"],
+          help = "parity oracle to attack: CMD is run with a ciphertext in hex as its last \
+          argument and exits with 0 if it decrypts to an even number, 1 if odd")]
+    command: Option<String>,
+    #[arg(short = 'n', long, value_name = "N", value_parser = parse_decimal, requires = "command",
+          help = "the RSA modulus N of the oracle, in decimal")]
+    modulus: Option<BigUint>,
+    #[arg(short, long, value_name = "C", value_parser = parse_decimal, requires = "command",
+          help = "the raw RSA ciphertext to attack, in decimal")]
+    ciphertext: Option<BigUint>,
+    #[arg(short, long, value_name = "E", value_parser = parse_decimal,
+          default_value_t = BigUint::from(DEFAULT_EXPONENT), help = "public exponent")]
+    exponent: BigUint,
+    #[arg(short, long, value_name = "TEXT", default_value = DEFAULT_MESSAGE,
+          conflicts_with = "command", help = "message to encrypt under the key of challenge 1")]
+    message: String,
+}
+
+#[derive(clap::Args)]
+struct HastadArgs {
+    #[arg(short = 'n', long, value_name = "N", value_parser = parse_decimal,
+          help = "a modulus the message was encrypted under, in decimal; repeated")]
+    modulus: Vec<BigUint>,
+    #[arg(short, long, value_name = "C", value_parser = parse_decimal,
+          help = "the raw RSA ciphertext under each --modulus in turn, in decimal")]
+    ciphertext: Vec<BigUint>,
+    #[arg(short, long, value_name = "E", default_value_t = 3,
+          value_parser = clap::value_parser!(u32).range(1..), help = "public exponent")]
+    exponent: u32,
+    #[arg(short, long, value_name = "TEXT", default_value = DEFAULT_BROADCAST,
+          conflicts_with_all = ["modulus", "ciphertext"],
+          help = "message to encrypt under three built-in moduli")]
+    message: String,
+}
+
+pub fn run(args: Args) -> Result<(), Error> {
+    log::init(&args.log);
+    let operation = match args.command {
+        None => "rsa",
+        Some(Command::Parity(_)) => "rsa parity",
+        Some(Command::Hastad(_)) => "rsa hastad",
+    };
+    output::init(operation, &args.output);
+    output::finish(match args.command {
+        None => factor(args),
+        Some(Command::Parity(args)) => parity(args),
+        Some(Command::Hastad(args)) => broadcast(args),
+    })
+}
+
+// Factors --modulus and decrypts --ciphertext, or solves the assignment
+fn factor(args: Args) -> Result<(), Error> {
+    let n = match args.modulus {
+        Some(n) => n,
+        None => {
+            output::parameter("assignment", true);
+            run_assignment();
+            return Ok(());
+        },
+    };
+    let (ratio, steps) = (args.ratio, args.steps);
+    output::parameter("modulus", n.to_string());
+    output::parameter("ratio", format!("{}:{}", ratio.0, ratio.1));
+    output::parameter("steps", steps);
+    output::parameter("exponent", args.exponent.to_string());
+    output::parameter("ciphertext", args.ciphertext.as_ref().map(ToString::to_string));
+
+    let factors = fermat(&n, ratio, steps).ok_or(Error::NoFactor { steps, ratio })?;
+    text!("p = {}", factors.p);
+    text!("q = {}", factors.q);
+    text!("Found at step {}", factors.steps);
+    output::result("p", factors.p.to_string());
+    output::result("q", factors.q.to_string());
+    output::result("step", factors.steps);
+
+    if let Some(c) = args.ciphertext {
+        let msg = decrypt(&n, &factors, &args.exponent, &c)?;
+        text!("Plaintext: {:?}", String::from_utf8_lossy(&msg));
+        output::result("plaintext", String::from_utf8_lossy(&msg));
+    }
+    Ok(())
+}
@@ -0,0 +1,55 @@
+use crypto_utils::exit::{self, Status};
+use crypto_utils::oracle::OracleError;
+use num_bigint::BigUint;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("No factor found in {steps} steps: |{}p - {}q| may be too large, or try more --steps",
+            coef(ratio.0), coef(ratio.1))]
+    NoFactor { steps: u64, ratio: (u32, u32) },
+    #[error("e = {0} is not invertible mod φ(N)")]
+    NotInvertible(BigUint),
+    #[error("A {0}-byte modulus is too small for PKCS#1 v1.5")]
+    SmallModulus(usize),
+    #[error("Decryption failed: the block doesn't start with 00")]
+    LeadingByte,
+    #[error("Decryption failed: the block type is {0:#04x}, not 0x02")]
+    BlockType(u8),
+    #[error("Decryption failed: the padding string is {0} bytes, less than 8")]
+    ShortPadding(usize),
+    #[error("Decryption failed: no 00 byte ends the padding string")]
+    UnterminatedPadding,
+    #[error("The oracle failed: {0}")]
+    Oracle(#[from] OracleError),
+    #[error("Attack failed: the oracle's answers are inconsistent with the ciphertext")]
+    Inconsistent,
+    #[error("e = {e} needs {e} ciphertexts, not {found}")]
+    TooFewCiphertexts { e: u32, found: usize },
+    #[error("Attack failed: the moduli are not pairwise coprime, so some share a factor")]
+    NotCoprime,
+    #[error("Attack failed: the combined ciphertext is not an exact e-th power for e = {0}: the \
+             messages differ, e.g. by randomized padding")]
+    NotPower(u32),
+    #[error("--command must not be empty")]
+    EmptyCommand,
+    #[error("The message is too long for {0}")]
+    MessageTooLong(&'static str),
+    #[error("Give one --ciphertext for each --modulus")]
+    Unpaired,
+}
+
+fn coef(c: u32) -> String {
+    if c == 1 { String::new() } else { c.to_string() }
+}
+
+impl Status for Error {
+    fn exit_code(&self) -> u8 {
+        match self {
+            Error::Oracle(_) | Error::Inconsistent => exit::ORACLE,
+            Error::NotInvertible(_) | Error::SmallModulus(_) | Error::TooFewCiphertexts { .. }
+            | Error::EmptyCommand | Error::MessageTooLong(_) | Error::Unpaired => exit::USAGE,
+            _ => exit::FAILED,
+        }
+    }
+}
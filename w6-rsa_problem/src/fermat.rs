@@ -0,0 +1,75 @@
+use std::iter;
+use std::time::Instant;
+
+use crypto_utils::roots::{sqrt_ceil, sqrt_exact};
+use num_bigint::BigUint;
+use num_integer::Integer;
+use tracing::debug;
+
+#[derive(Debug, Clone)]
+pub struct Factors {
+    // p <= q
+    pub p: BigUint,
+    pub q: BigUint,
+    // Values of A tried
+    pub steps: u64,
+}
+
+// Fermat's method for N = pq with a*p close to b*q. Then 4abN = (2ap)(2bq)
+// = A^2 - x^2 with A = ap + bq and x = |ap - bq| small, so A lies just
+// above sqrt(4abN): tries that many values of A upwards for one with
+// A^2 - 4abN a square x^2, and then gcd(A - x, N) is a factor.
+pub fn fermat(n: &BigUint, (a, b): (u32, u32), steps: u64) -> Option<Factors> {
+    debug!(bits = n.bits(), a, b, steps, "Searching above sqrt(4abN)");
+    let start = Instant::now();
+    let m = n * (4 * u64::from(a) * u64::from(b));
+    let first = sqrt_ceil(&m);
+    let mut r = &first * &first - &m;
+    let candidates = iter::successors(Some(first), |a| Some(a + 1u32)).take(steps as usize);
+    for (step, big_a) in (1..).zip(candidates) {
+        if let Some(x) = sqrt_exact(&r) {
+            let g = (&big_a - &x).gcd(n);
+            if g > BigUint::from(1u32) && g < *n {
+                let h = n / &g;
+                let (p, q) = if g < h { (g, h) } else { (h, g) };
+                debug!(step, elapsed = ?start.elapsed(), "Factored");
+                return Some(Factors { p, q, steps: step });
+            }
+        }
+        // (A + 1)^2 - 4abN
+        r += big_a * 2u32 + 1u32;
+    }
+    let elapsed = start.elapsed();
+    debug!(elapsed = ?elapsed, "Not factored, at {:.0} steps/s", steps as f64 / elapsed.as_secs_f64());
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use num_traits::ToPrimitive;
+
+    use super::*;
+
+    fn factors(n: u32, ratio: (u32, u32), steps: u64) -> Option<(u32, u32, u64)> {
+        let found = fermat(&BigUint::from(n), ratio, steps)?;
+        Some((found.p.to_u32()?, found.q.to_u32()?, found.steps))
+    }
+
+    #[test]
+    fn factors_close_primes() {
+        // (101 + 103) / 2 is the first A above sqrt(N)
+        assert_eq!(factors(101 * 103, (1, 1), 1), Some((101, 103, 1)));
+        // A = 11 + 1009 is about 800 steps above sqrt(4N)
+        assert_eq!(factors(11 * 1009, (1, 1), 100), None);
+        assert!(matches!(factors(11 * 1009, (1, 1), 1000), Some((11, 1009, _))));
+    }
+
+    #[test]
+    fn factors_with_a_ratio() {
+        // 3 * 673 = 2019 is next to 2 * 1009 = 2018, while 673 + 1009 is 34
+        // steps above sqrt(4N)
+        assert_eq!(factors(673 * 1009, (2, 3), 1), Some((673, 1009, 1)));
+        assert_eq!(factors(673 * 1009, (1, 1), 33), None);
+        assert_eq!(factors(673 * 1009, (1, 1), 34), Some((673, 1009, 34)));
+    }
+}
@@ -0,0 +1,51 @@
+use crypto_utils::modular::crt;
+use crypto_utils::roots;
+use num_bigint::BigUint;
+use tracing::debug;
+
+use crate::error::Error;
+
+// Recovers m from its encryptions ci = m^e mod Ni, given as pairs (Ni, ci),
+// under e pairwise coprime moduli and without padding. The CRT gives m^e
+// mod N1...Ne, and as m < Ni for each i, m^e < N1...Ne is that residue
+// itself, so m is its integer eth root.
+pub fn hastad(e: u32, pairs: &[(BigUint, BigUint)]) -> Result<BigUint, Error> {
+    if pairs.len() < e as usize {
+        return Err(Error::TooFewCiphertexts { e, found: pairs.len() });
+    }
+    let congruences: Vec<(BigUint, BigUint)> = pairs.iter()
+        .take(e as usize)
+        .map(|(n, c)| (c.clone(), n.clone()))
+        .collect();
+    let (x, _) = crt(&congruences).ok_or(Error::NotCoprime)?;
+    debug!(bits = x.bits(), "Combined the ciphertexts into m^{} by the CRT", e);
+    roots::root_exact(&x, e).ok_or(Error::NotPower(e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encrypt(m: u32, moduli: &[u32]) -> Vec<(BigUint, BigUint)> {
+        let (m, e) = (BigUint::from(m), BigUint::from(3u32));
+        moduli.iter().map(|&n| (BigUint::from(n), m.modpow(&e, &BigUint::from(n)))).collect()
+    }
+
+    #[test]
+    fn recovers_m_for_e_3() {
+        // 61 * 53, 47 * 59 and 71 * 73, and a fourth pair that is not needed
+        let pairs = encrypt(2000, &[3233, 2773, 5183, 4087]);
+        assert_eq!(hastad(3, &pairs).unwrap(), BigUint::from(2000u32));
+        assert_eq!(hastad(3, &pairs[..3]).unwrap(), BigUint::from(2000u32));
+    }
+
+    #[test]
+    fn needs_e_coprime_moduli() {
+        let pairs = encrypt(2000, &[3233, 2773, 5183]);
+        assert!(matches!(hastad(3, &pairs[..2]),
+                         Err(Error::TooFewCiphertexts { e: 3, found: 2 })));
+        // 4087 = 61 * 67 shares 61 with 3233
+        let pairs = encrypt(2000, &[3233, 2773, 4087]);
+        assert!(matches!(hastad(3, &pairs), Err(Error::NotCoprime)));
+    }
+}
@@ -0,0 +1,14 @@
+extern crate clap;
+extern crate crypto_utils;
+extern crate num_bigint;
+extern crate num_integer;
+extern crate num_traits;
+extern crate thiserror;
+extern crate tracing;
+
+pub mod cli;
+pub mod error;
+pub mod fermat;
+pub mod hastad;
+mod parity;
+pub mod rsa;
@@ -0,0 +1,105 @@
+use crypto_utils::oracle::{Oracle, OracleError, Parity};
+use num_bigint::BigUint;
+use num_integer::Integer;
+use num_traits::Zero;
+use tracing::trace;
+
+use crate::error::Error;
+use crate::rsa;
+
+// Knows the private key and leaks the least significant bit of every
+// decryption
+pub struct LocalParityOracle {
+    n: BigUint,
+    d: BigUint,
+}
+
+impl LocalParityOracle {
+    pub fn new(n: &BigUint, d: &BigUint) -> LocalParityOracle {
+        LocalParityOracle { n: n.clone(), d: d.clone() }
+    }
+}
+
+impl Oracle for LocalParityOracle {
+    type Response = Parity;
+
+    fn query(&self, ciphertext: &[u8]) -> Result<Parity, OracleError> {
+        let c = BigUint::from_bytes_be(ciphertext);
+        if c >= self.n {
+            return Err(OracleError::Rejected("the ciphertext is not less than N".to_string()));
+        }
+        if c.modpow(&self.d, &self.n).is_odd() {
+            Ok(Parity::Odd)
+        } else {
+            Ok(Parity::Even)
+        }
+    }
+}
+
+// ceil(x / 2^i)
+fn ceil_shr(x: &BigUint, i: usize) -> BigUint {
+    let floor = x >> i;
+    if &floor << i == *x {
+        floor
+    } else {
+        floor + 1u32
+    }
+}
+
+// Recovers m from c = m^e mod N. Since N is odd, 2^(ie) c decrypts to
+// 2^i m mod N, which is odd exactly when 2^(i-1) m mod N wrapped around
+// on doubling, i.e. the ith bit of the binary fraction m/N is 1. Each
+// query halves the interval known to contain m, so it takes as many
+// queries as N has bits. Calls report with the number of queries made and
+// the bounds on m so far.
+pub fn parity_attack<F>(oracle: &dyn Oracle<Response = Parity>, n: &BigUint, e: &BigUint,
+                        c: &BigUint, mut report: F) -> Result<BigUint, Error>
+    where F: FnMut(usize, &BigUint, &BigUint)
+{
+    let k = rsa::modulus_len(n);
+    let two_e = BigUint::from(2u32).modpow(e, n);
+    let mut query = c.clone();
+    // The bits of m/N so far
+    let mut a = BigUint::zero();
+    for i in 1..=n.bits() {
+        query = query * &two_e % n;
+        a <<= 1;
+        let parity = oracle.query(&rsa::i2osp(&query, k))?;
+        trace!(query = i, ?parity, "Oracle answered");
+        if parity == Parity::Odd {
+            a += 1u32;
+        }
+        // m/N is in [a/2^i, (a + 1)/2^i)
+        let lo = ceil_shr(&(n * &a), i);
+        let hi = ceil_shr(&(n * (&a + 1u32)), i) - 1u32;
+        report(i, &lo, &hi);
+    }
+
+    let m = ceil_shr(&(n * &a), n.bits());
+    if m.modpow(e, n) != *c {
+        return Err(Error::Inconsistent);
+    }
+    Ok(m)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recovers_every_message() {
+        // The textbook key: N = 61 * 53
+        let (n, e, d) = (BigUint::from(3233u32), BigUint::from(17u32), BigUint::from(2753u32));
+        let oracle = LocalParityOracle::new(&n, &d);
+        // With every m < N come small ones, whose top bits are zero, as
+        // 5 = 0b000000000101 of N's 12 bits
+        for m in 0..3233u32 {
+            let m = BigUint::from(m);
+            let c = m.modpow(&e, &n);
+            let found = parity_attack(&oracle, &n, &e, &c, |_, lo, hi| {
+                assert!(*lo <= m && m <= *hi);
+            });
+            assert_eq!(found.unwrap(), m);
+        }
+    }
+}
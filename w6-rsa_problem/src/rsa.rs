@@ -0,0 +1,47 @@
+use crypto_utils::modular::mod_inverse;
+use num_bigint::BigUint;
+use num_traits::One;
+
+use crate::error::Error;
+
+// The decryption exponent d = e^-1 mod φ(N) for N = pq, if e is
+// invertible
+pub fn private_exponent(e: &BigUint, p: &BigUint, q: &BigUint) -> Option<BigUint> {
+    let one = BigUint::one();
+    let phi_n = (p - &one) * (q - &one);
+    mod_inverse(e, &phi_n)
+}
+
+// Length of N in bytes
+pub fn modulus_len(n: &BigUint) -> usize {
+    n.bits().div_ceil(8)
+}
+
+// x as k big-endian bytes, as RSA ciphertexts are encoded (I2OSP in
+// PKCS#1); x must fit
+pub fn i2osp(x: &BigUint, k: usize) -> Vec<u8> {
+    let bytes = x.to_bytes_be();
+    let mut block = vec![0u8; k - bytes.len()];
+    block.extend(bytes);
+    block
+}
+
+// The message in a PKCS#1 v1.5 encryption block 00 02 PS 00 M, for a
+// modulus of k bytes, with at least 8 nonzero bytes of PS
+pub fn pkcs1_v15_unpad(m: &BigUint, k: usize) -> Result<Vec<u8>, Error> {
+    if k < 11 {
+        return Err(Error::SmallModulus(k));
+    }
+    if m.bits() > 8 * (k - 1) {
+        return Err(Error::LeadingByte);
+    }
+    let block = i2osp(m, k);
+    if block[1] != 2 {
+        return Err(Error::BlockType(block[1]));
+    }
+    match block[2..].iter().position(|&b| b == 0) {
+        Some(len) if len >= 8 => Ok(block[3 + len..].to_vec()),
+        Some(len) => Err(Error::ShortPadding(len)),
+        None => Err(Error::UnterminatedPadding),
+    }
+}